@@ -0,0 +1,174 @@
+use ::AltoResult;
+use sys;
+use al::*;
+
+
+/// A source playing a B-Format buffer, with control over the rotation of its sound field.
+/// Requires `AL_EXT_BFORMAT`
+///
+/// The field can be given a fixed rotation, and can optionally follow the listener so that
+/// it counter-rotates as the listener turns. In that mode, call `sync` whenever the listener
+/// orientation may have changed, ideally inside the same `defer_updates` batch.
+pub struct AmbisonicSource {
+	src: StaticSource,
+	rotation: [[f32; 3]; 3],
+	follow: bool,
+	listener: Option<([f32; 3], [f32; 3])>,
+}
+
+
+impl AmbisonicSource {
+	/// Wrap a source that plays, or will play, a B-Format buffer.
+	/// The field starts unrotated and does not follow the listener.
+	pub fn new(src: StaticSource) -> AltoResult<AmbisonicSource> {
+		src.context().0.exts.AL_EXT_BFORMAT()?;
+		let mut amb = AmbisonicSource{src, rotation: IDENTITY, follow: false, listener: None};
+		amb.apply()?;
+		Ok(amb)
+	}
+
+
+	/// The wrapped source.
+	pub fn source(&self) -> &StaticSource { &self.src }
+	/// The wrapped source.
+	pub fn source_mut(&mut self) -> &mut StaticSource { &mut self.src }
+	/// Unwrap the source, leaving its current orientation in place.
+	pub fn into_inner(self) -> StaticSource { self.src }
+
+
+	/// Rotate the field by yaw, pitch and roll, in radians, applied in that order.
+	/// Positive yaw turns to the left about +Y, positive pitch turns upward about +X,
+	/// and positive roll turns counter-clockwise about the forward axis.
+	pub fn set_field_rotation(&mut self, yaw: f32, pitch: f32, roll: f32) -> AltoResult<()> {
+		self.rotation = ypr_matrix(yaw, pitch, roll);
+		self.apply()
+	}
+
+
+	/// Whether the field counter-rotates to follow the listener orientation.
+	pub fn follows_listener(&self) -> bool { self.follow }
+	/// Make the field counter-rotate to follow the listener orientation.
+	/// Since the listener can't be observed for changes, `sync` must be called to pick them up.
+	pub fn set_follow_listener(&mut self, value: bool) -> AltoResult<()> {
+		self.follow = value;
+		self.listener = None;
+		self.sync()
+	}
+
+
+	/// `alGetListenerfv(AL_ORIENTATION)`
+	/// If following the listener and its orientation has changed, update the field orientation to match.
+	pub fn sync(&mut self) -> AltoResult<()> {
+		if !self.follow {
+			return self.apply();
+		}
+
+		let listener = self.src.context().orientation::<[f32; 3]>();
+		if self.listener == Some(listener) {
+			return Ok(());
+		}
+
+		self.listener = Some(listener);
+		self.apply()
+	}
+
+
+	/// `alSourcefv(AL_ORIENTATION)`
+	fn apply(&mut self) -> AltoResult<()> {
+		let mut rot = self.rotation;
+		if let (true, Some((at, up))) = (self.follow, self.listener) {
+			rot = mat_mul(&transpose(&listener_matrix(at, up)), &rot);
+		}
+		let value = orientation_from_matrix(&rot);
+
+		let ctx = self.src.context();
+		let _lock = ctx.make_current(true);
+		unsafe { ctx.0.dev.0.alto.0.api.alSourcefv(self.src.as_raw(), sys::AL_ORIENTATION, &value as *const [[f32; 3]; 2] as *const sys::ALfloat); }
+		record!(ctx.0, r => Call::Sourcefv{src: r.source(self.src.as_raw()), param: sys::AL_ORIENTATION, values: value.iter().flat_map(|v| v.iter().cloned()).collect()});
+		ctx.get_error()
+	}
+}
+
+
+const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+
+/// Compute the B-Format source orientation that keeps a sound field fixed in the world
+/// while the listener faces along `at` with `up` overhead.
+/// Returns the `(at, up)` pair for `AL_ORIENTATION`, which is the inverse of the listener rotation
+/// applied to the default orientation of `-Z` forward and `+Y` up.
+pub fn field_counter_orientation(at: [f32; 3], up: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+	let rot = transpose(&listener_matrix(at, up));
+	let value = orientation_from_matrix(&rot);
+	(value[0], value[1])
+}
+
+
+/// Rotation taking the default orientation to the listener's, as columns of right, up and back.
+fn listener_matrix(at: [f32; 3], up: [f32; 3]) -> [[f32; 3]; 3] {
+	let back = normalize([-at[0], -at[1], -at[2]]);
+	let right = normalize(cross(up, back));
+	let up = cross(back, right);
+
+	[
+		[right[0], up[0], back[0]],
+		[right[1], up[1], back[1]],
+		[right[2], up[2], back[2]],
+	]
+}
+
+
+fn ypr_matrix(yaw: f32, pitch: f32, roll: f32) -> [[f32; 3]; 3] {
+	let (sy, cy) = yaw.sin_cos();
+	let (sp, cp) = pitch.sin_cos();
+	let (sr, cr) = roll.sin_cos();
+
+	let yaw = [[cy, 0.0, sy], [0.0, 1.0, 0.0], [-sy, 0.0, cy]];
+	let pitch = [[1.0, 0.0, 0.0], [0.0, cp, -sp], [0.0, sp, cp]];
+	let roll = [[cr, -sr, 0.0], [sr, cr, 0.0], [0.0, 0.0, 1.0]];
+	mat_mul(&mat_mul(&yaw, &pitch), &roll)
+}
+
+
+/// The images of `-Z` and `+Y` under `rot`.
+fn orientation_from_matrix(rot: &[[f32; 3]; 3]) -> [[f32; 3]; 2] {
+	[
+		[-rot[0][2], -rot[1][2], -rot[2][2]],
+		[rot[0][1], rot[1][1], rot[2][1]],
+	]
+}
+
+
+fn mat_mul(a: &[[f32; 3]; 3], b: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+	let mut out = [[0.0; 3]; 3];
+	for (i, row) in out.iter_mut().enumerate() {
+		for (j, v) in row.iter_mut().enumerate() {
+			*v = (0 .. 3).map(|k| a[i][k] * b[k][j]).sum();
+		}
+	}
+	out
+}
+
+
+fn transpose(m: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+	[
+		[m[0][0], m[1][0], m[2][0]],
+		[m[0][1], m[1][1], m[2][1]],
+		[m[0][2], m[1][2], m[2][2]],
+	]
+}
+
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+	[
+		a[1] * b[2] - a[2] * b[1],
+		a[2] * b[0] - a[0] * b[2],
+		a[0] * b[1] - a[1] * b[0],
+	]
+}
+
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+	let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+	if len == 0.0 { v } else { [v[0] / len, v[1] / len, v[2] / len] }
+}
@@ -0,0 +1,170 @@
+use std::f32;
+
+use al::*;
+
+
+/// The source parameters that determine its gain at a listener position.
+/// Defaults match a freshly generated source.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SourceProps {
+	/// `AL_POSITION`
+	pub position: [f32; 3],
+	/// `AL_SOURCE_RELATIVE`
+	/// A relative source is positioned relative to the listener, so the listener position is ignored.
+	pub relative: bool,
+	/// `AL_GAIN`
+	pub gain: f32,
+	/// `AL_MIN_GAIN`
+	pub min_gain: f32,
+	/// `AL_MAX_GAIN`
+	pub max_gain: f32,
+	/// `AL_REFERENCE_DISTANCE`
+	pub reference_distance: f32,
+	/// `AL_ROLLOFF_FACTOR`
+	pub rolloff_factor: f32,
+	/// `AL_MAX_DISTANCE`
+	pub max_distance: f32,
+	/// `AL_DIRECTION`
+	/// A zero vector makes the source omnidirectional.
+	pub direction: [f32; 3],
+	/// `AL_CONE_INNER_ANGLE`
+	pub cone_inner_angle: f32,
+	/// `AL_CONE_OUTER_ANGLE`
+	pub cone_outer_angle: f32,
+	/// `AL_CONE_OUTER_GAIN`
+	pub cone_outer_gain: f32,
+	/// Per-source distance model, used instead of the context's when set.
+	/// Requires `AL_EXT_source_distance_model` to match what the mixer does.
+	pub distance_model: Option<DistanceModel>,
+}
+
+
+impl Default for SourceProps {
+	fn default() -> SourceProps {
+		SourceProps{
+			position: [0.0, 0.0, 0.0],
+			relative: false,
+			gain: 1.0,
+			min_gain: 0.0,
+			max_gain: 1.0,
+			reference_distance: 1.0,
+			rolloff_factor: 1.0,
+			max_distance: f32::MAX,
+			direction: [0.0, 0.0, 0.0],
+			cone_inner_angle: 360.0,
+			cone_outer_angle: 360.0,
+			cone_outer_gain: 0.0,
+			distance_model: None,
+		}
+	}
+}
+
+
+impl SourceProps {
+	/// Read the current properties of a source.
+	/// The distance model is only captured if the context is using per-source distance models.
+	pub fn from_source<S: Source>(src: &S) -> SourceProps {
+		SourceProps{
+			position: src.position(),
+			relative: src.relative(),
+			gain: src.gain(),
+			min_gain: src.min_gain(),
+			max_gain: src.max_gain(),
+			reference_distance: src.reference_distance(),
+			rolloff_factor: src.rolloff_factor(),
+			max_distance: src.max_distance(),
+			direction: src.direction(),
+			cone_inner_angle: src.cone_inner_angle(),
+			cone_outer_angle: src.cone_outer_angle(),
+			cone_outer_gain: src.cone_outer_gain(),
+			distance_model: if src.context().using_source_distance_model() { Some(src.distance_model()) } else { None },
+		}
+	}
+}
+
+
+/// Gain from distance alone, per the OpenAL 1.1 formula for `model`.
+/// Degenerate parameters, such as a zero reference distance, give a gain of 1 as they do in OpenAL Soft.
+pub fn distance_gain(model: DistanceModel, distance: f32, reference: f32, rolloff: f32, max_distance: f32) -> f32 {
+	let distance = match model {
+		DistanceModel::InverseClamped | DistanceModel::LinearClamped | DistanceModel::ExponentClamped => {
+			// A max distance below the reference distance disables the clamped models.
+			if max_distance < reference {
+				return 1.0;
+			}
+			distance.clamp(reference, max_distance)
+		},
+		_ => distance,
+	};
+
+	match model {
+		DistanceModel::Inverse | DistanceModel::InverseClamped => {
+			let denom = reference + rolloff * (distance - reference);
+			if reference > 0.0 && denom > 0.0 { reference / denom } else { 1.0 }
+		},
+		DistanceModel::Linear | DistanceModel::LinearClamped => {
+			if max_distance != reference {
+				(1.0 - rolloff * (distance.min(max_distance) - reference) / (max_distance - reference)).max(0.0)
+			} else {
+				1.0
+			}
+		},
+		DistanceModel::Exponent | DistanceModel::ExponentClamped => {
+			if distance > 0.0 && reference > 0.0 { (distance / reference).powf(-rolloff) } else { 1.0 }
+		},
+		DistanceModel::None | DistanceModel::Unknown(..) => 1.0,
+	}
+}
+
+
+/// Gain from the source's sound cone, given the vector from the source to the listener.
+pub fn cone_gain(props: &SourceProps, to_listener: [f32; 3]) -> f32 {
+	let dir_len = length(props.direction);
+	let to_len = length(to_listener);
+	if dir_len == 0.0 || to_len == 0.0 {
+		return 1.0;
+	}
+
+	let cos = dot(props.direction, to_listener) / (dir_len * to_len);
+	let angle = cos.clamp(-1.0, 1.0).acos().to_degrees() * 2.0;
+	if angle <= props.cone_inner_angle {
+		1.0
+	} else if angle >= props.cone_outer_angle {
+		props.cone_outer_gain
+	} else {
+		let t = (angle - props.cone_inner_angle) / (props.cone_outer_angle - props.cone_inner_angle);
+		1.0 + (props.cone_outer_gain - 1.0) * t
+	}
+}
+
+
+/// Gain of a source as heard at `listener_pos` under `model`, before the listener gain is applied.
+/// Combines source gain, distance and cone attenuation, then clamps to the source's min and max gain.
+/// The source's own distance model, if set, takes precedence over `model`.
+pub fn attenuation(model: DistanceModel, props: &SourceProps, listener_pos: [f32; 3]) -> f32 {
+	let to_listener = if props.relative {
+		[-props.position[0], -props.position[1], -props.position[2]]
+	} else {
+		[listener_pos[0] - props.position[0], listener_pos[1] - props.position[1], listener_pos[2] - props.position[2]]
+	};
+
+	let model = props.distance_model.unwrap_or(model);
+	let gain = props.gain
+		* distance_gain(model, length(to_listener), props.reference_distance, props.rolloff_factor, props.max_distance)
+		* cone_gain(props, to_listener);
+	gain.max(props.min_gain).min(props.max_gain)
+}
+
+
+/// Listener relative position that pans a source by `pan`, from -1 for hard left to 1 for hard right, as used by `Source::set_pan`.
+/// The source is placed in front of the listener on the unit circle, at `pan` times 90 degrees, so its distance never changes
+/// and its x coordinate follows the constant power curve `sin(pan * pi / 2)`. Out of range values are clamped, and NaN is centered.
+pub fn pan_position(pan: f32) -> [f32; 3] {
+	let pan = if pan.is_nan() { 0.0 } else { pan.clamp(-1.0, 1.0) };
+	let angle = pan * f32::consts::FRAC_PI_2;
+	[angle.sin(), 0.0, -angle.cos()]
+}
+
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 { a[0] * b[0] + a[1] * b[1] + a[2] * b[2] }
+fn length(v: [f32; 3]) -> f32 { dot(v, v).sqrt() }
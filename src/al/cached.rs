@@ -0,0 +1,60 @@
+use std::ops::{Deref, DerefMut};
+
+use AltoResult;
+use al::*;
+
+
+/// A source that keeps a copy of its gain and pitch, so that they can be read every frame without calling into OpenAL.
+/// Wrapping a source opts into the cache; every other `Source` method is reached through `Deref`.
+/// The cache is write-through: `set_gain` and `set_pitch` update OpenAL, then the cached value if that succeeded.
+/// Changes made any other way, such as through the raw API or through `Source::set_gain` on the inner source, bypass the cache.
+pub struct CachedSource<S: Source> {
+	src: S,
+	gain: f32,
+	pitch: f32,
+}
+
+
+impl<S: Source> CachedSource<S> {
+	/// Wrap `src`, reading its current gain and pitch once to seed the cache.
+	pub fn new(src: S) -> CachedSource<S> {
+		let (gain, pitch) = (src.gain(), src.pitch());
+		CachedSource{src, gain, pitch}
+	}
+
+
+	/// Unwrap the source, discarding the cache.
+	pub fn into_inner(self) -> S { self.src }
+
+
+	/// `alSourcef(AL_GAIN)`
+	pub fn set_gain(&mut self, value: f32) -> AltoResult<()> {
+		self.src.set_gain(value)?;
+		self.gain = value;
+		Ok(())
+	}
+	/// The gain last set through `set_gain`, read without calling into OpenAL.
+	pub fn cached_gain(&self) -> f32 { self.gain }
+
+
+	/// `alSourcef(AL_PITCH)`
+	pub fn set_pitch(&mut self, value: f32) -> AltoResult<()> {
+		self.src.set_pitch(value)?;
+		self.pitch = value;
+		Ok(())
+	}
+	/// The pitch last set through `set_pitch`, read without calling into OpenAL.
+	pub fn cached_pitch(&self) -> f32 { self.pitch }
+}
+
+
+impl<S: Source> Deref for CachedSource<S> {
+	type Target = S;
+
+	fn deref(&self) -> &S { &self.src }
+}
+
+
+impl<S: Source> DerefMut for CachedSource<S> {
+	fn deref_mut(&mut self) -> &mut S { &mut self.src }
+}
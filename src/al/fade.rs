@@ -0,0 +1,86 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use AltoResult;
+use al::*;
+
+
+/// A fade started by `Source::fade_out`, advanced by the application with `tick`.
+/// Once the fade completes, the source is stopped and its gain restored to what it was when the fade began,
+/// so that it can be played again at its usual volume.
+/// The handle does not keep the source alive; if the source is dropped first, the fade simply counts as finished.
+pub struct FadeHandle {
+	src: Weak<SourceInner>,
+	start_gain: f32,
+	duration: Duration,
+	elapsed: Duration,
+	finished: bool,
+}
+
+
+/// Gain of a linear fade from `start_gain` to silence lasting `duration`, after `elapsed` has passed.
+/// A zero duration is silent straight away.
+pub fn fade_gain(start_gain: f32, elapsed: Duration, duration: Duration) -> f32 {
+	if elapsed >= duration {
+		return 0.0;
+	}
+	let t = elapsed.as_secs_f64() / duration.as_secs_f64();
+	(start_gain as f64 * (1.0 - t)) as f32
+}
+
+
+impl FadeHandle {
+	pub(crate) fn new(src: &Arc<SourceInner>, duration: Duration) -> AltoResult<FadeHandle> {
+		let start_gain = src.gain();
+		src.ctx.get_error()?;
+		Ok(FadeHandle{
+			src: Arc::downgrade(src),
+			start_gain,
+			duration,
+			elapsed: Duration::from_secs(0),
+			finished: false,
+		})
+	}
+
+
+	/// Advance the fade by `elapsed` and lower the source gain accordingly.
+	/// When the fade completes, the source is stopped. Returns whether the fade has finished.
+	pub fn tick(&mut self, elapsed: Duration) -> AltoResult<bool> {
+		if self.finished {
+			return Ok(true);
+		}
+		let src = match self.src.upgrade() {
+			Some(src) => src,
+			None => {
+				self.finished = true;
+				return Ok(true);
+			},
+		};
+
+		self.elapsed += elapsed;
+		if self.elapsed < self.duration {
+			src.set_gain(fade_gain(self.start_gain, self.elapsed, self.duration))?;
+			return Ok(false);
+		}
+
+		src.set_gain(0.0)?;
+		src.stop();
+		self.finished = true;
+		src.set_gain(self.start_gain)?;
+		Ok(true)
+	}
+
+
+	/// Whether the fade has completed and the source has been stopped.
+	pub fn is_finished(&self) -> bool { self.finished }
+	/// Time the fade has been running, as accumulated by `tick`.
+	pub fn elapsed(&self) -> Duration { self.elapsed }
+	/// Total length of the fade.
+	pub fn duration(&self) -> Duration { self.duration }
+	/// Gain of the source when the fade began.
+	pub fn start_gain(&self) -> f32 { self.start_gain }
+	/// Gain the source is currently faded to.
+	pub fn gain(&self) -> f32 {
+		if self.finished { 0.0 } else { fade_gain(self.start_gain, self.elapsed, self.duration) }
+	}
+}
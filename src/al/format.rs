@@ -8,7 +8,9 @@ use ext;
 
 
 /// Audio formats supported by OpenAL.
+/// More variants may be added as extensions are supported, so matches outside of Alto need a wildcard arm.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
 pub enum Format {
 	Standard(StandardFormat),
 	ExtALaw(ExtALawFormat),
@@ -21,6 +23,7 @@ pub enum Format {
 	ExtMuLawBFormat(ExtMuLawBFormat),
 	ExtMuLawMcFormats(ExtMuLawMcFormat),
 	SoftMsadpcm(SoftMsadpcmFormat),
+	SoftByte3(SoftByte3Format),
 }
 
 
@@ -182,6 +185,18 @@ pub enum SoftMsadpcmFormat {
 }
 
 
+/// Packed 24-bit formats provided by `AL_SOFT_buffer_samples`.
+/// There is no `alBufferData()` format for these, so they are uploaded with `alBufferSamplesSOFT()`
+/// as `AL_BYTE3_SOFT` and stored as 32-bit float.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SoftByte3Format {
+	/// `AL_BYTE3_SOFT` samples in `AL_MONO_SOFT`
+	Mono24,
+	/// `AL_BYTE3_SOFT` samples in `AL_STEREO_SOFT`
+	Stereo24,
+}
+
+
 /// Implemented by structs that represent a frame of audio samples.
 /// A sample frame is a grouping of audio samples from each channel
 /// of an output format.
@@ -221,6 +236,10 @@ pub struct ALawSample(pub u8);
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[repr(C)]
 pub struct MuLawSample(pub u8);
+/// A signed 24-bit sample packed into 3 little-endian bytes.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[repr(C)]
+pub struct Int24Sample(pub [u8; 3]);
 
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
@@ -314,6 +333,8 @@ pub struct BFormat3D<S: Copy> {
 
 
 impl Format {
+	/// The format token passed to `alBufferData()`.
+	/// Packed 24-bit formats have none, as they are uploaded with `alBufferSamplesSOFT()`, and are an `InvalidValue`.
 	pub fn into_raw(self, ctx: Option<&Context>) -> AltoResult<sys::ALint> {
 		match self {
 			Format::Standard(f) => Ok(f.into_raw()),
@@ -327,8 +348,86 @@ impl Format {
 			Format::ExtMuLawBFormat(f) => f.into_raw(ctx),
 			Format::ExtMuLawMcFormats(f) => f.into_raw(ctx),
 			Format::SoftMsadpcm(f) => f.into_raw(ctx),
+			Format::SoftByte3(_) => Err(AltoError::InvalidValue),
+		}
+	}
+
+
+	/// Size in bytes of one frame of this format, or `None` for block compressed formats
+	/// whose frames don't occupy a whole number of bytes.
+	pub fn frame_bytes(self) -> Option<usize> {
+		let (channels, bytes) = match self {
+			Format::Standard(StandardFormat::MonoU8) => (1, 1),
+			Format::Standard(StandardFormat::MonoI16) => (1, 2),
+			Format::Standard(StandardFormat::StereoU8) => (2, 1),
+			Format::Standard(StandardFormat::StereoI16) => (2, 2),
+			Format::ExtALaw(ExtALawFormat::Mono) => (1, 1),
+			Format::ExtALaw(ExtALawFormat::Stereo) => (2, 1),
+			Format::ExtBFormat(ExtBFormat::B2DU8) => (3, 1),
+			Format::ExtBFormat(ExtBFormat::B2DI16) => (3, 2),
+			Format::ExtBFormat(ExtBFormat::B2DF32) => (3, 4),
+			Format::ExtBFormat(ExtBFormat::B3DU8) => (4, 1),
+			Format::ExtBFormat(ExtBFormat::B3DI16) => (4, 2),
+			Format::ExtBFormat(ExtBFormat::B3DF32) => (4, 4),
+			Format::ExtDouble(ExtDoubleFormat::Mono) => (1, 8),
+			Format::ExtDouble(ExtDoubleFormat::Stereo) => (2, 8),
+			Format::ExtFloat32(ExtFloat32Format::Mono) => (1, 4),
+			Format::ExtFloat32(ExtFloat32Format::Stereo) => (2, 4),
+			Format::ExtIma4(_) => return None,
+			Format::ExtMcFormats(f) => match f {
+				ExtMcFormat::QuadU8 => (4, 1),
+				ExtMcFormat::QuadI16 => (4, 2),
+				ExtMcFormat::QuadF32 => (4, 4),
+				ExtMcFormat::RearU8 => (1, 1),
+				ExtMcFormat::RearI16 => (1, 2),
+				ExtMcFormat::RearF32 => (1, 4),
+				ExtMcFormat::Mc51ChnU8 => (6, 1),
+				ExtMcFormat::Mc51ChnI16 => (6, 2),
+				ExtMcFormat::Mc51ChnF32 => (6, 4),
+				ExtMcFormat::Mc61ChnU8 => (7, 1),
+				ExtMcFormat::Mc61ChnI16 => (7, 2),
+				ExtMcFormat::Mc61ChnF32 => (7, 4),
+				ExtMcFormat::Mc71ChnU8 => (8, 1),
+				ExtMcFormat::Mc71ChnI16 => (8, 2),
+				ExtMcFormat::Mc71ChnF32 => (8, 4),
+			},
+			Format::ExtMuLaw(ExtMuLawFormat::Mono) => (1, 1),
+			Format::ExtMuLaw(ExtMuLawFormat::Stereo) => (2, 1),
+			Format::ExtMuLawBFormat(ExtMuLawBFormat::B2D) => (3, 1),
+			Format::ExtMuLawBFormat(ExtMuLawBFormat::B3D) => (4, 1),
+			Format::ExtMuLawMcFormats(f) => match f {
+				ExtMuLawMcFormat::Mono => (1, 1),
+				ExtMuLawMcFormat::Stereo => (2, 1),
+				ExtMuLawMcFormat::Quad => (4, 1),
+				ExtMuLawMcFormat::Rear => (1, 1),
+				ExtMuLawMcFormat::Mc51Chn => (6, 1),
+				ExtMuLawMcFormat::Mc61Chn => (7, 1),
+				ExtMuLawMcFormat::Mc71Chn => (8, 1),
+			},
+			Format::SoftMsadpcm(_) => return None,
+			Format::SoftByte3(SoftByte3Format::Mono24) => (1, 3),
+			Format::SoftByte3(SoftByte3Format::Stereo24) => (2, 3),
+		};
+		Some(channels * bytes)
+	}
+
+
+	/// Number of whole frames in `byte_len` bytes of this format.
+	/// Lengths that end partway through a frame, and block compressed formats, are an `InvalidValue`.
+	pub fn frames_in(self, byte_len: usize) -> AltoResult<usize> {
+		match self.frame_bytes() {
+			Some(frame_bytes) if byte_len.is_multiple_of(frame_bytes) => Ok(byte_len / frame_bytes),
+			_ => Err(AltoError::InvalidValue),
 		}
 	}
+
+
+	/// Whether a capture device can be opened in this format.
+	/// Only the standard PCM formats qualify. Every other format, float included, is an extension
+	/// whose token can only be resolved through a context, which capture devices do not have.
+	pub fn is_capture_compatible(&self) -> bool {
+		matches!(*self, Format::Standard(_))
+	}
 }
 
 
@@ -466,6 +565,41 @@ impl SoftMsadpcmFormat {
 }
 
 
+impl SoftByte3Format {
+	/// The internal format the samples are stored in, as passed to `alBufferSamplesSOFT()`.
+	pub(crate) fn internal_raw(self, ctx: &Context) -> AltoResult<sys::ALint> {
+		match self {
+			SoftByte3Format::Mono24 => Ok(ctx.0.exts.AL_SOFT_buffer_samples()?.AL_MONO32F_SOFT?),
+			SoftByte3Format::Stereo24 => Ok(ctx.0.exts.AL_SOFT_buffer_samples()?.AL_STEREO32F_SOFT?),
+		}
+	}
+
+
+	/// The `alBufferSamplesSOFT()` channel configuration.
+	pub(crate) fn channels_raw(self, ctx: &Context) -> AltoResult<sys::ALint> {
+		match self {
+			SoftByte3Format::Mono24 => Ok(ctx.0.exts.AL_SOFT_buffer_samples()?.AL_MONO_SOFT?),
+			SoftByte3Format::Stereo24 => Ok(ctx.0.exts.AL_SOFT_buffer_samples()?.AL_STEREO_SOFT?),
+		}
+	}
+}
+
+
+impl Int24Sample {
+	/// Pack the low 24 bits of `value`.
+	pub fn new(value: i32) -> Int24Sample {
+		let b = value.to_le_bytes();
+		Int24Sample([b[0], b[1], b[2]])
+	}
+
+
+	/// The sample value, sign extended.
+	pub fn value(self) -> i32 {
+		i32::from_le_bytes([0, self.0[0], self.0[1], self.0[2]]) >> 8
+	}
+}
+
+
 unsafe impl SampleFrame for Mono<u8> {
 	type Sample = u8;
 
@@ -502,6 +636,12 @@ unsafe impl SampleFrame for Mono<MuLawSample> {
 	#[inline] fn len() -> usize { 1 }
 	#[inline] fn format() -> Format { Format::ExtMuLaw(ExtMuLawFormat::Mono) }
 }
+unsafe impl SampleFrame for Mono<Int24Sample> {
+	type Sample = Int24Sample;
+
+	#[inline] fn len() -> usize { 1 }
+	#[inline] fn format() -> Format { Format::SoftByte3(SoftByte3Format::Mono24) }
+}
 
 
 unsafe impl SampleFrame for Stereo<u8> {
@@ -540,6 +680,12 @@ unsafe impl SampleFrame for Stereo<MuLawSample> {
 	#[inline] fn len() -> usize { 2 }
 	#[inline] fn format() -> Format { Format::ExtMuLaw(ExtMuLawFormat::Stereo) }
 }
+unsafe impl SampleFrame for Stereo<Int24Sample> {
+	type Sample = Int24Sample;
+
+	#[inline] fn len() -> usize { 2 }
+	#[inline] fn format() -> Format { Format::SoftByte3(SoftByte3Format::Stereo24) }
+}
 
 
 unsafe impl SampleFrame for McRear<u8> {
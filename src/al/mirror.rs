@@ -0,0 +1,334 @@
+use std::sync::{Arc, Weak};
+use parking_lot::Mutex;
+
+use ::AltoResult;
+use alc::*;
+use al::*;
+
+
+/// The largest pitch correction that drift correction will apply to the secondary device,
+/// as a fraction of a source's pitch.
+pub const MAX_DRIFT_NUDGE: f32 = 0.005;
+/// How long drift correction takes to work off most of an offset between the devices, in nanoseconds.
+pub const DRIFT_CORRECTION_WINDOW: i64 = 2_000_000_000;
+
+
+/// Plays the same audio on two output devices at once.
+/// Buffers and sources are created as twin pairs, one in a context on each device,
+/// and every property change or playback command is applied to both.
+///
+/// If both devices support `ALC_SOFT_device_clock`, calling `correct_drift` periodically
+/// will nudge the pitch of the secondary's sources to keep it in step with the primary,
+/// both matching the rate of the primary's clock and working off any offset that has built up.
+/// Otherwise the devices are mirrored without correction, as reported by `is_drift_corrected`.
+pub struct MirrorOutput {
+	primary: Context,
+	secondary: Context,
+	clock_base: Option<(i64, i64)>,
+	drift: Mutex<DriftTracker>,
+	nudge: Arc<Mutex<f32>>,
+	sources: Mutex<Vec<MirrorEntry>>,
+}
+
+
+#[derive(Default)]
+struct DriftTracker {
+	/// Clock readings at the last correction, relative to the clock base.
+	last: (i64, i64),
+	/// How far the secondary's audio has got ahead of the primary's, in nanoseconds.
+	offset: f64,
+	nudge: f32,
+}
+
+
+struct MirrorEntry {
+	src: Weak<SourceInner>,
+	pitch: Weak<Mutex<f32>>,
+}
+
+
+/// A pair of buffers holding the same data, one on each device of a `MirrorOutput`.
+pub struct MirrorBuffer {
+	primary: Arc<Buffer>,
+	secondary: Arc<Buffer>,
+}
+
+
+/// A pair of static sources, one on each device of a `MirrorOutput`.
+pub struct MirrorSource {
+	primary: StaticSource,
+	secondary: StaticSource,
+	pitch: Arc<Mutex<f32>>,
+	nudge: Arc<Mutex<f32>>,
+}
+
+
+impl MirrorOutput {
+	/// Create a context on each device and begin mirroring.
+	pub fn new(primary: OutputDevice, secondary: OutputDevice) -> AltoResult<MirrorOutput> {
+		let primary = primary.new_context(None)?;
+		let secondary = secondary.new_context(None)?;
+
+		let clock_base = match (primary.0.dev.0.soft_device_clock(), secondary.0.dev.0.soft_device_clock()) {
+			(Ok(p), Ok(s)) => Some((p, s)),
+			_ => None,
+		};
+
+		Ok(MirrorOutput{
+			primary,
+			secondary,
+			clock_base,
+			drift: Mutex::new(DriftTracker::default()),
+			nudge: Arc::new(Mutex::new(0.0)),
+			sources: Mutex::new(Vec::new()),
+		})
+	}
+
+
+	/// Context on the primary device.
+	pub fn primary(&self) -> &Context { &self.primary }
+	/// Context on the secondary device.
+	pub fn secondary(&self) -> &Context { &self.secondary }
+
+
+	/// Whether both devices expose a clock that drift correction can use.
+	/// Requires `ALC_SOFT_device_clock`
+	pub fn is_drift_corrected(&self) -> bool { self.clock_base.is_some() }
+	/// The fraction by which the pitch of the secondary's sources is currently scaled.
+	pub fn drift_nudge(&self) -> f32 { *self.nudge.lock() }
+	/// How far the secondary's audio is estimated to be ahead of the primary's, in nanoseconds, as of the last correction.
+	pub fn drift_offset(&self) -> f64 { self.drift.lock().offset }
+
+
+	/// `alGenBuffers()`
+	/// Creates a buffer on each device holding the same data.
+	pub fn new_buffer<F: SampleFrame, B: AsBufferData<F>>(&self, data: B, freq: i32) -> AltoResult<MirrorBuffer> {
+		Ok(MirrorBuffer{
+			primary: Arc::new(self.primary.new_buffer(&data, freq)?),
+			secondary: Arc::new(self.secondary.new_buffer(&data, freq)?),
+		})
+	}
+
+
+	/// `alGenSources()`
+	/// Creates a static source on each device.
+	pub fn new_source(&self) -> AltoResult<MirrorSource> {
+		let primary = self.primary.new_static_source()?;
+		let mut secondary = self.secondary.new_static_source()?;
+		let nudge = *self.nudge.lock();
+		secondary.set_pitch(1.0 + nudge)?;
+
+		let pitch = Arc::new(Mutex::new(1.0));
+		self.sources.lock().push(MirrorEntry{src: Arc::downgrade(&secondary.src), pitch: Arc::downgrade(&pitch)});
+
+		Ok(MirrorSource{primary, secondary, pitch, nudge: self.nudge.clone()})
+	}
+
+
+	/// `alcGetInteger64vSOFT(ALC_DEVICE_CLOCK_SOFT)`
+	/// Reads both device clocks and adjusts the pitch of the secondary's sources to compensate for drift.
+	/// Returns the nudge now in effect, which is always zero if the devices lack a usable clock.
+	/// Requires `ALC_SOFT_device_clock`
+	pub fn correct_drift(&self) -> AltoResult<f32> {
+		if self.clock_base.is_none() {
+			return Ok(0.0);
+		}
+
+		let primary = self.primary.0.dev.0.soft_device_clock()?;
+		let secondary = self.secondary.0.dev.0.soft_device_clock()?;
+		self.apply_clock_readings(primary, secondary)
+	}
+
+
+	/// Adjust the pitch of the secondary's sources from explicit device clock readings, in nanoseconds.
+	/// This is what `correct_drift` uses internally, and is exposed for callers that track the
+	/// device clocks through other means, such as counting rendered samples on loopback devices.
+	/// The nudge combines `drift_pitch_nudge` with `drift_offset_nudge` of the offset built up since the previous readings.
+	pub fn apply_clock_readings(&self, primary: i64, secondary: i64) -> AltoResult<f32> {
+		let (base_p, base_s) = self.clock_base.unwrap_or((0, 0));
+		let nudge = self.drift.lock().update(primary - base_p, secondary - base_s);
+		*self.nudge.lock() = nudge;
+
+		let mut sources = self.sources.lock();
+		sources.retain(|e| e.src.upgrade().is_some() && e.pitch.upgrade().is_some());
+		for entry in sources.iter() {
+			if let (Some(src), Some(pitch)) = (entry.src.upgrade(), entry.pitch.upgrade()) {
+				let pitch = *pitch.lock();
+				src.set_pitch(pitch * (1.0 + nudge))?;
+			}
+		}
+
+		Ok(nudge)
+	}
+}
+
+
+impl DriftTracker {
+	/// Account for the audio each device has played since the last readings, then compute the nudge to apply from now on.
+	fn update(&mut self, primary: i64, secondary: i64) -> f32 {
+		let (dp, ds) = (primary - self.last.0, secondary - self.last.1);
+		self.offset += ds as f64 * (1.0 + f64::from(self.nudge)) - dp as f64;
+		self.last = (primary, secondary);
+
+		let nudge = drift_pitch_nudge(primary, secondary) + drift_offset_nudge(self.offset as i64);
+		self.nudge = nudge.clamp(-MAX_DRIFT_NUDGE, MAX_DRIFT_NUDGE);
+		self.nudge
+	}
+}
+
+
+impl MirrorBuffer {
+	/// Buffer on the primary device.
+	pub fn primary(&self) -> &Arc<Buffer> { &self.primary }
+	/// Buffer on the secondary device.
+	pub fn secondary(&self) -> &Arc<Buffer> { &self.secondary }
+}
+
+
+impl MirrorSource {
+	/// Source on the primary device.
+	pub fn primary(&self) -> &StaticSource { &self.primary }
+	/// Source on the secondary device.
+	pub fn secondary(&self) -> &StaticSource { &self.secondary }
+
+
+	/// Apply an arbitrary operation to both sources, stopping at the first error.
+	pub fn apply<O: FnMut(&mut StaticSource) -> AltoResult<()>>(&mut self, mut op: O) -> AltoResult<()> {
+		op(&mut self.primary)?;
+		op(&mut self.secondary)
+	}
+
+
+	/// `alSourcei(AL_BUFFER)`
+	pub fn set_buffer(&mut self, buf: &MirrorBuffer) -> AltoResult<()> {
+		self.primary.set_buffer(buf.primary.clone())?;
+		self.secondary.set_buffer(buf.secondary.clone())
+	}
+	/// `alSourcei(AL_BUFFER)`
+	pub fn clear_buffer(&mut self) {
+		self.primary.clear_buffer();
+		self.secondary.clear_buffer();
+	}
+
+
+	/// `alGetSourcei(AL_SOURCE_STATE)`
+	/// Reports the state of the primary source.
+	pub fn state(&self) -> SourceState { self.primary.state() }
+	/// `alSourcePlay()`
+	pub fn play(&mut self) {
+		self.primary.play();
+		self.secondary.play();
+	}
+	/// `alSourcePause()`
+	pub fn pause(&mut self) {
+		self.primary.pause();
+		self.secondary.pause();
+	}
+	/// `alSourceStop()`
+	pub fn stop(&mut self) {
+		self.primary.stop();
+		self.secondary.stop();
+	}
+	/// `alSourceRewind()`
+	pub fn rewind(&mut self) {
+		self.primary.rewind();
+		self.secondary.rewind();
+	}
+
+
+	/// `alGetSourcei(AL_LOOPING)`
+	pub fn looping(&self) -> bool { self.primary.looping() }
+	/// `alSourcei(AL_LOOPING)`
+	pub fn set_looping(&mut self, value: bool) {
+		self.primary.set_looping(value);
+		self.secondary.set_looping(value);
+	}
+
+
+	/// `alGetSourcef(AL_GAIN)`
+	pub fn gain(&self) -> f32 { self.primary.gain() }
+	/// `alSourcef(AL_GAIN)`
+	pub fn set_gain(&mut self, value: f32) -> AltoResult<()> {
+		self.apply(|s| s.set_gain(value))
+	}
+
+
+	/// `alGetSourcef(AL_PITCH)`
+	/// Reports the pitch as set, without any drift correction.
+	pub fn pitch(&self) -> f32 { *self.pitch.lock() }
+	/// `alSourcef(AL_PITCH)`
+	/// The secondary source additionally receives the current drift correction.
+	pub fn set_pitch(&mut self, value: f32) -> AltoResult<()> {
+		self.primary.set_pitch(value)?;
+		let nudge = *self.nudge.lock();
+		self.secondary.set_pitch(value * (1.0 + nudge))?;
+		*self.pitch.lock() = value;
+		Ok(())
+	}
+
+
+	/// `alGetSourcefv(AL_POSITION)`
+	pub fn position<V: From<[f32; 3]>>(&self) -> V { self.primary.position() }
+	/// `alSourcefv(AL_POSITION)`
+	pub fn set_position<V: Into<[f32; 3]>>(&mut self, value: V) -> AltoResult<()> {
+		let value = value.into();
+		self.apply(|s| s.set_position(value))
+	}
+
+
+	/// `alGetSourcefv(AL_VELOCITY)`
+	pub fn velocity<V: From<[f32; 3]>>(&self) -> V { self.primary.velocity() }
+	/// `alSourcefv(AL_VELOCITY)`
+	pub fn set_velocity<V: Into<[f32; 3]>>(&mut self, value: V) -> AltoResult<()> {
+		let value = value.into();
+		self.apply(|s| s.set_velocity(value))
+	}
+}
+
+
+/// Compute the pitch correction for the secondary device of a `MirrorOutput`.
+/// `primary` and `secondary` are the nanoseconds each device clock has advanced since mirroring began.
+/// A secondary clock that runs fast yields a negative nudge, slowing its sources down, and vice versa.
+/// The result is clamped to `MAX_DRIFT_NUDGE` in either direction.
+pub fn drift_pitch_nudge(primary: i64, secondary: i64) -> f32 {
+	if primary <= 0 || secondary <= 0 {
+		return 0.0;
+	}
+
+	let nudge = (primary as f64 / secondary as f64 - 1.0) as f32;
+	nudge.clamp(-MAX_DRIFT_NUDGE, MAX_DRIFT_NUDGE)
+}
+
+
+/// Compute the pitch correction that works off an offset between the devices of a `MirrorOutput`.
+/// `offset` is how far the secondary's audio is ahead of the primary's, in nanoseconds,
+/// and is corrected in proportion to `DRIFT_CORRECTION_WINDOW`.
+/// The result is clamped to `MAX_DRIFT_NUDGE` in either direction.
+pub fn drift_offset_nudge(offset: i64) -> f32 {
+	let nudge = (-(offset as f64) / DRIFT_CORRECTION_WINDOW as f64) as f32;
+	nudge.clamp(-MAX_DRIFT_NUDGE, MAX_DRIFT_NUDGE)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+
+	#[test]
+	fn drift_offset_converges() {
+		// The secondary starts 20ms ahead, and its clock runs 0.1% fast.
+		let mut drift = DriftTracker{offset: 20_000_000.0, ..DriftTracker::default()};
+		let (mut primary, mut secondary) = (0, 0);
+		let mut prev = drift.offset.abs();
+		for _ in 0 .. 300 {
+			primary += 100_000_000;
+			secondary += 100_100_000;
+			drift.update(primary, secondary);
+			assert!(drift.offset.abs() <= prev + 100_000.0, "{} after {}", drift.offset, prev);
+			prev = drift.offset.abs();
+		}
+		assert!(drift.offset.abs() < 100_000.0, "{}", drift.offset);
+		assert!((drift.nudge - (1.0 / 1.001 - 1.0)).abs() < 1e-4, "{}", drift.nudge);
+	}
+}
@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+
+use ::{AltoError, AltoResult};
+use al::*;
+
+
+/// How long a sound plays before its audibility is halved by age, in seconds.
+const AGE_SCALE_SECS: f32 = 10.0;
+
+
+/// Identifies a sound started through an `AutoMixer`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct SoundId(u64);
+
+
+/// Plays any number of logical sounds through a fixed pool of sources, giving the voices to the most audible ones.
+///
+/// Each sound is scored by `audibility` from the attenuation predicted for the current listener position,
+/// its priority and its age. The highest scoring sounds, up to the number of voices, are played through real sources.
+/// The rest are virtual: their buffer, properties and playback position are kept, the position advancing
+/// with the time passed to `update`, so that they resume at the right place if they become audible again.
+/// Sounds with a score of zero are never voiced.
+pub struct AutoMixer {
+	ctx: Context,
+	voices: usize,
+	free: Vec<StaticSource>,
+	sounds: Vec<Sound>,
+	next_id: u64,
+}
+
+
+struct Sound {
+	id: SoundId,
+	buf: Arc<Buffer>,
+	props: SourceProps,
+	priority: f32,
+	looping: bool,
+	/// Playback position, read back from the voice while voiced and extrapolated while virtual.
+	position: Duration,
+	age: Duration,
+	score: f32,
+	voice: Option<StaticSource>,
+}
+
+
+/// Score of a sound predicted to be mixed at gain `attenuation`, as used by `AutoMixer` to rank sounds.
+/// This is `priority * attenuation / (1 + age / 10s)`, so a sound that has been playing for 10 seconds
+/// counts half as much as a new one of the same loudness and priority.
+pub fn audibility(attenuation: f32, priority: f32, age: Duration) -> f32 {
+	let age = age.as_secs() as f32 + age.subsec_nanos() as f32 / 1_000_000_000.0;
+	priority * attenuation / (1.0 + age / AGE_SCALE_SECS)
+}
+
+
+impl AutoMixer {
+	/// Create a mixer that plays at most `voices` sounds at once, generating its sources up front.
+	pub fn new(ctx: &Context, voices: usize) -> AltoResult<AutoMixer> {
+		if voices == 0 {
+			return Err(AltoError::InvalidValue);
+		}
+
+		let mut free = Vec::with_capacity(voices);
+		for _ in 0 .. voices {
+			free.push(ctx.new_static_source()?);
+		}
+		Ok(AutoMixer{ctx: ctx.clone(), voices, free, sounds: Vec::new(), next_id: 0})
+	}
+
+
+	/// The context the voices belong to.
+	pub fn context(&self) -> &Context { &self.ctx }
+	/// Maximum number of sounds voiced at once.
+	pub fn voices(&self) -> usize { self.voices }
+	/// Number of logical sounds, voiced or not.
+	pub fn len(&self) -> usize { self.sounds.len() }
+	/// Whether there are no logical sounds.
+	pub fn is_empty(&self) -> bool { self.sounds.is_empty() }
+
+
+	/// Start a sound from the beginning of `buf`. It is voiced right away if it scores high enough,
+	/// virtualizing the least audible voiced sound if every voice is taken.
+	/// `priority` scales the score and must be finite and non-negative.
+	pub fn play(&mut self, buf: Arc<Buffer>, props: SourceProps, priority: f32, looping: bool) -> AltoResult<SoundId> {
+		if !priority.is_finite() || priority < 0.0 {
+			return Err(AltoError::InvalidValue);
+		}
+		self.ctx.check_device(&buf.ctx)?;
+
+		let id = SoundId(self.next_id);
+		self.next_id += 1;
+		self.sounds.push(Sound{
+			id,
+			buf,
+			props,
+			priority,
+			looping,
+			position: Duration::from_secs(0),
+			age: Duration::from_secs(0),
+			score: 0.0,
+			voice: None,
+		});
+		self.rebalance()?;
+		Ok(id)
+	}
+
+
+	/// Stop a sound and forget it, freeing its voice if it had one.
+	/// Returns whether the sound was still active.
+	pub fn stop(&mut self, id: SoundId) -> bool {
+		match self.index(id) {
+			Some(i) => {
+				let sound = self.sounds.remove(i);
+				if let Some(src) = sound.voice {
+					self.release(src);
+				}
+				true
+			},
+			None => false,
+		}
+	}
+
+
+	/// Replace the properties of a sound, applying them to its voice if it has one.
+	/// They take part in scoring at the next `update`.
+	pub fn set_props(&mut self, id: SoundId, props: SourceProps) -> AltoResult<()> {
+		let i = self.index(id).ok_or(AltoError::InvalidName)?;
+		self.sounds[i].props = props;
+		if let Some(ref mut src) = self.sounds[i].voice {
+			apply_props(&self.ctx, src, &props)?;
+		}
+		Ok(())
+	}
+	/// Change the priority of a sound. It takes part in scoring at the next `update`.
+	pub fn set_priority(&mut self, id: SoundId, priority: f32) -> AltoResult<()> {
+		if !priority.is_finite() || priority < 0.0 {
+			return Err(AltoError::InvalidValue);
+		}
+		let i = self.index(id).ok_or(AltoError::InvalidName)?;
+		self.sounds[i].priority = priority;
+		Ok(())
+	}
+
+
+	/// Whether the sound is still playing, voiced or not.
+	pub fn is_active(&self, id: SoundId) -> bool { self.index(id).is_some() }
+	/// Whether the sound is currently playing through a source.
+	pub fn is_voiced(&self, id: SoundId) -> bool {
+		self.index(id).is_some_and(|i| self.sounds[i].voice.is_some())
+	}
+	/// Score of the sound as of the last `play` or `update`.
+	pub fn score(&self, id: SoundId) -> Option<f32> {
+		self.index(id).map(|i| self.sounds[i].score)
+	}
+	/// Properties last given for the sound.
+	pub fn props(&self, id: SoundId) -> Option<SourceProps> {
+		self.index(id).map(|i| self.sounds[i].props)
+	}
+	/// Playback position of the sound as of the last `play` or `update`.
+	pub fn offset(&self, id: SoundId) -> Option<Duration> {
+		self.index(id).map(|i| self.sounds[i].position)
+	}
+	/// The source currently voicing the sound, if any.
+	pub fn source(&self, id: SoundId) -> Option<&StaticSource> {
+		self.index(id).and_then(|i| self.sounds[i].voice.as_ref())
+	}
+	/// All sounds currently playing through a source.
+	pub fn voiced(&self) -> Vec<SoundId> {
+		self.sounds.iter().filter(|s| s.voice.is_some()).map(|s| s.id).collect()
+	}
+
+
+	/// Advance every sound by `elapsed`, drop those that have finished, then rescore them against the current
+	/// listener and move voices from the least to the most audible sounds, re-voicing virtual sounds at their
+	/// extrapolated position. `elapsed` should be the time mixed since the last call.
+	pub fn update(&mut self, elapsed: Duration) -> AltoResult<()> {
+		let mut i = 0;
+		while i < self.sounds.len() {
+			if self.advance(i, elapsed) {
+				i += 1;
+			} else if let Some(src) = self.sounds.remove(i).voice {
+				self.release(src);
+			}
+		}
+
+		self.rebalance()
+	}
+
+
+	/// Advance a sound, returning `false` once it has finished.
+	fn advance(&mut self, i: usize, elapsed: Duration) -> bool {
+		let sound = &mut self.sounds[i];
+		sound.age += elapsed;
+
+		if let Some(ref src) = sound.voice {
+			if src.state() == SourceState::Stopped {
+				return false;
+			}
+			sound.position = Duration::from_secs_f32(src.sec_offset().max(0.0));
+			return true;
+		}
+
+		let len = Duration::from_nanos(buffer_nanos(sound.buf.len, sound.buf.freq));
+		sound.position += elapsed;
+		if sound.position < len {
+			true
+		} else if sound.looping && len > Duration::from_secs(0) {
+			sound.position = Duration::from_nanos((sound.position.as_nanos() % len.as_nanos()) as u64);
+			true
+		} else {
+			false
+		}
+	}
+
+
+	fn rebalance(&mut self) -> AltoResult<()> {
+		let model = self.ctx.distance_model();
+		let gain = self.ctx.gain();
+		let listener = self.ctx.position();
+		for sound in &mut self.sounds {
+			sound.score = audibility(attenuation(model, &sound.props, listener) * gain, sound.priority, sound.age);
+		}
+
+		let mut order: Vec<usize> = (0 .. self.sounds.len()).collect();
+		{
+			let sounds = &self.sounds;
+			order.sort_by(|&a, &b| sounds[b].score.partial_cmp(&sounds[a].score)
+				.unwrap_or(Ordering::Equal)
+				.then(sounds[a].id.cmp(&sounds[b].id)));
+		}
+		let mut wanted = vec![false; self.sounds.len()];
+		for &i in order.iter().take(self.voices) {
+			wanted[i] = self.sounds[i].score > 0.0;
+		}
+
+		// Free voices before handing any out, so there are always enough to go around.
+		for (i, &wanted) in wanted.iter().enumerate() {
+			if !wanted {
+				if let Some(src) = self.sounds[i].voice.take() {
+					self.sounds[i].position = Duration::from_secs_f32(src.sec_offset().max(0.0));
+					self.release(src);
+				}
+			}
+		}
+		for &i in &order {
+			if wanted[i] && self.sounds[i].voice.is_none() {
+				self.voice(i)?;
+			}
+		}
+		Ok(())
+	}
+
+
+	fn voice(&mut self, i: usize) -> AltoResult<()> {
+		let mut src = self.free.pop().expect("no free voice");
+		let ctx = &self.ctx;
+		let sound = &mut self.sounds[i];
+		let result = (|| -> AltoResult<()> {
+			src.set_buffer(sound.buf.clone())?;
+			src.set_looping(sound.looping);
+			apply_props(ctx, &mut src, &sound.props)?;
+			let pos = sound.position;
+			src.set_sec_offset(pos.as_secs() as f32 + pos.subsec_nanos() as f32 / 1_000_000_000.0)?;
+			src.play();
+			Ok(())
+		})();
+
+		match result {
+			Ok(()) => {
+				sound.voice = Some(src);
+				Ok(())
+			},
+			Err(e) => {
+				src.clear_buffer();
+				self.free.push(src);
+				Err(e)
+			},
+		}
+	}
+
+
+	fn release(&mut self, mut src: StaticSource) {
+		src.stop();
+		src.clear_buffer();
+		self.free.push(src);
+	}
+
+
+	fn index(&self, id: SoundId) -> Option<usize> {
+		self.sounds.binary_search_by_key(&id, |s| s.id).ok()
+	}
+}
+
+
+/// Set every property in `props` on a voice, so nothing carries over from the sound it last played.
+fn apply_props(ctx: &Context, src: &mut StaticSource, props: &SourceProps) -> AltoResult<()> {
+	let _defer = ctx.defer_updates();
+	src.set_position(props.position)?;
+	src.set_relative(props.relative);
+	src.set_gain(props.gain)?;
+	src.set_min_gain(props.min_gain)?;
+	src.set_max_gain(props.max_gain)?;
+	src.set_reference_distance(props.reference_distance)?;
+	src.set_rolloff_factor(props.rolloff_factor)?;
+	src.set_max_distance(props.max_distance)?;
+	src.set_direction(props.direction)?;
+	src.set_cone_inner_angle(props.cone_inner_angle)?;
+	src.set_cone_outer_angle(props.cone_outer_angle)?;
+	src.set_cone_outer_gain(props.cone_outer_gain)?;
+	if ctx.0.exts.AL_EXT_source_distance_model().is_ok() {
+		src.set_distance_model(props.distance_model.unwrap_or_else(|| ctx.distance_model()))?;
+	}
+	Ok(())
+}
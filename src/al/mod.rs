@@ -1,24 +1,70 @@
 use std::ops::Deref;
+use std::fmt;
 use std::iter;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::collections::VecDeque;
+use std::cmp;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::mem;
 use std::ptr;
 use std::hash::{Hash, Hasher};
-use std::ffi::{CString, CStr};
-use parking_lot::{Mutex, MutexGuard};
+use std::ffi::CString;
+use std::time::Duration;
+use parking_lot::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
 
-use ::{AltoError, AltoResult};
+use ::{AltoError, AltoResult, BufferConstraint};
 use sys;
 use alc::*;
 use efx::*;
 use ext;
 
 
+/// Record a call through the given `ContextInner` if it is being recorded, binding the recorder to `$r`.
+#[cfg(feature = "replay")]
+macro_rules! record {
+	($inner:expr, $r:ident => $call:expr) => {
+		if let Some(ref mut $r) = *$inner.recording.lock() {
+			#[allow(unused_imports)]
+			use al::replay::Call;
+			let call = $call;
+			$r.push(call);
+		}
+	};
+}
+#[cfg(not(feature = "replay"))]
+macro_rules! record {
+	($inner:expr, $r:ident => $call:expr) => { () };
+}
+
+
+#[cfg(feature = "replay")]
+pub mod replay;
+
 mod format;
 pub use self::format::*;
 
+mod mirror;
+pub use self::mirror::*;
+
+mod ambisonic;
+pub use self::ambisonic::*;
+
+mod stream;
+pub use self::stream::*;
+
+mod attenuation;
+pub use self::attenuation::*;
+mod occlusion;
+pub use self::occlusion::*;
+mod mixer;
+pub use self::mixer::*;
+mod fade;
+pub use self::fade::*;
+mod sink;
+pub use self::sink::*;
+mod cached;
+pub use self::cached::*;
+
 
 lazy_static! {
 	#[doc(hidden)]
@@ -26,6 +72,9 @@ lazy_static! {
 }
 
 
+static NEXT_CONTEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+
 /// The gain curve of sources as a function of distance to the listener.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum DistanceModel {
@@ -61,15 +110,211 @@ pub enum SoftSourceSpatialization {
 }
 
 
+/// Where a buffer's data should be stored on X-RAM capable hardware.
+/// Requires `EAX-RAM`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum XRamStorage {
+	/// `AL_STORAGE_AUTOMATIC`
+	Automatic,
+	/// `AL_STORAGE_HARDWARE`
+	Hardware,
+	/// `AL_STORAGE_ACCESSIBLE`
+	Accessible,
+	Unknown(sys::ALenum),
+}
+
+
 /// A listener context.
+/// Buffers, sources and EFX objects each hold a strong reference to the context
+/// that created them, so the underlying `ALCcontext` is only destroyed once the
+/// last of them has been dropped. If the context is destroyed early by `Alto::shutdown`,
+/// every source and buffer name it still tracks is deleted first and the remaining handles become inert.
 pub struct Context(pub(crate) Arc<ContextInner>);
 
 
 pub(crate) struct ContextInner {
 	pub dev: Device,
 	pub ctx: *mut sys::ALCcontext,
+	pub id: usize,
 	pub exts: ext::AlCache,
 	defer_rc: Arc<AtomicUsize>,
+	streams: Mutex<Vec<StreamEntry>>,
+	sources: Mutex<Vec<Weak<SourceInner>>>,
+	buffers: Mutex<HashSet<sys::ALuint>>,
+	buffered: Mutex<BufferTotals>,
+	pub slots: AtomicUsize,
+	pub effects: AtomicUsize,
+	underruns: AtomicUsize,
+	destroyed: AtomicBool,
+	/// Held for reading while the context is current, and for writing while `Alto::shutdown` destroys it,
+	/// so that it is never destroyed between the check of `destroyed` and the calls made with it current.
+	live: RwLock<()>,
+	pause: Mutex<PauseState>,
+	default_resampler: Mutex<Option<sys::ALint>>,
+	/// Bytes reused by `new_buffer_from_iter` to encode generated frames into before uploading them.
+	scratch: Mutex<Vec<u8>>,
+	/// Current generation of every source and aux effect slot name that has been deleted at least once.
+	/// Other kinds of object are tracked by the device.
+	generations: Mutex<HashMap<(ObjectKind, sys::ALuint), u32>>,
+	#[cfg(feature = "replay")]
+	recording: Mutex<Option<replay::Recorder>>,
+}
+
+
+/// The kinds of AL object that can be named by a `RawHandle`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ObjectKind {
+	Source,
+	Buffer,
+	AuxEffectSlot,
+	Effect,
+	Filter,
+}
+
+
+impl ObjectKind {
+	/// Whether names of this kind are shared by every context on a device, rather than owned by a single context.
+	pub fn is_device_shared(self) -> bool {
+		match self {
+			ObjectKind::Source | ObjectKind::AuxEffectSlot => false,
+			ObjectKind::Buffer | ObjectKind::Effect | ObjectKind::Filter => true,
+		}
+	}
+}
+
+
+/// An object name together with its generation, which is bumped every time the name is deleted.
+/// OpenAL hands deleted names out again to new objects, so a bare name kept around after its object is gone
+/// can silently refer to something else. `Context::check_handle` tells the two apart.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct RawHandle {
+	pub kind: ObjectKind,
+	pub name: sys::ALuint,
+	pub generation: u32,
+	/// Id of the device that owns the object if its kind is device shared, or of the owning context otherwise.
+	pub owner: usize,
+}
+
+
+#[derive(Default)]
+struct BufferTotals {
+	bytes: u64,
+	nanos: u64,
+}
+
+
+/// A snapshot of the objects alive in a context, as returned by `Context::stats`.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ContextStats {
+	/// Static and streaming sources.
+	pub sources: usize,
+	/// Sources in the `Initial` state.
+	pub initial: usize,
+	/// Sources in the `Playing` state.
+	pub playing: usize,
+	/// Sources in the `Paused` state.
+	pub paused: usize,
+	/// Sources in the `Stopped` state.
+	pub stopped: usize,
+	/// Buffers.
+	pub buffers: usize,
+	/// Total size of the data uploaded to all buffers.
+	pub buffered_bytes: u64,
+	/// Total duration of the data uploaded to all buffers, in seconds.
+	pub buffered_secs: f64,
+	/// Auxiliary effect slots.
+	pub aux_slots: usize,
+	/// Effects of any type.
+	pub effects: usize,
+	/// Streaming sources restarted by `Context::process_streams` after running dry.
+	pub underruns: usize,
+}
+
+
+/// The global state of a context and its listener, as captured by `Context::snapshot_global`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GlobalState {
+	/// `AL_DISTANCE_MODEL`
+	pub distance_model: DistanceModel,
+	/// `AL_DOPPLER_FACTOR`
+	pub doppler_factor: f32,
+	/// `AL_SPEED_OF_SOUND`
+	pub speed_of_sound: f32,
+	/// `AL_POSITION` of the listener
+	pub position: [f32; 3],
+	/// `AL_VELOCITY` of the listener
+	pub velocity: [f32; 3],
+	/// `AL_GAIN` of the listener
+	pub gain: f32,
+	/// `AL_ORIENTATION` of the listener, as the "at" and "up" vectors
+	pub orientation: ([f32; 3], [f32; 3]),
+}
+
+
+/// The commonly inspected properties of a source, as read by `Source::read_state`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SourceSnapshot {
+	/// `AL_GAIN`
+	pub gain: f32,
+	/// `AL_PITCH`
+	pub pitch: f32,
+	/// `AL_POSITION`
+	pub position: [f32; 3],
+	/// `AL_VELOCITY`
+	pub velocity: [f32; 3],
+	/// `AL_LOOPING`
+	pub looping: bool,
+	/// `AL_SOURCE_STATE`
+	pub state: SourceState,
+}
+
+
+/// Context-wide pause bookkeeping shared by all outstanding `PauseToken`s.
+#[derive(Default)]
+struct PauseState {
+	/// Number of outstanding tokens.
+	depth: usize,
+	/// Whether the whole device was paused instead of individual sources.
+	device: bool,
+	/// Sources that were playing when a token paused them.
+	paused: Vec<Weak<SourceInner>>,
+	/// Sources created while paused, and the playback requested for them.
+	born: Vec<(Weak<SourceInner>, DeferredPlay)>,
+}
+
+
+/// Playback requested for a source created while its context is paused.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum DeferredPlay {
+	Idle,
+	Now,
+	/// Start at the given device clock time, as with `alSourcePlayAtTimeSOFT()`.
+	At(i64),
+}
+
+
+/// Keeps a context paused until it is resumed or dropped, as returned by `Context::pause_all`.
+pub struct PauseToken {
+	ctx: Context,
+}
+
+
+/// Capacity measured by `Context::probe_max_sources`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct ProbeResult {
+	/// Most sources that could exist at once, capped at the probe ceiling.
+	pub max_sources: usize,
+	/// Most of those sources that could be playing at once.
+	/// Only measured on loopback devices, where starting playback is inaudible.
+	pub max_playing: Option<usize>,
+}
+
+
+/// Raw sources generated by a probe, deleted again when dropped.
+struct SourceProbe<'c> {
+	ctx: &'c Context,
+	srcs: Vec<sys::ALuint>,
 }
 
 
@@ -84,7 +329,12 @@ pub struct DeferLock<'c> {
 pub struct Buffer {
 	ctx: Context,
 	buf: sys::ALuint, 
+	gen: u32,
 	len: sys::ALsizei,
+	size: usize,
+	freq: sys::ALint,
+	channels: sys::ALint,
+	bits: sys::ALint,
 }
 
 
@@ -93,12 +343,31 @@ pub unsafe trait Source {
 	/// The context from which this source was created.
 	fn context(&self) -> &Context;
 	/// Raw handle as provided by OpenAL.
+	/// The name is handed out again once this source is dropped, so keep a `raw_handle` instead to be able to tell.
 	fn as_raw(&self) -> sys::ALuint;
+	/// Name and generation of this source, which `Context::check_handle` rejects once the source is dropped.
+	fn raw_handle(&self) -> RawHandle;
 
 	/// `alGetSourcei(AL_SOURCE_STATE)`
 	fn state(&self) -> SourceState;
+	/// Whether `state` is `SourceState::Initial`.
+	fn is_initial(&self) -> bool { self.state().is_initial() }
+	/// Whether `state` is `SourceState::Playing`.
+	fn is_playing(&self) -> bool { self.state().is_playing() }
+	/// Whether `state` is `SourceState::Paused`.
+	fn is_paused(&self) -> bool { self.state().is_paused() }
+	/// Whether `state` is `SourceState::Stopped`.
+	fn is_stopped(&self) -> bool { self.state().is_stopped() }
+	/// Read gain, pitch, position, velocity, looping and state together, with updates deferred while doing so.
+	/// This takes the context lock once rather than once per property.
+	fn read_state(&self) -> AltoResult<SourceSnapshot>;
 	/// `alSourcePlay()`
 	fn play(&mut self);
+	/// `alSourcePlayAtTimeSOFT()`
+	/// Start playing once the device clock, as reported by `DeviceObject::clock_time`, reaches `device_clock_ns`.
+	/// A time already past starts playback right away, as does a time that passed while the context was paused by `Context::pause_all`.
+	/// Requires `AL_SOFT_source_start_delay`
+	fn play_at(&mut self, device_clock_ns: i64) -> AltoResult<()>;
 	/// `alSourcePause()`
 	fn pause(&mut self);
 	/// `alSourceStop()`
@@ -115,6 +384,9 @@ pub unsafe trait Source {
 	fn gain(&self) -> f32;
 	/// `alSourcef(AL_GAIN)`
 	fn set_gain(&mut self, f32) -> AltoResult<()>;
+	/// Fade the gain linearly to silence over `duration`, calling `stop()` once the fade completes.
+	/// Nothing happens until the returned handle is ticked, typically once per frame with the time elapsed.
+	fn fade_out(&self, duration: Duration) -> AltoResult<FadeHandle>;
 
 	/// `alGetSourcef(AL_MIN_GAIN)`
 	fn min_gain(&self) -> f32;
@@ -180,6 +452,9 @@ pub unsafe trait Source {
 	fn sec_offset(&self) -> f32;
 	/// `alSourcef(AL_SEC_OFFSET)`
 	fn set_sec_offset(&mut self, f32) -> AltoResult<()>;
+	/// `alGetSourcef(AL_SEC_OFFSET)`
+	/// The playback position as a `Duration`, relative to the buffers currently attached or queued.
+	fn progress(&self) -> AltoResult<Duration>;
 
 	/// `alGetSourcei(AL_SAMPLE_OFFSET)`
 	fn sample_offset(&self) -> sys::ALint;
@@ -232,6 +507,18 @@ pub unsafe trait Source {
 	/// Requires `AL_SOFT_source_spatialization`
 	fn set_soft_spatialization(&mut self, value: SoftSourceSpatialization) -> AltoResult<()>;
 
+	/// Play this source as plain, unpositioned 2D sound, or go back to 3D with the parameters it had before.
+	/// Entering 2D makes the source relative at the origin with no rolloff, and then turns on direct
+	/// channels for multichannel buffers or turns off spatialization otherwise, where the extensions allow.
+	/// The changes are applied as a single batch when `AL_SOFT_deferred_updates` is available.
+	/// If any change fails, those already made are undone and the source stays in its previous mode.
+	fn set_2d(&mut self, enabled: bool) -> AltoResult<()>;
+	/// Whether the source is in 2D mode, as set by `set_2d`.
+	fn is_2d(&self) -> bool;
+	/// Pan the source between the left and right of the listener, from -1 for hard left to 1 for hard right.
+	/// This makes the source relative and moves it to `pan_position(pan)`, in a single batch when `AL_SOFT_deferred_updates` is available.
+	fn set_pan(&mut self, pan: f32) -> AltoResult<()>;
+
 	/// `alGetSourcei(AL_SOURCE_RESAMPLER_SOFT)`
 	/// Requires `AL_SOFT_source_resampler`
 	fn soft_resampler(&self) -> AltoResult<sys::ALint>;
@@ -269,6 +556,34 @@ pub unsafe trait Source {
 	/// `alSourceiv(AL_AUXILIARY_SEND_FILTER)`
 	/// Requires `ALC_EXT_EFX`
 	fn clear_aux_send(&mut self, send: sys::ALint);
+	/// `alSourceiv(AL_AUXILIARY_SEND_FILTER)`
+	/// Detach the slot and filter from aux sends `0 .. max_sends`, or from every send the device has if `None`.
+	/// Requires `ALC_EXT_EFX`
+	fn clear_aux_sends(&mut self, max_sends: Option<sys::ALint>) -> AltoResult<()>;
+	/// Slot and filter last attached to the given aux send, if any.
+	/// Requires `ALC_EXT_EFX`
+	fn aux_send(&self, send: sys::ALint) -> Option<SendInfo>;
+	/// All aux sends that currently have a slot attached, by send index.
+	/// Requires `ALC_EXT_EFX`
+	fn aux_sends(&self) -> ::std::vec::IntoIter<(sys::ALint, SendInfo)>;
+
+	/// Low-pass the direct path and aux send 0 through filters owned by the source.
+	/// Filter parameters are copied when a filter is attached, so both filters are re-attached on every call.
+	/// The send filter is attached alongside whatever slot is on send 0, so set the slot first;
+	/// `set_aux_send` or `set_direct_filter` afterwards replace the corresponding filter.
+	/// Without `ALC_EXT_EFX`, the source gain is lowered to `Occlusion::fallback_gain` instead,
+	/// overriding any gain set while occluded until `clear_occlusion` restores it.
+	fn set_occlusion(&mut self, direct_lf: f32, direct_gain: f32, send_lf: f32, send_gain: f32) -> AltoResult<()>;
+	/// `set_occlusion` with the values from `Occlusion::occluded(amount)`.
+	fn occlude(&mut self, amount: f32) -> AltoResult<()>;
+	/// `set_occlusion` with the values from `Occlusion::obstructed(amount)`.
+	fn obstruct(&mut self, amount: f32) -> AltoResult<()>;
+	/// `set_occlusion` with the values from `Occlusion::excluded(amount)`.
+	fn exclude(&mut self, amount: f32) -> AltoResult<()>;
+	/// Detach the occlusion filters, or restore the source gain without EFX.
+	fn clear_occlusion(&mut self);
+	/// The occlusion last applied by `set_occlusion` or one of its presets, if any.
+	fn occlusion(&self) -> Option<Occlusion>;
 
 	/// `alGetSourcef(AL_AIR_ABSORPTION_FACTOR)`
 	/// Requires `ALC_EXT_EFX`
@@ -315,10 +630,46 @@ pub enum SourceState {
 }
 
 
+impl SourceState {
+	/// Whether this is `Initial`.
+	pub fn is_initial(self) -> bool { self == SourceState::Initial }
+	/// Whether this is `Playing`.
+	pub fn is_playing(self) -> bool { self == SourceState::Playing }
+	/// Whether this is `Paused`.
+	pub fn is_paused(self) -> bool { self == SourceState::Paused }
+	/// Whether this is `Stopped`.
+	pub fn is_stopped(self) -> bool { self == SourceState::Stopped }
+
+
+	fn from_raw(value: sys::ALint) -> SourceState {
+		match value {
+			sys::AL_INITIAL => SourceState::Initial,
+			sys::AL_PLAYING => SourceState::Playing,
+			sys::AL_PAUSED => SourceState::Paused,
+			sys::AL_STOPPED => SourceState::Stopped,
+			ss => SourceState::Unknown(ss),
+		}
+	}
+}
+
+
 pub(crate) struct SourceInner {
 	ctx: Context,
 	src: sys::ALuint,
-	sends: Mutex<Vec<sys::ALuint>>,
+	gen: u32,
+	sends: Mutex<Vec<Option<SendInfo>>>,
+	saved_3d: Mutex<Option<Saved3d>>,
+	occlusion: Mutex<Option<OcclusionState>>,
+}
+
+
+/// 3D parameters overridden by `Source::set_2d`, restored when 2D mode is left.
+struct Saved3d {
+	relative: bool,
+	position: [f32; 3],
+	rolloff_factor: f32,
+	direct_channels: Option<bool>,
+	spatialization: Option<SoftSourceSpatialization>,
 }
 
 
@@ -332,7 +683,30 @@ pub struct StaticSource {
 /// A source that plays a queue of owned buffers.
 pub struct StreamingSource {
 	src: Arc<SourceInner>,
-	bufs: VecDeque<Buffer>,
+	bufs: Arc<Mutex<StreamQueue>>,
+}
+
+
+pub(crate) struct StreamQueue {
+	/// Buffers still queued on the source, in queue order.
+	queued: VecDeque<Buffer>,
+	/// Buffers unqueued by `Context::process_streams` that have not yet been handed back.
+	processed: VecDeque<Buffer>,
+	/// Total playing time of every buffer that has been unqueued from the source.
+	unqueued_nanos: u64,
+}
+
+
+struct StreamEntry {
+	src: Weak<SourceInner>,
+	bufs: Weak<Mutex<StreamQueue>>,
+}
+
+
+/// Returned by `Context::make_current`, keeping the context current and alive until dropped.
+pub(crate) struct CurrentLock<'a> {
+	_global: Option<MutexGuard<'static, ()>>,
+	_live: RwLockReadGuard<'a, ()>,
 }
 
 
@@ -343,12 +717,35 @@ impl Context {
 			ext::AlCache::new(&dev.0.alto.0.api)
 		};
 
-		Context(Arc::new(ContextInner{
+		let inner = Arc::new(ContextInner{
 			dev: dev,
 			ctx: ctx,
+			id: NEXT_CONTEXT_ID.fetch_add(1, Ordering::Relaxed) + 1,
 			exts: exts,
 			defer_rc: Arc::new(AtomicUsize::new(0)),
-		}))
+			streams: Mutex::new(Vec::new()),
+			sources: Mutex::new(Vec::new()),
+			buffers: Mutex::new(HashSet::new()),
+			buffered: Mutex::new(BufferTotals::default()),
+			slots: AtomicUsize::new(0),
+			effects: AtomicUsize::new(0),
+			underruns: AtomicUsize::new(0),
+			destroyed: AtomicBool::new(false),
+			live: RwLock::new(()),
+			pause: Mutex::new(PauseState::default()),
+			default_resampler: Mutex::new(None),
+			scratch: Mutex::new(Vec::new()),
+			generations: Mutex::new(HashMap::new()),
+			#[cfg(feature = "replay")]
+			recording: Mutex::new(None),
+		});
+
+		{
+			let mut contexts = inner.dev.0.contexts.lock();
+			contexts.retain(|c| Weak::strong_count(c) > 0);
+			contexts.push(Arc::downgrade(&inner));
+		}
+		Context(inner)
 	}
 
 
@@ -356,6 +753,9 @@ impl Context {
 	pub fn device(&self) -> &Device { &self.0.dev }
 	/// Raw context pointer as provided by OpenAL.
 	pub fn as_raw(&self) -> *mut sys::ALCcontext { self.0.ctx }
+	/// Process-unique id of this context, as reported by `AltoError::WrongContext`.
+	/// Unlike the raw pointer, an id is never reused after the context is destroyed.
+	pub fn id(&self) -> usize { self.0.id }
 
 
 	/// `alIsExtensionPresent()`
@@ -371,7 +771,7 @@ impl Context {
 			ext::Al::MuLawBFormat => self.0.exts.AL_EXT_MULAW_BFORMAT().is_ok(),
 			ext::Al::MuLawMcFormats => self.0.exts.AL_EXT_MULAW_MCFORMATS().is_ok(),
 			ext::Al::SoftBlockAlignment => self.0.exts.AL_SOFT_block_alignment().is_ok(),
-//			ext::Al::SoftBufferSamples => self.0.ext.AL_SOFT_buffer_samples().is_ok(),
+			ext::Al::SoftBufferSamples => self.0.exts.AL_SOFT_buffer_samples().is_ok(),
 //			ext::Al::SoftBufferSubData => self.0.ext.AL_SOFT_buffer_sub_data().is_ok(),
 			ext::Al::SoftDeferredUpdates => self.0.exts.AL_SOFT_deferred_updates().is_ok(),
 			ext::Al::SoftDirectChannels => self.0.exts.AL_SOFT_direct_channels().is_ok(),
@@ -382,9 +782,11 @@ impl Context {
 			ext::Al::SourceDistanceModel => self.0.exts.AL_EXT_source_distance_model().is_ok(),
 			ext::Al::SoftSourceSpatialize => self.0.exts.AL_SOFT_source_spatialize().is_ok(),
 			ext::Al::SoftSourceResampler => self.0.exts.AL_SOFT_source_resampler().is_ok(),
+			ext::Al::SoftSourceStartDelay => self.0.exts.AL_SOFT_source_start_delay().is_ok(),
 			ext::Al::SoftGainClampEx => self.0.exts.AL_SOFT_gain_clamp_ex().is_ok(),
 			ext::Al::StereoAngles => self.0.exts.AL_EXT_STEREO_ANGLES().is_ok(),
 			ext::Al::SourceRadius => self.0.exts.AL_EXT_SOURCE_RADIUS().is_ok(),
+			ext::Al::XRam => self.0.exts.EAX_RAM().is_ok(),
 		}
 	}
 
@@ -406,18 +808,18 @@ impl Context {
 	/// `alDistanceModel()`
 	pub fn set_distance_model(&self, value: DistanceModel) {
 		let _lock = self.make_current(true);
-		unsafe {
-			self.0.dev.0.alto.0.api.alDistanceModel(match value {
-				DistanceModel::None => sys::AL_NONE,
-				DistanceModel::Inverse => sys::AL_INVERSE_DISTANCE,
-				DistanceModel::InverseClamped => sys::AL_INVERSE_DISTANCE_CLAMPED,
-				DistanceModel::Linear => sys::AL_LINEAR_DISTANCE,
-				DistanceModel::LinearClamped => sys::AL_LINEAR_DISTANCE_CLAMPED,
-				DistanceModel::Exponent => sys::AL_EXPONENT_DISTANCE,
-				DistanceModel::ExponentClamped => sys::AL_EXPONENT_DISTANCE_CLAMPED,
-				DistanceModel::Unknown(dm) => dm,
-			})
+		let value = match value {
+			DistanceModel::None => sys::AL_NONE,
+			DistanceModel::Inverse => sys::AL_INVERSE_DISTANCE,
+			DistanceModel::InverseClamped => sys::AL_INVERSE_DISTANCE_CLAMPED,
+			DistanceModel::Linear => sys::AL_LINEAR_DISTANCE,
+			DistanceModel::LinearClamped => sys::AL_LINEAR_DISTANCE_CLAMPED,
+			DistanceModel::Exponent => sys::AL_EXPONENT_DISTANCE,
+			DistanceModel::ExponentClamped => sys::AL_EXPONENT_DISTANCE_CLAMPED,
+			DistanceModel::Unknown(dm) => dm,
 		};
+		unsafe { self.0.dev.0.alto.0.api.alDistanceModel(value); }
+		record!(self.0, r => Call::DistanceModel{value});
 	}
 
 
@@ -435,8 +837,10 @@ impl Context {
 		let _lock = self.make_current(true);
 		if value {
 			unsafe { self.0.dev.0.alto.0.api.alEnable(self.0.exts.AL_EXT_source_distance_model()?.AL_SOURCE_DISTANCE_MODEL?); }
+			record!(self.0, r => Call::Enable{cap: self.0.exts.AL_EXT_source_distance_model()?.AL_SOURCE_DISTANCE_MODEL?});
 		} else {
 			unsafe { self.0.dev.0.alto.0.api.alDisable(self.0.exts.AL_EXT_source_distance_model()?.AL_SOURCE_DISTANCE_MODEL?); }
+			record!(self.0, r => Call::Disable{cap: self.0.exts.AL_EXT_source_distance_model()?.AL_SOURCE_DISTANCE_MODEL?});
 		}
 		self.get_error()
 	}
@@ -451,6 +855,7 @@ impl Context {
 	pub fn set_doppler_factor(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.make_current(true);
 		unsafe { self.0.dev.0.alto.0.api.alDopplerFactor(value); }
+		record!(self.0, r => Call::DopplerFactor{value});
 		self.get_error()
 	}
 
@@ -464,10 +869,24 @@ impl Context {
 	pub fn set_speed_of_sound(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.make_current(true);
 		unsafe { self.0.dev.0.alto.0.api.alSpeedOfSound(value); }
+		record!(self.0, r => Call::SpeedOfSound{value});
 		self.get_error()
 	}
 
 
+	/// Predict the gain a source with `source_props` would be mixed at for a listener at `listener_pos`,
+	/// using the active distance model and the listener gain, without playing anything.
+	/// See `attenuation` for the formula.
+	pub fn compute_attenuation(&self, source_props: &SourceProps, listener_pos: [f32; 3]) -> f32 {
+		attenuation(self.distance_model(), source_props, listener_pos) * self.gain()
+	}
+	/// Like `compute_attenuation` for many listener positions at once, such as when plotting a falloff curve.
+	pub fn compute_attenuations(&self, source_props: &SourceProps, listener_positions: &[[f32; 3]]) -> Vec<f32> {
+		let (model, gain) = (self.distance_model(), self.gain());
+		listener_positions.iter().map(|&p| attenuation(model, source_props, p) * gain).collect()
+	}
+
+
 	/// `alGetListenerv(AL_GAIN)`
 	pub fn gain(&self) -> f32 {
 		let _lock = self.make_current(true);
@@ -479,6 +898,7 @@ impl Context {
 	pub fn set_gain(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.make_current(true);
 		unsafe { self.0.dev.0.alto.0.api.alListenerf(sys::AL_GAIN, value); }
+		record!(self.0, r => Call::Listenerf{param: sys::AL_GAIN, value});
 		self.get_error()
 	}
 
@@ -495,6 +915,7 @@ impl Context {
 		let _lock = self.make_current(true);
 		let value = value.into();
 		unsafe { self.0.dev.0.alto.0.api.alListenerfv(sys::AL_POSITION, &value as *const [f32; 3] as *const sys::ALfloat); }
+		record!(self.0, r => Call::Listenerfv{param: sys::AL_POSITION, values: value.to_vec()});
 		self.get_error()
 	}
 
@@ -511,6 +932,7 @@ impl Context {
 		let _lock = self.make_current(true);
 		let value = value.into();
 		unsafe { self.0.dev.0.alto.0.api.alListenerfv(sys::AL_VELOCITY, &value as *const [f32; 3] as *const sys::ALfloat); }
+		record!(self.0, r => Call::Listenerfv{param: sys::AL_VELOCITY, values: value.to_vec()});
 		self.get_error()
 	}
 
@@ -527,10 +949,64 @@ impl Context {
 		let _lock = self.make_current(true);
 		let value = [value.0.into(), value.1.into()];
 		unsafe { self.0.dev.0.alto.0.api.alListenerfv(sys::AL_ORIENTATION, &value as *const [[f32; 3]; 2] as *const sys::ALfloat); }
+		record!(self.0, r => Call::Listenerfv{param: sys::AL_ORIENTATION, values: value.iter().flat_map(|v| v.iter().cloned()).collect()});
 		self.get_error()
 	}
 
 
+	/// Restore the global state and the listener to the defaults of a freshly created context:
+	/// the `InverseClamped` distance model, a doppler factor of 1, a speed of sound of 343.3,
+	/// a listener at rest at the origin with a gain of 1, facing down -Z with +Y up.
+	/// Sources, buffers and EFX objects are left alone.
+	pub fn reset_to_initial(&self) -> AltoResult<()> {
+		self.set_distance_model(DistanceModel::InverseClamped);
+		self.set_doppler_factor(1.0)?;
+		self.set_speed_of_sound(343.3)?;
+		self.set_position([0.0, 0.0, 0.0])?;
+		self.set_velocity([0.0, 0.0, 0.0])?;
+		self.set_gain(1.0)?;
+		self.set_orientation(([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]))
+	}
+
+
+	/// Capture the global state and the listener, for restoring later with `restore_global`.
+	pub fn snapshot_global(&self) -> AltoResult<GlobalState> {
+		let state = GlobalState{
+			distance_model: self.distance_model(),
+			doppler_factor: self.doppler_factor(),
+			speed_of_sound: self.speed_of_sound(),
+			position: self.position(),
+			velocity: self.velocity(),
+			gain: self.gain(),
+			orientation: self.orientation(),
+		};
+		self.get_error().map(|_| state)
+	}
+
+
+	/// Run `f` between `snapshot_global` and `restore_global`, so that whatever it does to the global state
+	/// and the listener is undone afterwards. The state is restored even if `f` fails, and the error from `f` takes precedence.
+	pub fn preserving_global<T, F: FnOnce() -> AltoResult<T>>(&self, f: F) -> AltoResult<T> {
+		let state = self.snapshot_global()?;
+		let result = f();
+		let restored = self.restore_global(&state);
+		result.and_then(|r| restored.map(|_| r))
+	}
+
+
+	/// Set the global state and the listener to those captured by `snapshot_global`.
+	/// Sources, buffers and EFX objects are left alone.
+	pub fn restore_global(&self, state: &GlobalState) -> AltoResult<()> {
+		self.set_distance_model(state.distance_model);
+		self.set_doppler_factor(state.doppler_factor)?;
+		self.set_speed_of_sound(state.speed_of_sound)?;
+		self.set_position(state.position)?;
+		self.set_velocity(state.velocity)?;
+		self.set_gain(state.gain)?;
+		self.set_orientation(state.orientation)
+	}
+
+
 	/// `alGetListenerf(AL_GAIN_LIMIT_SOFT)`
 	/// Requires `AL_SOFT_gain_clamp_ex`
 	pub fn soft_gain_limit(&self) -> AltoResult<f32> {
@@ -551,8 +1027,8 @@ impl Context {
 
 			for i in 0 .. value {
 				unsafe {
-					let name = assr.alGetStringiSOFT?(assr.AL_RESAMPLER_NAME_SOFT?, i) as *mut _;
-					name_vec.push(CStr::from_ptr(name).to_owned());
+					let name = assr.alGetStringiSOFT?(assr.AL_RESAMPLER_NAME_SOFT?, i);
+					name_vec.push(Alto::string_from_ptr(name)?);
 				}
 			}
 
@@ -563,6 +1039,28 @@ impl Context {
 	}
 
 
+	/// Resampler index applied to every source created from this context from now on.
+	/// Existing sources are left unchanged.
+	/// Requires `AL_SOFT_source_resampler`
+	pub fn set_default_resampler(&self, index: sys::ALint) -> AltoResult<()> {
+		let assr = self.0.exts.AL_SOFT_source_resampler()?;
+		let count = {
+			let _lock = self.make_current(true);
+			unsafe { self.0.dev.0.alto.0.api.alGetInteger(assr.AL_NUM_RESAMPLERS_SOFT?) }
+		};
+		if index < 0 || index >= count {
+			return Err(AltoError::InvalidValue);
+		}
+
+		*self.0.default_resampler.lock() = Some(index);
+		Ok(())
+	}
+	/// Resampler index set by `set_default_resampler`, if any.
+	pub fn default_resampler(&self) -> Option<sys::ALint> {
+		*self.0.default_resampler.lock()
+	}
+
+
 	/// `alGetListenerf(AL_METERS_PER_UNIT)`
 	/// Requires `ALC_EXT_EFX`
 	pub fn meters_per_unit(&self) -> f32 {
@@ -580,10 +1078,21 @@ impl Context {
 		let efx = self.0.dev.0.exts.ALC_EXT_EFX()?;
 		let _lock = self.make_current(true);
 		unsafe { self.0.dev.0.alto.0.api.alListenerf(efx.AL_METERS_PER_UNIT?, value); }
+		record!(self.0, r => Call::Listenerf{param: efx.AL_METERS_PER_UNIT?, value});
 		self.get_error()
 	}
 
 
+	/// `alGetInteger(AL_EAX_RAM_FREE)`
+	/// Requires `EAX-RAM`
+	pub fn xram_free(&self) -> AltoResult<i64> {
+		let xram = self.0.exts.EAX_RAM()?;
+		let _lock = self.make_current(true);
+		let value = unsafe { self.0.dev.0.alto.0.api.alGetInteger(xram.AL_EAX_RAM_FREE?) };
+		self.get_error().map(|_| value as i64)
+	}
+
+
 	/// `alGenBuffers()`
 	/// To ensure well defined state, the buffer is assigned data immediately.
 	pub fn new_buffer<F: SampleFrame, B: AsBufferData<F>>(&self, data: B, freq: i32) -> AltoResult<Buffer> {
@@ -591,6 +1100,33 @@ impl Context {
 	}
 
 
+	/// `alGenBuffers()`, `alBufferData()`
+	/// Create a buffer from generated sample frames, such as the output of a synth.
+	/// The frames are encoded into byte storage kept by the context and reused between calls, rather than into a new `Vec`.
+	/// `len_hint` is the number of frames expected, so that the storage can be grown once up front.
+	pub fn new_buffer_from_iter<S: SampleFrame, I: IntoIterator<Item = S>>(&self, iter: I, len_hint: usize, freq: u32) -> AltoResult<Buffer> {
+		if freq > sys::ALint::MAX as u32 {
+			return Err(AltoError::InvalidValue);
+		}
+
+		// Taken out while in use, so that the iterator may itself create buffers on this context.
+		let mut scratch = mem::take(&mut *self.0.scratch.lock());
+		scratch.clear();
+		scratch.reserve(len_hint.saturating_mul(mem::size_of::<S>()));
+		for frame in iter {
+			// Sample frames are plain samples with no padding, so their bytes are exactly what OpenAL reads.
+			let bytes = unsafe { ::std::slice::from_raw_parts(&frame as *const S as *const u8, mem::size_of::<S>()) };
+			scratch.extend_from_slice(bytes);
+		}
+
+		let buf = Buffer::validate_data::<S>(self, scratch.len())
+			.and_then(|_| Buffer::new_empty(self.clone()))
+			.and_then(|mut buf| buf.upload_data::<S>(scratch.as_ptr() as *const sys::ALvoid, scratch.len(), freq as sys::ALint).map(|_| buf));
+		*self.0.scratch.lock() = scratch;
+		buf
+	}
+
+
 	/// `alGenSources()`
 	pub fn new_static_source(&self) -> AltoResult<StaticSource> {
 		StaticSource::new(self.clone())
@@ -599,7 +1135,264 @@ impl Context {
 
 	/// `alGenSources()`
 	pub fn new_streaming_source(&self) -> AltoResult<StreamingSource> {
-		StreamingSource::new(self.clone())
+		let src = StreamingSource::new(self.clone())?;
+		self.0.streams.lock().push(StreamEntry{src: Arc::downgrade(&src.src), bufs: Arc::downgrade(&src.bufs)});
+		Ok(src)
+	}
+
+
+	/// `alGenSources()`
+	/// Create a `Sink` playing interleaved `f32` audio in `format` at `freq` through a new streaming source.
+	pub fn sink(&self, format: Format, freq: u32) -> AltoResult<Sink> {
+		Sink::new(self, format, freq)
+	}
+
+
+	/// `alSourceUnqueueBuffers()`
+	/// Performs routine upkeep of every streaming source created from this context.
+	/// Processed buffers are unqueued and held until retrieved with `StreamingSource::unqueue_buffer`,
+	/// and any source that has stopped while it still has buffers queued is restarted.
+	pub fn process_streams(&self) -> AltoResult<()> {
+		let mut streams = self.0.streams.lock();
+		streams.retain(|e| e.src.upgrade().is_some() && e.bufs.upgrade().is_some());
+
+		for entry in streams.iter() {
+			if let (Some(src), Some(bufs)) = (entry.src.upgrade(), entry.bufs.upgrade()) {
+				let mut bufs = bufs.lock();
+				let processed = {
+					let _lock = self.make_current(true);
+					let mut value = 0;
+					unsafe { self.0.dev.0.alto.0.api.alGetSourcei(src.src, sys::AL_BUFFERS_PROCESSED, &mut value); }
+					let mut ids = vec![0; value as usize];
+					if value > 0 {
+						unsafe { self.0.dev.0.alto.0.api.alSourceUnqueueBuffers(src.src, value, ids.as_mut_ptr()); }
+						record!(self.0, r => Call::SourceUnqueueBuffers{src: r.source(src.src), count: value as u32});
+					}
+					self.get_error()?;
+					ids.len()
+				};
+
+				for _ in 0 .. processed {
+					if let Some(buf) = bufs.queued.pop_front() {
+						bufs.unqueued_nanos += buffer_nanos(buf.len, buf.freq);
+						bufs.processed.push_back(buf);
+					}
+				}
+
+				if !bufs.queued.is_empty() && src.state() == SourceState::Stopped {
+					src.play();
+					self.0.underruns.fetch_add(1, Ordering::Relaxed);
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+
+	/// Begin recording the AL calls made through this context to a log at `path`, replacing any recording in progress.
+	/// If `embed_data` is set, buffer payloads are stored in full rather than as a hash.
+	/// See the `replay` module for what is captured.
+	#[cfg(feature = "replay")]
+	pub fn start_recording<P: AsRef<::std::path::Path>>(&self, path: P, embed_data: bool) -> AltoResult<()> {
+		let rec = replay::Recorder::create(path.as_ref(), embed_data)?;
+		let prev = self.0.recording.lock().replace(rec);
+		prev.map_or(Ok(()), |r| r.close())
+	}
+	/// Finish the recording in progress, if any, returning the first IO error encountered while writing it.
+	#[cfg(feature = "replay")]
+	pub fn stop_recording(&self) -> AltoResult<()> {
+		let rec = self.0.recording.lock().take();
+		rec.map_or(Ok(()), |r| r.close())
+	}
+
+
+	/// `alGetSourcei(AL_SOURCE_STATE)`
+	/// Count the objects alive in this context.
+	/// Source states are queried on each call, while buffer sizes are tracked as data is uploaded,
+	/// so this is cheap enough to call every frame.
+	pub fn stats(&self) -> ContextStats {
+		let sources: Vec<_> = self.0.sources.lock().iter().filter_map(|s| s.upgrade()).collect();
+
+		let mut stats = ContextStats{sources: sources.len(), ..ContextStats::default()};
+		for src in &sources {
+			match src.state() {
+				SourceState::Initial => stats.initial += 1,
+				SourceState::Playing => stats.playing += 1,
+				SourceState::Paused => stats.paused += 1,
+				SourceState::Stopped => stats.stopped += 1,
+				SourceState::Unknown(..) => (),
+			}
+		}
+
+		{
+			let totals = self.0.buffered.lock();
+			stats.buffered_bytes = totals.bytes;
+			stats.buffered_secs = totals.nanos as f64 / 1_000_000_000.0;
+		}
+		stats.buffers = self.0.buffers.lock().len();
+		stats.aux_slots = self.0.slots.load(Ordering::Relaxed);
+		stats.effects = self.0.effects.load(Ordering::Relaxed);
+		stats.underruns = self.0.underruns.load(Ordering::Relaxed);
+		stats
+	}
+
+
+	/// Find how many sources this context can really hold, up to `ceiling`, by generating them in
+	/// exponentially growing batches until generation fails, then narrowing down on the exact limit.
+	/// On a loopback context the sources are also started, to count how many can play at once.
+	/// Everything the probe creates is deleted before it returns, on error paths included.
+	/// This can take a while and is intended for startup or an options screen, not for use during play.
+	pub fn probe_max_sources(&mut self, ceiling: usize) -> AltoResult<ProbeResult> {
+		let buf = if self.0.dev.0.loopback {
+			Some(self.new_buffer(&[Mono{center: 0i16}; 64][..], 44_100)?)
+		} else {
+			None
+		};
+		let mut probe = SourceProbe{ctx: self, srcs: Vec::new()};
+
+		let _lock = self.make_current(true);
+		let api = &self.0.dev.0.alto.0.api;
+		if self.0.destroyed.load(Ordering::SeqCst) {
+			return Err(AltoError::ShutDown);
+		}
+		unsafe { api.alGetError(); }
+
+		let mut batch = 1;
+		let mut narrowing = false;
+		while probe.srcs.len() < ceiling {
+			let n = cmp::min(batch, ceiling - probe.srcs.len());
+			let mut names = vec![0; n];
+			unsafe { api.alGenSources(n as sys::ALsizei, names.as_mut_ptr()); }
+			if unsafe { api.alGetError() } == sys::AL_NO_ERROR {
+				#[cfg(feature = "replay")]
+				for &src in &names {
+					record!(self.0, r => Call::GenSource{src: r.gen_source(src)});
+				}
+				probe.srcs.extend(names);
+				if !narrowing {
+					batch = n * 2;
+				}
+			} else if n > 1 {
+				narrowing = true;
+				batch = n / 2;
+			} else {
+				break;
+			}
+		}
+
+		let max_playing = buf.as_ref().map(|buf| {
+			let mut playing = 0;
+			for &src in &probe.srcs {
+				unsafe {
+					api.alSourcei(src, sys::AL_BUFFER, buf.as_raw() as sys::ALint);
+					record!(self.0, r => Call::SourceBuffer{src: r.source(src), buf: Some(r.buffer(buf.as_raw()))});
+					api.alSourcei(src, sys::AL_LOOPING, sys::AL_TRUE as sys::ALint);
+					record!(self.0, r => Call::Sourcei{src: r.source(src), param: sys::AL_LOOPING, value: sys::AL_TRUE as sys::ALint});
+					api.alSourcePlay(src);
+					record!(self.0, r => Call::SourcePlay{src: r.source(src)});
+					let mut state = 0;
+					api.alGetSourcei(src, sys::AL_SOURCE_STATE, &mut state);
+					if state == sys::AL_PLAYING as sys::ALint {
+						playing += 1;
+					}
+				}
+			}
+			unsafe { api.alGetError(); }
+			playing
+		});
+
+		Ok(ProbeResult{max_sources: probe.srcs.len(), max_playing})
+	}
+
+
+	/// `alGetSourcei(AL_SOURCE_STATE)`
+	/// Number of live sources in this context that are currently playing, for budgeting voices.
+	/// Updates are deferred while the states are read, if `AL_SOFT_deferred_updates` is available,
+	/// so that sources started or stopped alongside each other are seen together.
+	pub fn playing_source_count(&self) -> AltoResult<usize> {
+		let sources: Vec<_> = self.0.sources.lock().iter().filter_map(|s| s.upgrade()).collect();
+
+		let _defer = self.defer_updates();
+		let count = sources.iter().filter(|s| s.state() == SourceState::Playing).count();
+		self.get_error().map(|_| count)
+	}
+
+
+	/// `alSourcePlay()`
+	/// Start every source in `sources` with updates deferred, so that they begin playing together.
+	/// Every source is checked to belong to this context before any is started.
+	pub fn play_all<S: Source>(&self, sources: &mut [S]) -> AltoResult<()> {
+		self.check_sources(sources)?;
+
+		let _defer = self.defer_updates();
+		for src in sources.iter_mut() {
+			src.play();
+		}
+		self.get_error()
+	}
+
+
+	/// `alSourcefv(AL_POSITION)`
+	/// Move each source in `sources` to the corresponding entry of `positions`, with updates deferred.
+	/// Every source is checked to belong to this context, and the lengths to match, before any is moved.
+	pub fn set_positions<S: Source>(&self, sources: &mut [S], positions: &[[f32; 3]]) -> AltoResult<()> {
+		if sources.len() != positions.len() {
+			return Err(AltoError::InvalidValue);
+		}
+		self.check_sources(sources)?;
+
+		let _defer = self.defer_updates();
+		for (src, &pos) in sources.iter_mut().zip(positions) {
+			src.set_position(pos)?;
+		}
+		Ok(())
+	}
+
+
+	fn check_sources<S: Source>(&self, sources: &[S]) -> AltoResult<()> {
+		for src in sources {
+			self.check_context(src.context())?;
+		}
+		Ok(())
+	}
+
+
+	/// Pause every playing source until the returned token is resumed or dropped.
+	/// Only sources that were playing are resumed afterwards, so sources paused deliberately stay paused.
+	/// Sources created while paused do not start playing until the last token is resumed,
+	/// at which point any start time passed to `play_at` still applies.
+	/// If this is the only context on an output device with `ALC_SOFT_pause_device`,
+	/// the whole device is paused instead, which also lets the hardware idle.
+	/// Tokens nest, and may be dropped in any order: nothing is resumed until the last one is gone.
+	pub fn pause_all(&mut self) -> PauseToken {
+		let sources: Vec<_> = self.0.sources.lock().iter().filter_map(|s| s.upgrade()).collect();
+		let device = {
+			let mut state = self.0.pause.lock();
+			if state.depth == 0 {
+				state.device = !self.0.dev.0.loopback && self.0.dev.0.contexts.lock().iter().filter(|c| Weak::strong_count(c) > 0).count() == 1 && (|| -> AltoResult<_> {
+					self.0.dev.0.check_closed()?;
+					let adps = self.0.dev.0.exts.ALC_SOFT_pause_device()?.alcDevicePauseSOFT?;
+					self.0.dev.0.exts.ALC_SOFT_pause_device()?.alcDeviceResumeSOFT?;
+					unsafe { adps(self.0.dev.0.dev); }
+					self.0.dev.0.alto.get_error(self.0.dev.0.dev)
+				})().is_ok();
+			}
+			state.depth += 1;
+			state.device
+		};
+
+		if !device {
+			let mut paused = Vec::new();
+			for src in &sources {
+				if src.state() == SourceState::Playing {
+					src.pause();
+					paused.push(Arc::downgrade(src));
+				}
+			}
+			self.0.pause.lock().paused.extend(paused);
+		}
+		PauseToken{ctx: self.clone()}
 	}
 
 
@@ -631,24 +1424,80 @@ impl Context {
 	}
 
 
-	pub(crate) fn make_current(&self, set: bool) -> Option<MutexGuard<()>> {
-		Context::make_raw_current(&self.0.dev, if set { self.0.ctx } else { ptr::null_mut() })
+	pub(crate) fn make_current(&self, set: bool) -> CurrentLock {
+		// Recursive, as a writer waiting in `Alto::shutdown` must not block a thread that already has the context current.
+		let live = self.0.live.read_recursive();
+		let set = set && !self.0.destroyed.load(Ordering::SeqCst);
+		CurrentLock{_global: Context::make_raw_current(&self.0.dev, if set { self.0.ctx } else { ptr::null_mut() }), _live: live}
 	}
 
 
-	fn make_raw_current(dev: &Device, ctx: *mut sys::ALCcontext) -> Option<MutexGuard<()>> {
+	fn make_raw_current(dev: &Device, ctx: *mut sys::ALCcontext) -> Option<MutexGuard<'static, ()>> {
 		if let Ok(&ext::ALC_EXT_thread_local_context{alcSetThreadContext: Ok(astc), ..}) = dev.0.alto.0.exts.ALC_EXT_thread_local_context() {
 			unsafe { astc(ctx); }
 			None
 		} else {
+			let lock = ALTO_CTX_LOCK.lock();
 			unsafe { dev.0.alto.0.api.alcMakeContextCurrent(ctx); }
-			Some(ALTO_CTX_LOCK.lock())
+			Some(lock)
+		}
+	}
+
+
+	/// Ensure a resource owned by `other` may be used with objects of this context.
+	pub(crate) fn check_context(&self, other: &Context) -> AltoResult<()> {
+		if *other != *self {
+			Err(AltoError::WrongContext{expected: self.0.id, found: other.0.id})
+		} else {
+			Ok(())
+		}
+	}
+
+
+	/// Ensure a device shared resource owned by `other` may be used with objects of this context.
+	pub(crate) fn check_device(&self, other: &Context) -> AltoResult<()> {
+		if other.0.dev.0.id != self.0.dev.0.id {
+			Err(AltoError::WrongDevice)
+		} else {
+			Ok(())
+		}
+	}
+
+
+	/// Check that `handle` still refers to the object it was taken from,
+	/// returning `AltoError::StaleHandle` if that object has been deleted.
+	/// Buffers, effects and filters may come from any context on this device, other objects only from this context.
+	pub fn check_handle(&self, handle: RawHandle) -> AltoResult<()> {
+		if handle.kind.is_device_shared() {
+			if handle.owner != self.0.dev.0.id {
+				return Err(AltoError::WrongDevice);
+			}
+		} else if handle.owner != self.0.id {
+			return Err(AltoError::WrongContext{expected: self.0.id, found: handle.owner});
+		}
+
+		if self.0.generation(handle.kind, handle.name) == handle.generation {
+			Ok(())
+		} else {
+			Err(AltoError::StaleHandle)
 		}
 	}
 
 
 	pub(crate) fn get_error(&self) -> AltoResult<()> {
-		match unsafe { self.0.dev.0.alto.0.api.alGetError() } {
+		if self.0.destroyed.load(Ordering::SeqCst) {
+			return Err(AltoError::ShutDown);
+		}
+
+		let error = unsafe { self.0.dev.0.alto.0.api.alGetError() };
+		#[cfg(feature = "replay")]
+		{
+			if let Some(ref mut r) = *self.0.recording.lock() {
+				r.finish(error);
+			}
+		}
+
+		match error {
 			sys::AL_NO_ERROR => Ok(()),
 			e => Err(AltoError::from_al(e))
 		}
@@ -656,6 +1505,19 @@ impl Context {
 }
 
 
+impl fmt::Display for ContextStats {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{} sources ({} initial, {} playing, {} paused, {} stopped), {} buffers ({} bytes, {:.2}s), {} slots, {} effects, {} underruns",
+			self.sources, self.initial, self.playing, self.paused, self.stopped,
+			self.buffers, self.buffered_bytes, self.buffered_secs,
+			self.aux_slots, self.effects, self.underruns,
+		)
+	}
+}
+
+
 impl Clone for Context {
 	fn clone(&self) -> Context { Context(self.0.clone()) }
 }
@@ -669,18 +1531,164 @@ impl PartialEq for Context {
 impl Eq for Context { }
 
 
+impl ContextInner {
+	/// Stop every source and destroy the context on behalf of `Alto::shutdown`.
+	/// Their names are deleted first, since buffers outlive the context on the device.
+	pub(crate) fn shut_down(&self, report: &mut ShutdownReport) {
+		let sources: Vec<_> = self.sources.lock().drain(..).filter_map(|s| s.upgrade()).collect();
+		let buffers: Vec<_> = self.buffers.lock().drain().collect();
+		// Released before `sources`, whose drop may make the context current.
+		let _live = self.live.write();
+		{
+			let _lock = Context::make_raw_current(&self.dev, self.ctx);
+			for src in &sources {
+				unsafe { self.dev.0.alto.0.api.alSourceStop(src.src); }
+			}
+			let names: Vec<_> = sources.iter().map(|s| s.src).collect();
+			self.delete_names(&names, &buffers);
+		}
+		report.sources += sources.len();
+		report.buffers += buffers.len();
+
+		let _lock = Context::make_raw_current(&self.dev, ptr::null_mut());
+		if !self.destroyed.swap(true, Ordering::SeqCst) {
+			unsafe { self.dev.0.alto.0.api.alcDestroyContext(self.ctx); }
+		}
+	}
+
+
+	/// Generation of a name, to be stored by a handle when its object is created.
+	pub(crate) fn generation(&self, kind: ObjectKind, name: sys::ALuint) -> u32 {
+		self.generations_of(kind).lock().get(&(kind, name)).cloned().unwrap_or(0)
+	}
+	/// Bump the generation of a name after deleting it, invalidating any handle to the deleted object.
+	pub(crate) fn retire_name(&self, kind: ObjectKind, name: sys::ALuint) {
+		let mut generations = self.generations_of(kind).lock();
+		let generation = generations.entry((kind, name)).or_insert(0);
+		*generation = generation.wrapping_add(1);
+	}
+	// Buffers, effects and filters are named per device, so a name deleted through one context
+	// can be handed out again through another on the same device.
+	fn generations_of(&self, kind: ObjectKind) -> &Mutex<HashMap<(ObjectKind, sys::ALuint), u32>> {
+		if kind.is_device_shared() { &self.dev.0.generations } else { &self.generations }
+	}
+
+
+	/// Delete source and buffer names that are still tracked. The context must be current.
+	/// Sources go first so that no buffer is still attached when it is deleted.
+	fn delete_names(&self, sources: &[sys::ALuint], buffers: &[sys::ALuint]) {
+		let api = &self.dev.0.alto.0.api;
+		if !sources.is_empty() {
+			unsafe { api.alDeleteSources(sources.len() as sys::ALsizei, sources.as_ptr()); }
+		}
+		if !buffers.is_empty() {
+			unsafe { api.alDeleteBuffers(buffers.len() as sys::ALsizei, buffers.as_ptr()); }
+		}
+		unsafe { api.alGetError(); }
+		for &src in sources {
+			self.retire_name(ObjectKind::Source, src);
+		}
+		for &buf in buffers {
+			self.retire_name(ObjectKind::Buffer, buf);
+		}
+
+		#[cfg(feature = "replay")]
+		{
+			for &src in sources {
+				record!(self, r => Call::DeleteSource{src: r.delete_source(src)});
+			}
+			for &buf in buffers {
+				record!(self, r => Call::DeleteBuffer{buf: r.delete_buffer(buf)});
+			}
+		}
+	}
+}
+
+
 impl Drop for ContextInner {
 	fn drop(&mut self) {
+		if self.destroyed.load(Ordering::SeqCst) {
+			return;
+		}
+
 		let _lock = Context::make_raw_current(&self.dev, ptr::null_mut());
 		unsafe { self.dev.0.alto.0.api.alcDestroyContext(self.ctx); }
 	}
 }
 
 
+unsafe impl Send for ContextInner { }
+unsafe impl Sync for ContextInner { }
+
 unsafe impl Send for Context { }
 unsafe impl Sync for Context { }
 
 
+impl PauseToken {
+	/// The context this token is keeping paused.
+	pub fn context(&self) -> &Context { &self.ctx }
+
+
+	/// Resume the sources that were playing when the outstanding tokens were created, if this is the last of them.
+	/// Dropping the token has the same effect.
+	pub fn resume(self) { }
+}
+
+
+impl Drop for PauseToken {
+	fn drop(&mut self) {
+		let (paused, born, device) = {
+			let mut state = self.ctx.0.pause.lock();
+			state.depth -= 1;
+			if state.depth == 0 {
+				(mem::take(&mut state.paused), mem::take(&mut state.born), mem::take(&mut state.device))
+			} else {
+				return;
+			}
+		};
+
+		for src in paused.into_iter().filter_map(|s| s.upgrade()) {
+			if src.state() == SourceState::Paused {
+				src.play();
+			}
+		}
+		for (src, play) in born {
+			if let Some(src) = src.upgrade() {
+				match play {
+					DeferredPlay::Idle => (),
+					DeferredPlay::Now => src.play(),
+					DeferredPlay::At(device_clock_ns) => { let _ = src.play_at(device_clock_ns); },
+				}
+			}
+		}
+		if device && self.ctx.0.dev.0.check_closed().is_ok() {
+			if let Ok(&ext::ALC_SOFT_pause_device{alcDeviceResumeSOFT: Ok(adrs), ..}) = self.ctx.0.dev.0.exts.ALC_SOFT_pause_device() {
+				unsafe { adrs(self.ctx.0.dev.0.dev); }
+			}
+		}
+	}
+}
+
+
+impl<'c> Drop for SourceProbe<'c> {
+	fn drop(&mut self) {
+		if self.srcs.is_empty() || self.ctx.0.destroyed.load(Ordering::SeqCst) {
+			return;
+		}
+
+		let _lock = self.ctx.make_current(true);
+		unsafe {
+			self.ctx.0.dev.0.alto.0.api.alDeleteSources(self.srcs.len() as sys::ALsizei, self.srcs.as_ptr());
+			self.ctx.0.dev.0.alto.0.api.alGetError();
+		}
+		#[cfg(feature = "replay")]
+		for &src in &self.srcs {
+			record!(self.ctx.0, r => Call::DeleteSource{src: r.delete_source(src)});
+		}
+	}
+}
+
+
 impl<'c> DeferLock<'c> {
 	fn new(ctx: &'c Context) -> DeferLock {
 		let _ = (|| -> AltoResult<_> {
@@ -727,77 +1735,158 @@ impl<'c> Drop for DeferLock<'c> {
 
 impl Buffer {
 	pub(crate) fn new<F: SampleFrame, B: AsBufferData<F>>(ctx: Context, data: B, freq: i32) -> AltoResult<Buffer> {
+		let (data, size) = data.as_buffer_data();
+		Buffer::validate_data::<F>(&ctx, size)?;
+		let mut buf = Buffer::new_empty(ctx)?;
+		buf.upload_data::<F>(data, size, freq).map(|_| buf)
+	}
+
+
+	/// Generate a buffer without uploading anything to it, for callers that fill it before first use.
+	pub(crate) fn new_empty(ctx: Context) -> AltoResult<Buffer> {
 		let mut buf = 0;
 		{
 			let _lock = ctx.make_current(true);
 			unsafe { ctx.0.dev.0.alto.0.api.alGenBuffers(1, &mut buf as *mut sys::ALuint); }
+			record!(ctx.0, r => Call::GenBuffer{buf: r.gen_buffer(buf)});
 			ctx.get_error()?;
 		}
-		let mut buf = Buffer{ctx: ctx, buf: buf, len: 0};
-		buf.set_data(data, freq).map(|_| buf)
+		ctx.0.buffers.lock().insert(buf);
+		Ok(Buffer{gen: ctx.0.generation(ObjectKind::Buffer, buf), ctx: ctx, buf: buf, len: 0, size: 0, freq: 0, channels: 0, bits: 0})
 	}
 
 
 	/// Context from which this buffer was created.
 	pub fn context(&self) -> &Context { &self.ctx }
 	/// Raw handle as provided by OpenAL.
+	/// The name is handed out again once this buffer is dropped, so keep a `raw_handle` instead to be able to tell.
 	pub fn as_raw(&self) -> sys::ALuint { self.buf }
+	/// Name and generation of this buffer, which `Context::check_handle` rejects once the buffer is dropped.
+	pub fn raw_handle(&self) -> RawHandle { RawHandle{kind: ObjectKind::Buffer, name: self.buf, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 
 
 	/// `alBufferData()`
+	/// Packed 24-bit data is uploaded with `alBufferSamplesSOFT()` instead, and requires `AL_SOFT_buffer_samples`.
+	/// The data is checked before uploading, and `AltoError::InvalidBufferData` names the requirement it failed.
 	pub fn set_data<F: SampleFrame, B: AsBufferData<F>>(&mut self, data: B, freq: sys::ALint) -> AltoResult<()> {
 		let (data, size) = data.as_buffer_data();
-		if (sys::ALsizei::max_value() as usize) < size { return Err(AltoError::InvalidValue) }
+		Buffer::validate_data::<F>(&self.ctx, size)?;
+		self.upload_data::<F>(data, size, freq)
+	}
 
-		let _lock = self.ctx.make_current(true);
-		unsafe {
-			self.ctx.0.dev.0.alto.0.api.alBufferData(
-				self.buf,
-				F::format().into_raw(Some(&self.ctx))?,
-				data,
-				size as sys::ALsizei,
-				freq,
-			);
+
+	/// Upload `size` bytes of `F` frames that have already passed `validate_data`.
+	fn upload_data<F: SampleFrame>(&mut self, data: *const sys::ALvoid, size: usize, freq: sys::ALint) -> AltoResult<()> {
+		if let Format::SoftByte3(f) = F::format() {
+			return self.set_data_byte3(f, data, size, freq);
+		}
+
+		{
+			let format = F::format().into_raw(Some(&self.ctx))?;
+			let _lock = self.ctx.make_current(true);
+			unsafe {
+				self.ctx.0.dev.0.alto.0.api.alBufferData(
+					self.buf,
+					format,
+					data,
+					size as sys::ALsizei,
+					freq,
+				);
+			}
+			record!(self.ctx.0, r => r.buffer_data(self.buf, format, data, size, freq));
+			self.ctx.get_error()?;
 		}
-		self.ctx.get_error()?;
 
-		self.len = (size as usize / mem::size_of::<F::Sample>() / F::len()) as sys::ALsizei;
+		let len = (size as usize / mem::size_of::<F::Sample>() / F::len()) as sys::ALsizei;
+		self.set_cached(len, size, freq, F::len() as sys::ALint, (mem::size_of::<F::Sample>() * 8) as sys::ALint);
 		Ok(())
 	}
 
 
-	/// `alGetBufferi(AL_FREQUENCY)`
-	pub fn frequency(&self) -> sys::ALint {
-		let _lock = self.ctx.make_current(true);
-		let mut value = 0;
-		unsafe { self.ctx.0.dev.0.alto.0.api.alGetBufferi(self.buf, sys::AL_FREQUENCY, &mut value); }
-		value
+	/// Check `size` bytes of `F` frames against what `alBufferData` requires, and that the format is supported.
+	fn validate_data<F: SampleFrame>(ctx: &Context, size: usize) -> AltoResult<()> {
+		if size == 0 {
+			return Err(AltoError::InvalidBufferData(BufferConstraint::Empty));
+		}
+		let frame_size = mem::size_of::<F>();
+		if !size.is_multiple_of(frame_size) {
+			return Err(AltoError::InvalidBufferData(BufferConstraint::PartialFrame{size, frame_size}));
+		}
+		if (sys::ALsizei::MAX as usize) < size {
+			return Err(AltoError::InvalidBufferData(BufferConstraint::TooLarge{size}));
+		}
+
+		match F::format() {
+			Format::SoftByte3(f) => { f.internal_raw(ctx)?; },
+			format => { format.into_raw(Some(ctx))?; },
+		}
+		Ok(())
 	}
 
 
-	/// `alGetBufferi(AL_BITS)`
-	pub fn bits(&self) -> sys::ALint {
-		let _lock = self.ctx.make_current(true);
-		let mut value = 0;
-		unsafe { self.ctx.0.dev.0.alto.0.api.alGetBufferi(self.buf, sys::AL_BITS, &mut value); }
-		value
+	/// `alBufferSamplesSOFT()`
+	fn set_data_byte3(&mut self, format: SoftByte3Format, data: *const sys::ALvoid, size: usize, freq: sys::ALint) -> AltoResult<()> {
+		let asbs = self.ctx.0.exts.AL_SOFT_buffer_samples()?;
+		let absc = asbs.alBufferSamplesSOFT?;
+		let (ifmt, chans) = (format.internal_raw(&self.ctx)?, format.channels_raw(&self.ctx)?);
+
+		let frames = Format::SoftByte3(format).frames_in(size)? as sys::ALsizei;
+
+		{
+			let _lock = self.ctx.make_current(true);
+			let ty = asbs.AL_BYTE3_SOFT?;
+			unsafe { absc(self.buf, freq as sys::ALuint, ifmt, frames, chans, ty, data); }
+			record!(self.ctx.0, r => r.buffer_samples(self.buf, freq as u32, ifmt, frames as u32, chans, ty, data, size));
+			self.ctx.get_error()?;
+		}
+
+		let channels = match format { SoftByte3Format::Mono24 => 1, SoftByte3Format::Stereo24 => 2 };
+		self.set_cached(frames, size, freq, channels, 24);
+		Ok(())
 	}
 
 
-	/// `alGetBufferi(AL_CHANNELS)`
-	pub fn channels(&self) -> sys::ALint {
-		let _lock = self.ctx.make_current(true);
-		let mut value = 0;
-		unsafe { self.ctx.0.dev.0.alto.0.api.alGetBufferi(self.buf, sys::AL_CHANNELS, &mut value); }
-		value
+	/// Record the shape of newly uploaded data, keeping the context totals in step.
+	fn set_cached(&mut self, len: sys::ALsizei, size: usize, freq: sys::ALint, channels: sys::ALint, bits: sys::ALint) {
+		let mut totals = self.ctx.0.buffered.lock();
+		totals.bytes = totals.bytes - self.size as u64 + size as u64;
+		totals.nanos = totals.nanos - buffer_nanos(self.len, self.freq) + buffer_nanos(len, freq);
+
+		self.len = len;
+		self.size = size;
+		self.freq = freq;
+		self.channels = channels;
+		self.bits = bits;
 	}
 
 
+	/// `alGetBufferi(AL_FREQUENCY)`
+	/// Cached when data is uploaded, so no call into OpenAL is made.
+	pub fn frequency(&self) -> sys::ALint { self.freq }
+
+
+	/// `alGetBufferi(AL_BITS)`
+	/// Cached when data is uploaded. This is the sample size as uploaded,
+	/// which may differ from what the implementation converted it to internally.
+	pub fn bits(&self) -> sys::ALint { self.bits }
+
+
+	/// `alGetBufferi(AL_CHANNELS)`
+	/// Cached when data is uploaded, so no call into OpenAL is made.
+	pub fn channels(&self) -> sys::ALint { self.channels }
+
+
 	/// `alGetBufferi(AL_SIZE)`
-	pub fn size(&self) -> sys::ALint {
+	/// Cached when data is uploaded. This is the size in bytes as uploaded,
+	/// which may differ from what the implementation converted it to internally.
+	pub fn size(&self) -> sys::ALint { self.size as sys::ALint }
+
+
+	/// `alGetBufferi()`, bypassing the cache to see the implementation's internal storage.
+	fn query_i(&self, param: sys::ALenum) -> sys::ALint {
 		let _lock = self.ctx.make_current(true);
 		let mut value = 0;
-		unsafe { self.ctx.0.dev.0.alto.0.api.alGetBufferi(self.buf, sys::AL_SIZE, &mut value); }
+		unsafe { self.ctx.0.dev.0.alto.0.api.alGetBufferi(self.buf, param, &mut value); }
 		value
 	}
 
@@ -817,55 +1906,291 @@ impl Buffer {
 	pub fn set_soft_loop_points(&self, value: (sys::ALint, sys::ALint)) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alBufferiv(self.buf, self.ctx.0.exts.AL_SOFT_loop_points()?.AL_LOOP_POINTS_SOFT?, &[value.0, value.1] as *const [sys::ALint; 2] as *const sys::ALint); }
+		record!(self.ctx.0, r => Call::Bufferiv{buf: r.buffer(self.buf), param: self.ctx.0.exts.AL_SOFT_loop_points()?.AL_LOOP_POINTS_SOFT?, values: vec![value.0, value.1]});
+		self.ctx.get_error()
+	}
+	/// `alBufferiv(AL_LOOP_POINTS_SOFT)`
+	/// Like `set_soft_loop_points`, but the points are read back afterwards with `loop_points`,
+	/// and an `InvalidValue` is returned if the implementation did not keep them as given.
+	/// Requires `AL_SOFT_loop_points`
+	pub fn set_soft_loop_points_verified(&self, value: (sys::ALint, sys::ALint)) -> AltoResult<()> {
+		self.set_soft_loop_points(value)?;
+		Buffer::verify_loop_points(value, self.loop_points()?)
+	}
+	/// `alGetBufferiv(AL_LOOP_POINTS_SOFT)`
+	/// Like `soft_loop_points`, but a missing extension or an error from the query is reported rather than
+	/// replaced with the whole buffer.
+	/// Requires `AL_SOFT_loop_points`
+	pub fn loop_points(&self) -> AltoResult<(sys::ALint, sys::ALint)> {
+		let param = self.ctx.0.exts.AL_SOFT_loop_points()?.AL_LOOP_POINTS_SOFT?;
+		let _lock = self.ctx.make_current(true);
+		let mut value = [0, 0];
+		unsafe { self.ctx.0.dev.0.alto.0.api.alGetBufferiv(self.buf, param, &mut value as *mut [sys::ALint; 2] as *mut sys::ALint) }
+		self.ctx.get_error().map(|_| (value[0], value[1]))
+	}
+	/// Check loop points read back from a buffer against those that were set,
+	/// as done by `set_soft_loop_points_verified`. A mismatch is an `InvalidValue`.
+	pub(crate) fn verify_loop_points(requested: (sys::ALint, sys::ALint), actual: (sys::ALint, sys::ALint)) -> AltoResult<()> {
+		if requested == actual { Ok(()) } else { Err(AltoError::InvalidValue) }
+	}
+
+
+	/// `EAXGetBufferMode()`
+	/// Requires `EAX-RAM`
+	pub fn storage_mode(&self) -> AltoResult<XRamStorage> {
+		let xram = self.ctx.0.exts.EAX_RAM()?;
+		let _lock = self.ctx.make_current(true);
+		let value = unsafe { xram.EAXGetBufferMode?(self.buf, ptr::null_mut()) };
+		self.ctx.get_error()?;
+
+		Ok(match value {
+			v if v == xram.AL_STORAGE_AUTOMATIC? => XRamStorage::Automatic,
+			v if v == xram.AL_STORAGE_HARDWARE? => XRamStorage::Hardware,
+			v if v == xram.AL_STORAGE_ACCESSIBLE? => XRamStorage::Accessible,
+			v => XRamStorage::Unknown(v),
+		})
+	}
+	/// `EAXSetBufferMode()`
+	/// Requires `EAX-RAM`
+	/// Must be called before any data is stored in the buffer for the mode to take effect.
+	pub fn set_storage_mode(&mut self, value: XRamStorage) -> AltoResult<()> {
+		let xram = self.ctx.0.exts.EAX_RAM()?;
+		let value = match value {
+			XRamStorage::Automatic => xram.AL_STORAGE_AUTOMATIC?,
+			XRamStorage::Hardware => xram.AL_STORAGE_HARDWARE?,
+			XRamStorage::Accessible => xram.AL_STORAGE_ACCESSIBLE?,
+			XRamStorage::Unknown(v) => v,
+		};
+
+		let _lock = self.ctx.make_current(true);
+		let res = unsafe { xram.EAXSetBufferMode?(1, &self.buf, value as sys::ALint) };
+		record!(self.ctx.0, r => Call::BufferMode{buf: r.buffer(self.buf), value: value as sys::ALint});
+		self.ctx.get_error()?;
+		if res == sys::AL_TRUE { Ok(()) } else { Err(AltoError::InvalidOperation) }
+	}
+
+
+	/// `alGetBufferSamplesSOFT()`
+	/// Read `out.len()` frames starting at frame `offset`, converted to the sample type of `S`.
+	/// `S` must be a plain PCM frame with as many channels as the buffer.
+	/// Requires `AL_SOFT_buffer_samples`
+	pub fn get_samples<S: SampleFrame>(&self, offset: usize, out: &mut [S]) -> AltoResult<()> {
+		let asbs = self.ctx.0.exts.AL_SOFT_buffer_samples()?;
+		let agbs = asbs.alGetBufferSamplesSOFT?;
+		match S::format() {
+			Format::Standard(..) | Format::ExtFloat32(..) | Format::ExtMcFormats(..) => (),
+			_ => return Err(AltoError::InvalidValue),
+		}
+		let (chans_enum, ty_enum, _) = buffer_samples_enums(asbs, S::len() as sys::ALint, (mem::size_of::<S::Sample>() * 8) as sys::ALint)?;
+
+		if offset.checked_add(out.len()).is_none_or(|end| end > self.len as usize) {
+			return Err(AltoError::InvalidValue);
+		}
+		if out.is_empty() {
+			return Ok(());
+		}
+
+		let _lock = self.ctx.make_current(true);
+		unsafe { agbs(self.buf, offset as sys::ALint, out.len() as sys::ALsizei, chans_enum, ty_enum, out.as_mut_ptr() as *mut sys::ALvoid); }
 		self.ctx.get_error()
 	}
+
+
+	/// Create a new, independent buffer in `ctx` holding a copy of this buffer's data.
+	/// OpenAL has no native buffer copy, so the samples are read back with `alGetBufferSamplesSOFT()`
+	/// and uploaded again with `alBufferSamplesSOFT()`.
+	/// Requires `AL_SOFT_buffer_samples`
+	pub fn duplicate(&self, ctx: &Context) -> AltoResult<Buffer> {
+		ctx.check_device(&self.ctx)?;
+
+		let asbs = self.ctx.0.exts.AL_SOFT_buffer_samples()?;
+		let agbs = asbs.alGetBufferSamplesSOFT?;
+		let (freq, channels, bits, size) = (self.freq, self.query_i(sys::AL_CHANNELS), self.query_i(sys::AL_BITS), self.query_i(sys::AL_SIZE));
+
+		let (chans_enum, ty_enum, ifmt_enum) = buffer_samples_enums(asbs, channels, bits)?;
+
+		let frame_size = (channels * bits / 8) as usize;
+		let frames = size as usize / frame_size;
+		let mut data = vec![0u8; frames * frame_size];
+		if frames > 0 {
+			let _lock = self.ctx.make_current(true);
+			unsafe { agbs(self.buf, 0, frames as sys::ALsizei, chans_enum, ty_enum, data.as_mut_ptr() as *mut sys::ALvoid); }
+			self.ctx.get_error()?;
+		}
+
+		let absc = ctx.0.exts.AL_SOFT_buffer_samples()?.alBufferSamplesSOFT?;
+		let mut buf = 0;
+		{
+			let _lock = ctx.make_current(true);
+			unsafe { ctx.0.dev.0.alto.0.api.alGenBuffers(1, &mut buf as *mut sys::ALuint); }
+			record!(ctx.0, r => Call::GenBuffer{buf: r.gen_buffer(buf)});
+			ctx.get_error()?;
+		}
+		ctx.0.buffers.lock().insert(buf);
+		let mut buf = Buffer{ctx: ctx.clone(), buf, gen: ctx.0.generation(ObjectKind::Buffer, buf), len: 0, size: 0, freq: 0, channels: 0, bits: 0};
+
+		{
+			let _lock = ctx.make_current(true);
+			unsafe { absc(buf.buf, freq as sys::ALuint, ifmt_enum, frames as sys::ALsizei, chans_enum, ty_enum, data.as_ptr() as *const sys::ALvoid); }
+			record!(ctx.0, r => r.buffer_samples(buf.buf, freq as u32, ifmt_enum, frames as u32, chans_enum, ty_enum, data.as_ptr() as *const sys::ALvoid, data.len()));
+			ctx.get_error()?;
+		}
+		buf.set_cached(frames as sys::ALsizei, data.len(), freq, channels, bits);
+		Ok(buf)
+	}
+}
+
+
+/// Channel, sample type and internal format enums of `AL_SOFT_buffer_samples` for PCM data with the given layout.
+fn buffer_samples_enums(asbs: &ext::AL_SOFT_buffer_samples, channels: sys::ALint, bits: sys::ALint) -> AltoResult<(sys::ALenum, sys::ALenum, sys::ALenum)> {
+	match (channels, bits) {
+		(1, 8) => Ok((asbs.AL_MONO_SOFT?, asbs.AL_UNSIGNED_BYTE_SOFT?, asbs.AL_MONO8_SOFT?)),
+		(1, 16) => Ok((asbs.AL_MONO_SOFT?, asbs.AL_SHORT_SOFT?, asbs.AL_MONO16_SOFT?)),
+		(1, 32) => Ok((asbs.AL_MONO_SOFT?, asbs.AL_FLOAT_SOFT?, asbs.AL_MONO32F_SOFT?)),
+		(2, 8) => Ok((asbs.AL_STEREO_SOFT?, asbs.AL_UNSIGNED_BYTE_SOFT?, asbs.AL_STEREO8_SOFT?)),
+		(2, 16) => Ok((asbs.AL_STEREO_SOFT?, asbs.AL_SHORT_SOFT?, asbs.AL_STEREO16_SOFT?)),
+		(2, 32) => Ok((asbs.AL_STEREO_SOFT?, asbs.AL_FLOAT_SOFT?, asbs.AL_STEREO32F_SOFT?)),
+		(4, 8) => Ok((asbs.AL_QUAD_SOFT?, asbs.AL_UNSIGNED_BYTE_SOFT?, asbs.AL_QUAD8_SOFT?)),
+		(4, 16) => Ok((asbs.AL_QUAD_SOFT?, asbs.AL_SHORT_SOFT?, asbs.AL_QUAD16_SOFT?)),
+		(4, 32) => Ok((asbs.AL_QUAD_SOFT?, asbs.AL_FLOAT_SOFT?, asbs.AL_QUAD32F_SOFT?)),
+		(6, 8) => Ok((asbs.AL_5POINT1_SOFT?, asbs.AL_UNSIGNED_BYTE_SOFT?, asbs.AL_5POINT1_8_SOFT?)),
+		(6, 16) => Ok((asbs.AL_5POINT1_SOFT?, asbs.AL_SHORT_SOFT?, asbs.AL_5POINT1_16_SOFT?)),
+		(6, 32) => Ok((asbs.AL_5POINT1_SOFT?, asbs.AL_FLOAT_SOFT?, asbs.AL_5POINT1_32F_SOFT?)),
+		(7, 8) => Ok((asbs.AL_6POINT1_SOFT?, asbs.AL_UNSIGNED_BYTE_SOFT?, asbs.AL_6POINT1_8_SOFT?)),
+		(7, 16) => Ok((asbs.AL_6POINT1_SOFT?, asbs.AL_SHORT_SOFT?, asbs.AL_6POINT1_16_SOFT?)),
+		(7, 32) => Ok((asbs.AL_6POINT1_SOFT?, asbs.AL_FLOAT_SOFT?, asbs.AL_6POINT1_32F_SOFT?)),
+		(8, 8) => Ok((asbs.AL_7POINT1_SOFT?, asbs.AL_UNSIGNED_BYTE_SOFT?, asbs.AL_7POINT1_8_SOFT?)),
+		(8, 16) => Ok((asbs.AL_7POINT1_SOFT?, asbs.AL_SHORT_SOFT?, asbs.AL_7POINT1_16_SOFT?)),
+		(8, 32) => Ok((asbs.AL_7POINT1_SOFT?, asbs.AL_FLOAT_SOFT?, asbs.AL_7POINT1_32F_SOFT?)),
+		_ => Err(AltoError::InvalidValue),
+	}
+}
+
+
+fn buffer_nanos(len: sys::ALsizei, freq: sys::ALint) -> u64 {
+	if freq <= 0 { 0 } else { len as u64 * 1_000_000_000 / freq as u64 }
 }
 
 
 impl Drop for Buffer {
 	fn drop(&mut self) {
-		if self.buf == 0 {
+		self.ctx.0.buffers.lock().remove(&self.buf);
+		{
+			let mut totals = self.ctx.0.buffered.lock();
+			totals.bytes -= self.size as u64;
+			totals.nanos -= buffer_nanos(self.len, self.freq);
+		}
+		if self.buf == 0 || self.ctx.0.destroyed.load(Ordering::SeqCst) {
 			return; 
 		}
 
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alDeleteBuffers(1, &mut self.buf as *mut sys::ALuint); }
+		record!(self.ctx.0, r => Call::DeleteBuffer{buf: r.delete_buffer(self.buf)});
+		self.ctx.0.retire_name(ObjectKind::Buffer, self.buf);
 	}
 }
 
 
 impl SourceInner {
+	fn register(self: &Arc<Self>) {
+		{
+			let mut sources = self.ctx.0.sources.lock();
+			sources.retain(|s| Weak::strong_count(s) > 0);
+			sources.push(Arc::downgrade(self));
+		}
+
+		let mut state = self.ctx.0.pause.lock();
+		if state.depth > 0 && !state.device {
+			state.born.push((Arc::downgrade(self), DeferredPlay::Idle));
+		}
+	}
+
+
+	/// While the context is paused, record whether and when a source created during the pause should
+	/// start once it ends, and report that the request was deferred.
+	fn defer_play(&self, play: DeferredPlay) -> bool {
+		let mut state = self.ctx.0.pause.lock();
+		match state.born.iter_mut().find(|&&mut (ref s, _)| ptr::eq(s.as_ptr(), self)) {
+			Some(&mut (_, ref mut requested)) => { *requested = play; true },
+			None => false,
+		}
+	}
+
+
 	fn context(&self) -> &Context { &self.ctx }
 	pub fn as_raw(&self) -> sys::ALuint { self.src }
+	fn raw_handle(&self) -> RawHandle { RawHandle{kind: ObjectKind::Source, name: self.src, generation: self.gen, owner: self.ctx.0.id} }
 
 
 	fn state(&self) -> SourceState {
 		let _lock = self.ctx.make_current(true);
 		let mut value = 0;
 		unsafe { self.ctx.0.dev.0.alto.0.api.alGetSourcei(self.src, sys::AL_SOURCE_STATE, &mut value); }
-		match value {
-			sys::AL_INITIAL => SourceState::Initial,
-			sys::AL_PLAYING => SourceState::Playing,
-			sys::AL_PAUSED => SourceState::Paused,
-			sys::AL_STOPPED => SourceState::Stopped,
-			ss => SourceState::Unknown(ss),
-		}
+		SourceState::from_raw(value)
+	}
+	fn read_state(&self) -> AltoResult<SourceSnapshot> {
+		let _defer = self.ctx.defer_updates();
+		let snapshot = {
+			let _lock = self.ctx.make_current(true);
+			let api = &self.ctx.0.dev.0.alto.0.api;
+			let (mut gain, mut pitch, mut looping, mut state) = (0.0, 0.0, 0, 0);
+			let (mut position, mut velocity) = ([0.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
+			unsafe {
+				api.alGetSourcef(self.src, sys::AL_GAIN, &mut gain);
+				api.alGetSourcef(self.src, sys::AL_PITCH, &mut pitch);
+				api.alGetSourcefv(self.src, sys::AL_POSITION, &mut position as *mut [f32; 3] as *mut sys::ALfloat);
+				api.alGetSourcefv(self.src, sys::AL_VELOCITY, &mut velocity as *mut [f32; 3] as *mut sys::ALfloat);
+				api.alGetSourcei(self.src, sys::AL_LOOPING, &mut looping);
+				api.alGetSourcei(self.src, sys::AL_SOURCE_STATE, &mut state);
+			}
+			SourceSnapshot{
+				gain,
+				pitch,
+				position,
+				velocity,
+				looping: looping == sys::AL_TRUE as sys::ALint,
+				state: SourceState::from_raw(state),
+			}
+		};
+		self.ctx.get_error().map(|_| snapshot)
 	}
 	fn play(&self) {
+		if self.defer_play(DeferredPlay::Now) {
+			return;
+		}
+
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcePlay(self.src); }
+		record!(self.ctx.0, r => Call::SourcePlay{src: r.source(self.src)});
+	}
+	fn play_at(&self, device_clock_ns: i64) -> AltoResult<()> {
+		let apat = self.ctx.0.exts.AL_SOFT_source_start_delay()?.alSourcePlayAtTimeSOFT?;
+		if self.defer_play(DeferredPlay::At(device_clock_ns)) {
+			return Ok(());
+		}
+
+		let _lock = self.ctx.make_current(true);
+		unsafe { apat(self.src, device_clock_ns); }
+		record!(self.ctx.0, r => Call::SourcePlayAtTime{src: r.source(self.src), time: device_clock_ns});
+		self.ctx.get_error()
 	}
 	fn pause(&self) {
+		self.defer_play(DeferredPlay::Idle);
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcePause(self.src); }
+		record!(self.ctx.0, r => Call::SourcePause{src: r.source(self.src)});
 	}
 	fn stop(&self) {
+		self.defer_play(DeferredPlay::Idle);
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourceStop(self.src); }
+		record!(self.ctx.0, r => Call::SourceStop{src: r.source(self.src)});
 	}
 	fn rewind(&self) {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourceRewind(self.src); }
+		record!(self.ctx.0, r => Call::SourceRewind{src: r.source(self.src)});
 	}
 
 
@@ -878,6 +2203,7 @@ impl SourceInner {
 	fn set_relative(&self, value: bool) {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, sys::AL_SOURCE_RELATIVE, if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: sys::AL_SOURCE_RELATIVE, value: if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint});
 	}
 
 
@@ -890,6 +2216,7 @@ impl SourceInner {
 	fn set_gain(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_GAIN, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_GAIN, value});
 		self.ctx.get_error()
 	}
 
@@ -903,6 +2230,7 @@ impl SourceInner {
 	fn set_min_gain(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_MIN_GAIN, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_MIN_GAIN, value});
 		self.ctx.get_error()
 	}
 
@@ -916,6 +2244,7 @@ impl SourceInner {
 	fn set_max_gain(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_MAX_GAIN, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_MAX_GAIN, value});
 		self.ctx.get_error()
 	}
 
@@ -929,6 +2258,7 @@ impl SourceInner {
 	fn set_reference_distance(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_REFERENCE_DISTANCE, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_REFERENCE_DISTANCE, value});
 		self.ctx.get_error()
 	}
 
@@ -942,6 +2272,7 @@ impl SourceInner {
 	fn set_rolloff_factor(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_ROLLOFF_FACTOR, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_ROLLOFF_FACTOR, value});
 		self.ctx.get_error()
 	}
 
@@ -955,6 +2286,7 @@ impl SourceInner {
 	fn set_max_distance(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_MAX_DISTANCE, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_MAX_DISTANCE, value});
 		self.ctx.get_error()
 	}
 
@@ -968,6 +2300,7 @@ impl SourceInner {
 	fn set_pitch(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_PITCH, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_PITCH, value});
 		self.ctx.get_error()
 	}
 
@@ -982,6 +2315,7 @@ impl SourceInner {
 		let _lock = self.ctx.make_current(true);
 		let value = value.into();
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcefv(self.src, sys::AL_POSITION, &value as *const [f32; 3] as *const sys::ALfloat); }
+		record!(self.ctx.0, r => Call::Sourcefv{src: r.source(self.src), param: sys::AL_POSITION, values: value.to_vec()});
 		self.ctx.get_error()
 	}
 
@@ -996,6 +2330,7 @@ impl SourceInner {
 		let _lock = self.ctx.make_current(true);
 		let value = value.into();
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcefv(self.src, sys::AL_VELOCITY, &value as *const [f32; 3] as *const sys::ALfloat); }
+		record!(self.ctx.0, r => Call::Sourcefv{src: r.source(self.src), param: sys::AL_VELOCITY, values: value.to_vec()});
 		self.ctx.get_error()
 	}
 
@@ -1010,6 +2345,7 @@ impl SourceInner {
 		let _lock = self.ctx.make_current(true);
 		let value = value.into();
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcefv(self.src, sys::AL_DIRECTION, &value as *const [f32; 3] as *const sys::ALfloat); }
+		record!(self.ctx.0, r => Call::Sourcefv{src: r.source(self.src), param: sys::AL_DIRECTION, values: value.to_vec()});
 		self.ctx.get_error()
 	}
 
@@ -1023,6 +2359,7 @@ impl SourceInner {
 	fn set_cone_inner_angle(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_CONE_INNER_ANGLE, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_CONE_INNER_ANGLE, value});
 		self.ctx.get_error()
 	}
 
@@ -1036,6 +2373,7 @@ impl SourceInner {
 	fn set_cone_outer_angle(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_CONE_OUTER_ANGLE, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_CONE_OUTER_ANGLE, value});
 		self.ctx.get_error()
 	}
 
@@ -1049,6 +2387,7 @@ impl SourceInner {
 	fn set_cone_outer_gain(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_CONE_OUTER_GAIN, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_CONE_OUTER_GAIN, value});
 		self.ctx.get_error()
 	}
 
@@ -1062,8 +2401,16 @@ impl SourceInner {
 	fn set_sec_offset(&self, value: f32) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, sys::AL_SEC_OFFSET, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: sys::AL_SEC_OFFSET, value});
 		self.ctx.get_error()
 	}
+	fn progress(&self) -> AltoResult<Duration> {
+		let _lock = self.ctx.make_current(true);
+		let mut value = 0.0;
+		unsafe { self.ctx.0.dev.0.alto.0.api.alGetSourcef(self.src, sys::AL_SEC_OFFSET, &mut value); }
+		self.ctx.get_error()?;
+		Ok(if value.is_finite() && value > 0.0 { Duration::from_secs_f32(value) } else { Duration::from_secs(0) })
+	}
 
 
 	fn sample_offset(&self) -> sys::ALint {
@@ -1075,6 +2422,7 @@ impl SourceInner {
 	fn set_sample_offset(&self, value: sys::ALint) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, sys::AL_SAMPLE_OFFSET, value); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: sys::AL_SAMPLE_OFFSET, value});
 		self.ctx.get_error()
 	}
 
@@ -1088,6 +2436,7 @@ impl SourceInner {
 	fn set_byte_offset(&self, value: sys::ALint) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, sys::AL_BYTE_OFFSET, value); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: sys::AL_BYTE_OFFSET, value});
 		self.ctx.get_error()
 	}
 
@@ -1121,6 +2470,7 @@ impl SourceInner {
 	fn set_soft_direct_channels(&self, value: bool) -> AltoResult<()> {
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, self.ctx.0.exts.AL_SOFT_direct_channels()?.AL_DIRECT_CHANNELS_SOFT?, if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: self.ctx.0.exts.AL_SOFT_direct_channels()?.AL_DIRECT_CHANNELS_SOFT?, value: if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint});
 		self.ctx.get_error()
 	}
 
@@ -1170,18 +2520,18 @@ impl SourceInner {
 	fn set_distance_model(&self, value: DistanceModel) -> AltoResult<()> {
 		self.ctx.0.exts.AL_EXT_source_distance_model()?;
 		let _lock = self.ctx.make_current(true);
-		unsafe {
-			self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, sys::AL_DISTANCE_MODEL, match value {
-				DistanceModel::None => sys::AL_NONE,
-				DistanceModel::Inverse => sys::AL_INVERSE_DISTANCE,
-				DistanceModel::InverseClamped => sys::AL_INVERSE_DISTANCE_CLAMPED,
-				DistanceModel::Linear => sys::AL_LINEAR_DISTANCE,
-				DistanceModel::LinearClamped => sys::AL_LINEAR_DISTANCE_CLAMPED,
-				DistanceModel::Exponent => sys::AL_EXPONENT_DISTANCE,
-				DistanceModel::ExponentClamped => sys::AL_EXPONENT_DISTANCE_CLAMPED,
-				DistanceModel::Unknown(dm) => dm,
-			});
-		}
+		let value = match value {
+			DistanceModel::None => sys::AL_NONE,
+			DistanceModel::Inverse => sys::AL_INVERSE_DISTANCE,
+			DistanceModel::InverseClamped => sys::AL_INVERSE_DISTANCE_CLAMPED,
+			DistanceModel::Linear => sys::AL_LINEAR_DISTANCE,
+			DistanceModel::LinearClamped => sys::AL_LINEAR_DISTANCE_CLAMPED,
+			DistanceModel::Exponent => sys::AL_EXPONENT_DISTANCE,
+			DistanceModel::ExponentClamped => sys::AL_EXPONENT_DISTANCE_CLAMPED,
+			DistanceModel::Unknown(dm) => dm,
+		};
+		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, sys::AL_DISTANCE_MODEL, value); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: sys::AL_DISTANCE_MODEL, value});
 		self.ctx.get_error()
 	}
 
@@ -1203,18 +2553,107 @@ impl SourceInner {
 	fn set_soft_spatialization(&self, value: SoftSourceSpatialization) -> AltoResult<()> {
 		let assp = self.ctx.0.exts.AL_SOFT_source_spatialize()?;
 		let _lock = self.ctx.make_current(true);
-		unsafe {
-			self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, assp.AL_SOURCE_SPATIALIZE_SOFT?, match value {
-				SoftSourceSpatialization::Disabled => sys::AL_FALSE as sys::ALint,
-				SoftSourceSpatialization::Enabled => sys::AL_TRUE as sys::ALint,
-				SoftSourceSpatialization::Auto => assp.AL_AUTO_SOFT?,
-				SoftSourceSpatialization::Unknown(ssp) => ssp as sys::ALint,
-			});
-		}
+		let value = match value {
+			SoftSourceSpatialization::Disabled => sys::AL_FALSE as sys::ALint,
+			SoftSourceSpatialization::Enabled => sys::AL_TRUE as sys::ALint,
+			SoftSourceSpatialization::Auto => assp.AL_AUTO_SOFT?,
+			SoftSourceSpatialization::Unknown(ssp) => ssp as sys::ALint,
+		};
+		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, assp.AL_SOURCE_SPATIALIZE_SOFT?, value); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: assp.AL_SOURCE_SPATIALIZE_SOFT?, value});
 		self.ctx.get_error()
 	}
 
 
+	fn set_2d(&self, enabled: bool, channels: sys::ALint) -> AltoResult<()> {
+		let mut saved = self.saved_3d.lock();
+		if enabled == saved.is_some() {
+			return Ok(());
+		}
+
+		let _defer = self.ctx.defer_updates();
+		if let Some(ref prev) = *saved {
+			let current = Saved3d{
+				relative: self.relative(),
+				position: self.position(),
+				rolloff_factor: self.rolloff_factor(),
+				direct_channels: prev.direct_channels.map(|_| self.soft_direct_channels()),
+				spatialization: prev.spatialization.map(|_| self.soft_spatialization()),
+			};
+			self.apply_saved_3d(prev, &current)?;
+			*saved = None;
+			return Ok(());
+		}
+
+		let mut prev = Saved3d{
+			relative: self.relative(),
+			position: self.position(),
+			rolloff_factor: self.rolloff_factor(),
+			direct_channels: None,
+			spatialization: None,
+		};
+		let mut flat = Saved3d{relative: true, position: [0.0, 0.0, 0.0], rolloff_factor: 0.0, direct_channels: None, spatialization: None};
+		if channels > 1 && self.ctx.0.exts.AL_SOFT_direct_channels().is_ok() {
+			prev.direct_channels = Some(self.soft_direct_channels());
+			flat.direct_channels = Some(true);
+		} else if self.ctx.0.exts.AL_SOFT_source_spatialize().is_ok() {
+			prev.spatialization = Some(self.soft_spatialization());
+			flat.spatialization = Some(SoftSourceSpatialization::Disabled);
+		}
+
+		*saved = Some(prev);
+		let res = self.apply_saved_3d(&flat, saved.as_ref().unwrap());
+		if res.is_err() {
+			*saved = None;
+		}
+		res
+	}
+	/// Apply each setting of `target` in turn. If one fails, the settings already applied are restored from `undo`.
+	fn apply_saved_3d(&self, target: &Saved3d, undo: &Saved3d) -> AltoResult<()> {
+		let mut applied = 0;
+		let res = (|| {
+			self.set_relative(target.relative);
+			applied = 1;
+			self.set_position(target.position)?;
+			applied = 2;
+			self.set_rolloff_factor(target.rolloff_factor)?;
+			applied = 3;
+			if let Some(dc) = target.direct_channels {
+				self.set_soft_direct_channels(dc)?;
+			}
+			applied = 4;
+			if let Some(sp) = target.spatialization {
+				self.set_soft_spatialization(sp)?;
+			}
+			Ok(())
+		})();
+
+		if res.is_err() {
+			if applied > 0 {
+				self.set_relative(undo.relative);
+			}
+			if applied > 1 {
+				let _ = self.set_position(undo.position);
+			}
+			if applied > 2 {
+				let _ = self.set_rolloff_factor(undo.rolloff_factor);
+			}
+			if let (true, Some(dc)) = (applied > 3, undo.direct_channels) {
+				let _ = self.set_soft_direct_channels(dc);
+			}
+		}
+		res
+	}
+	fn is_2d(&self) -> bool { self.saved_3d.lock().is_some() }
+
+
+	fn set_pan(&self, pan: f32) -> AltoResult<()> {
+		let _defer = self.ctx.defer_updates();
+		self.set_relative(true);
+		self.set_position(pan_position(pan))
+	}
+
+
 	fn soft_resampler(&self) -> AltoResult<sys::ALint> {
 		let assr = self.ctx.0.exts.AL_SOFT_source_resampler()?;
 		let _lock = self.ctx.make_current(true);
@@ -1222,6 +2661,13 @@ impl SourceInner {
 		unsafe { self.ctx.0.dev.0.alto.0.api.alGetSourceiv(self.src, assr.AL_SOURCE_RESAMPLER_SOFT?, &mut value); }
 		Ok(value.into())
 	}
+	fn apply_defaults(&self) -> AltoResult<()> {
+		let resampler = *self.ctx.0.default_resampler.lock();
+		if let Some(index) = resampler {
+			self.set_soft_resampler(index)?;
+		}
+		Ok(())
+	}
 	fn set_soft_resampler(&self, value: sys::ALint) -> AltoResult<()> {
 		let assr = self.ctx.0.exts.AL_SOFT_source_resampler()?;
 		let _lock = self.ctx.make_current(true);
@@ -1229,6 +2675,7 @@ impl SourceInner {
 			let value = value.into();
 			self.ctx.0.dev.0.alto.0.api.alSourceiv(self.src, assr.AL_SOURCE_RESAMPLER_SOFT?, &value);
 		}
+		record!(self.ctx.0, r => Call::Sourceiv{src: r.source(self.src), param: assr.AL_SOURCE_RESAMPLER_SOFT?, values: vec![value]});
 		self.ctx.get_error()
 	}
 
@@ -1243,10 +2690,9 @@ impl SourceInner {
 	fn set_stereo_angles<V: Into<[f32; 2]>>(&self, value: V) -> AltoResult<()> {
 		let aesa = self.ctx.0.exts.AL_EXT_STEREO_ANGLES()?;
 		let _lock = self.ctx.make_current(true);
-		unsafe {
-			let value = value.into();
-			self.ctx.0.dev.0.alto.0.api.alSourcefv(self.src, aesa.AL_STEREO_ANGLES?, &value as *const [f32; 2] as *const f32);
-		}
+		let value: [f32; 2] = value.into();
+		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcefv(self.src, aesa.AL_STEREO_ANGLES?, &value as *const [f32; 2] as *const f32); }
+		record!(self.ctx.0, r => Call::Sourcefv{src: r.source(self.src), param: aesa.AL_STEREO_ANGLES?, values: value.to_vec()});
 		self.ctx.get_error()
 	}
 
@@ -1267,18 +2713,18 @@ impl SourceInner {
 			let value = value.into();
 			self.ctx.0.dev.0.alto.0.api.alSourcefv(self.src, aesr.AL_SOURCE_RADIUS?, &value);
 		}
+		record!(self.ctx.0, r => Call::Sourcefv{src: r.source(self.src), param: aesr.AL_SOURCE_RADIUS?, values: vec![value]});
 		self.ctx.get_error()
 	}
 
 
 	fn set_direct_filter<F: Filter>(&self, value: &F) -> AltoResult<()> {
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
-		if *value.context() != self.ctx {
-			return Err(AltoError::WrongContext);
-		}
+		self.ctx.check_context(value.context())?;
 
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, efx.AL_DIRECT_FILTER?, value.as_raw() as sys::ALint); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: efx.AL_DIRECT_FILTER?, value: value.as_raw() as sys::ALint});
 		self.ctx.get_error()
 	}
 	fn clear_direct_filter(&self) {
@@ -1286,32 +2732,33 @@ impl SourceInner {
 			let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
 			let _lock = self.ctx.make_current(true);
 			unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, efx.AL_DIRECT_FILTER?, 0); }
+			record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: efx.AL_DIRECT_FILTER?, value: 0});
 			Ok(())
 		})();
 	}
 
 
 	fn set_aux_send(arc_self: &Arc<SourceInner>, send: sys::ALint, slot: &mut AuxEffectSlot) -> AltoResult<()> {
-		SourceInner::set_aux_send_impl(arc_self, send, slot, 0)
+		SourceInner::set_aux_send_impl(arc_self, send, slot, None)
 	}
 	fn set_aux_send_filter<F: Filter>(arc_self: &Arc<SourceInner>, send: sys::ALint, slot: &mut AuxEffectSlot, filter: &F) -> AltoResult<()> {
-		if *filter.context() != arc_self.ctx {
-			return Err(AltoError::WrongContext);
-		}
+		arc_self.ctx.check_context(filter.context())?;
 
-		SourceInner::set_aux_send_impl(arc_self, send, slot, filter.as_raw())
+		SourceInner::set_aux_send_impl(arc_self, send, slot, Some((filter.raw_handle(), filter.alive())))
 	}
-	fn set_aux_send_impl(arc_self: &Arc<SourceInner>, send: sys::ALint, slot: &mut AuxEffectSlot, filter: sys::ALuint) -> AltoResult<()> {
+	fn set_aux_send_impl(arc_self: &Arc<SourceInner>, send: sys::ALint, slot: &mut AuxEffectSlot, filter_info: Option<(RawHandle, Weak<()>)>) -> AltoResult<()> {
+		let filter = filter_info.as_ref().map_or(0, |&(f, _)| f.name);
 		let efx = arc_self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
 		if send >= arc_self.ctx.0.dev.max_aux_sends() || *slot.context() != arc_self.ctx {
-			return Err(AltoError::WrongContext);
+			return Err(AltoError::WrongContext{expected: arc_self.ctx.0.id, found: slot.context().0.id});
 		}
 
 		let _lock = arc_self.ctx.make_current(true);
 		let mut sends = arc_self.sends.lock();
 		unsafe { arc_self.ctx.0.dev.0.alto.0.api.alSourceiv(arc_self.src, efx.AL_AUXILIARY_SEND_FILTER?, &mut [slot.as_raw() as sys::ALint, send, filter as sys::ALint] as *mut [sys::ALint; 3] as *mut sys::ALint); }
+		record!(arc_self.ctx.0, r => Call::Sourceiv{src: r.source(arc_self.src), param: efx.AL_AUXILIARY_SEND_FILTER?, values: vec![slot.as_raw() as sys::ALint, send, filter as sys::ALint]});
 		arc_self.ctx.get_error()?;
-		sends[send as usize] = 0;
+		sends[send as usize] = Some(slot.send_info(filter_info));
 		slot.add_input(Arc::downgrade(arc_self));
 		Ok(())
 	}
@@ -1325,20 +2772,125 @@ impl SourceInner {
 			let _lock = self.ctx.make_current(true);
 			let mut sends = self.sends.lock();
 			unsafe { self.ctx.0.dev.0.alto.0.api.alSourceiv(self.src, efx.AL_AUXILIARY_SEND_FILTER?, &mut [0, send, 0] as *mut [sys::ALint; 3] as *mut sys::ALint); }
-			sends[send as usize] = 0;
+			record!(self.ctx.0, r => Call::Sourceiv{src: r.source(self.src), param: efx.AL_AUXILIARY_SEND_FILTER?, values: vec![0, send, 0]});
+			sends[send as usize] = None;
 			Ok(())
 		})();
 	}
-	pub fn clear_aux_effect_slot(&self, slot: sys::ALuint) {
+	fn clear_aux_sends(&self, max_sends: Option<sys::ALint>) -> AltoResult<()> {
+		let aasf = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?.AL_AUXILIARY_SEND_FILTER?;
+		let device_sends = self.ctx.0.dev.max_aux_sends();
+		let count = match max_sends {
+			Some(n) if n < 0 || n > device_sends => return Err(AltoError::InvalidValue),
+			Some(n) => n,
+			None => device_sends,
+		};
+
+		let _lock = self.ctx.make_current(true);
+		let mut sends = self.sends.lock();
+		for send in 0 .. count {
+			unsafe { self.ctx.0.dev.0.alto.0.api.alSourceiv(self.src, aasf, &mut [0, send, 0] as *mut [sys::ALint; 3] as *mut sys::ALint); }
+			record!(self.ctx.0, r => Call::Sourceiv{src: r.source(self.src), param: aasf, values: vec![0, send, 0]});
+			if let Some(s) = sends.get_mut(send as usize) {
+				*s = None;
+			}
+		}
+		self.ctx.get_error()
+	}
+	pub fn clear_aux_effect_slot(&self, slot: RawHandle) {
 		if let Ok(&ext::ALC_EXT_EFX{AL_AUXILIARY_SEND_FILTER: Ok(aasf), ..}) = self.ctx.0.dev.0.exts.ALC_EXT_EFX() {
 			for (i, s) in self.sends.lock().iter_mut().enumerate() {
-				if *s == slot {
+				if s.as_ref().is_some_and(|s| s.slot_handle() == slot) {
 					unsafe { self.ctx.0.dev.0.alto.0.api.alSourceiv(self.src, aasf, &mut [0, i as sys::ALint, 0] as *mut [sys::ALint; 3] as *mut sys::ALint); }
-					*s = 0;
+					record!(self.ctx.0, r => Call::Sourceiv{src: r.source(self.src), param: aasf, values: vec![0, i as sys::ALint, 0]});
+					*s = None;
 				}
 			}
 		}
 	}
+	fn aux_send(&self, send: sys::ALint) -> Option<SendInfo> {
+		if send < 0 { return None; }
+		self.sends.lock().get(send as usize).and_then(|s| s.clone())
+	}
+	fn aux_sends(&self) -> ::std::vec::IntoIter<(sys::ALint, SendInfo)> {
+		self.sends.lock().iter()
+			.enumerate()
+			.filter_map(|(i, s)| s.clone().map(|s| (i as sys::ALint, s)))
+			.collect::<Vec<_>>()
+			.into_iter()
+	}
+	fn set_occlusion(&self, occ: Occlusion) -> AltoResult<()> {
+		occ.validate()?;
+		let mut state = self.occlusion.lock();
+
+		if self.ctx.0.dev.0.exts.ALC_EXT_EFX().is_err() {
+			let base = match *state {
+				Some(OcclusionState::Gain{base, ..}) => base,
+				_ => self.gain(),
+			};
+			self.set_gain(base * occ.fallback_gain())?;
+			*state = Some(OcclusionState::Gain{base, current: occ});
+			return Ok(());
+		}
+
+		if state.is_none() {
+			let direct = self.ctx.new_filter::<LowpassFilter>()?;
+			let send = self.ctx.new_filter::<LowpassFilter>()?;
+			*state = Some(OcclusionState::Efx{direct, send, current: occ});
+		}
+		if let Some(OcclusionState::Efx{ref mut direct, ref mut send, ref mut current}) = *state {
+			direct.set_gain(occ.direct_gain)?;
+			direct.set_gainhf(occ.direct_lf)?;
+			send.set_gain(occ.send_gain)?;
+			send.set_gainhf(occ.send_lf)?;
+			self.set_direct_filter(direct)?;
+			self.reattach_send_filter(0, Some((send.raw_handle(), send.alive())))?;
+			*current = occ;
+		}
+		Ok(())
+	}
+	fn clear_occlusion(&self) {
+		let state = self.occlusion.lock().take();
+		match state {
+			Some(OcclusionState::Efx{..}) => {
+				self.clear_direct_filter();
+				let _ = self.reattach_send_filter(0, None);
+			},
+			Some(OcclusionState::Gain{base, ..}) => {
+				let _ = self.set_gain(base);
+			},
+			None => (),
+		}
+	}
+	fn occlusion(&self) -> Option<Occlusion> {
+		self.occlusion.lock().as_ref().map(|s| s.current())
+	}
+	/// Attach a filter to an aux send while keeping whatever slot it is routed to.
+	fn reattach_send_filter(&self, send: sys::ALint, filter_info: Option<(RawHandle, Weak<()>)>) -> AltoResult<()> {
+		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
+		if send >= self.ctx.0.dev.max_aux_sends() {
+			return Ok(());
+		}
+
+		let filter = filter_info.as_ref().map_or(0, |&(f, _)| f.name);
+		let _lock = self.ctx.make_current(true);
+		let mut sends = self.sends.lock();
+		let slot = sends[send as usize].as_ref().map_or(0, |s| s.slot());
+		unsafe { self.ctx.0.dev.0.alto.0.api.alSourceiv(self.src, efx.AL_AUXILIARY_SEND_FILTER?, &mut [slot as sys::ALint, send, filter as sys::ALint] as *mut [sys::ALint; 3] as *mut sys::ALint); }
+		record!(self.ctx.0, r => Call::Sourceiv{src: r.source(self.src), param: efx.AL_AUXILIARY_SEND_FILTER?, values: vec![slot as sys::ALint, send, filter as sys::ALint]});
+		self.ctx.get_error()?;
+		if let Some(ref mut info) = sends[send as usize] {
+			*info = info.with_filter(filter_info);
+		}
+		Ok(())
+	}
+	pub(crate) fn aux_sends_to(&self, slot: RawHandle) -> Vec<(sys::ALuint, sys::ALint)> {
+		self.sends.lock().iter()
+			.enumerate()
+			.filter(|&(_, s)| s.as_ref().is_some_and(|s| s.slot_handle() == slot))
+			.map(|(i, _)| (self.src, i as sys::ALint))
+			.collect()
+	}
 
 
 	fn air_absorption_factor(&self) -> f32 {
@@ -1354,6 +2906,7 @@ impl SourceInner {
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, efx.AL_AIR_ABSORPTION_FACTOR?, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: efx.AL_AIR_ABSORPTION_FACTOR?, value});
 		self.ctx.get_error()
 	}
 
@@ -1371,6 +2924,7 @@ impl SourceInner {
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, efx.AL_ROOM_ROLLOFF_FACTOR?, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: efx.AL_ROOM_ROLLOFF_FACTOR?, value});
 		self.ctx.get_error()
 	}
 
@@ -1388,6 +2942,7 @@ impl SourceInner {
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcef(self.src, efx.AL_CONE_OUTER_GAINHF?, value); }
+		record!(self.ctx.0, r => Call::Sourcef{src: r.source(self.src), param: efx.AL_CONE_OUTER_GAINHF?, value});
 		self.ctx.get_error()
 	}
 
@@ -1405,6 +2960,7 @@ impl SourceInner {
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX()?;
 		let _lock = self.ctx.make_current(true);
 		unsafe { self.ctx.0.dev.0.alto.0.api.alSourcei(self.src, efx.AL_CONE_OUTER_GAINHF?, if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint); }
+		record!(self.ctx.0, r => Call::Sourcei{src: r.source(self.src), param: efx.AL_CONE_OUTER_GAINHF?, value: if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint});
 		self.ctx.get_error()
 	}
 }
@@ -1428,8 +2984,34 @@ impl Hash for SourceInner {
 
 impl Drop for SourceInner {
 	fn drop(&mut self) {
+		if self.ctx.0.destroyed.load(Ordering::SeqCst) {
+			return;
+		}
+
+		// Stop and detach everything first, so that neither the delete nor the buffers dropped after it can fail.
 		let _lock = self.ctx.make_current(true);
-		unsafe { self.ctx.0.dev.0.alto.0.api.alDeleteSources(1, &mut self.src as *mut sys::ALuint); }
+		let api = &self.ctx.0.dev.0.alto.0.api;
+		unsafe { api.alSourceStop(self.src); }
+		record!(self.ctx.0, r => Call::SourceStop{src: r.source(self.src)});
+
+		// Stopping marks every queued buffer as processed, so the whole queue can be unqueued at once.
+		let (mut ty, mut queued) = (0, 0);
+		unsafe {
+			api.alGetSourcei(self.src, sys::AL_SOURCE_TYPE, &mut ty);
+			api.alGetSourcei(self.src, sys::AL_BUFFERS_QUEUED, &mut queued);
+		}
+		if ty == sys::AL_STREAMING && queued > 0 {
+			let mut ids = vec![0; queued as usize];
+			unsafe { api.alSourceUnqueueBuffers(self.src, queued, ids.as_mut_ptr()); }
+			record!(self.ctx.0, r => Call::SourceUnqueueBuffers{src: r.source(self.src), count: queued as u32});
+		}
+
+		unsafe { api.alSourcei(self.src, sys::AL_BUFFER, 0); }
+		record!(self.ctx.0, r => Call::SourceBuffer{src: r.source(self.src), buf: None});
+
+		unsafe { api.alDeleteSources(1, &mut self.src as *mut sys::ALuint); }
+		record!(self.ctx.0, r => Call::DeleteSource{src: r.delete_source(self.src)});
+		self.ctx.0.retire_name(ObjectKind::Source, self.src);
 	}
 }
 
@@ -1440,10 +3022,14 @@ impl StaticSource {
 		{
 			let _lock = ctx.make_current(true);
 			unsafe { ctx.0.dev.0.alto.0.api.alGenSources(1, &mut src as *mut sys::ALuint); }
+			record!(ctx.0, r => Call::GenSource{src: r.gen_source(src)});
 			ctx.get_error()?;
 		}
-		let sends = iter::repeat(0).take(ctx.0.dev.0.max_aux_sends() as usize).collect();
-		Ok(StaticSource{src: Arc::new(SourceInner{ctx: ctx, src: src, sends: Mutex::new(sends)}), buf: None})
+		let sends = iter::repeat(None).take(ctx.0.dev.0.max_aux_sends() as usize).collect();
+		let src = Arc::new(SourceInner{gen: ctx.0.generation(ObjectKind::Source, src), ctx: ctx, src: src, sends: Mutex::new(sends), saved_3d: Mutex::new(None), occlusion: Mutex::new(None)});
+		src.register();
+		src.apply_defaults()?;
+		Ok(StaticSource{src, buf: None})
 	}
 
 
@@ -1452,23 +3038,39 @@ impl StaticSource {
 
 	/// `alSourcei(AL_BUFFER)`
 	pub fn set_buffer(&mut self, buf: Arc<Buffer>) -> AltoResult<()> {
-		if buf.ctx.device().as_raw() != self.src.ctx.device().as_raw() {
-			return Err(AltoError::WrongDevice);
-		}
+		self.src.ctx.check_device(&buf.ctx)?;
 
 		{
 			let _lock = self.src.ctx.make_current(true);
 			unsafe { self.src.ctx.0.dev.0.alto.0.api.alSourcei(self.src.src, sys::AL_BUFFER, buf.buf as sys::ALint); }
+			record!(self.src.ctx.0, r => Call::SourceBuffer{src: r.source(self.src.src), buf: Some(r.buffer(buf.buf))});
 		}
 
 		self.buf = Some(buf);
 		Ok(())
 	}
+	/// `alSourceStop()`, `alSourcei(AL_BUFFER)`, `alSourcePlay()`
+	/// Play `buf` from the start, stopping whatever the source was playing or paused on first.
+	/// The source holds on to `buf` until another buffer is set or cleared. Stops at the first step that fails.
+	pub fn play_buffer(&mut self, buf: Arc<Buffer>) -> AltoResult<()> {
+		match self.state() {
+			SourceState::Playing | SourceState::Paused => {
+				self.stop();
+				self.src.ctx.get_error()?;
+			},
+			_ => (),
+		}
+		self.set_buffer(buf)?;
+		self.src.ctx.get_error()?;
+		self.play();
+		self.src.ctx.get_error()
+	}
 	/// `alSourcei(AL_BUFFER)`
 	pub fn clear_buffer(&mut self) {
 		{
 			let _lock = self.src.ctx.make_current(true);
 			unsafe { self.src.ctx.0.dev.0.alto.0.api.alSourcei(self.src.src, sys::AL_BUFFER, 0); }
+			record!(self.src.ctx.0, r => Call::SourceBuffer{src: r.source(self.src.src), buf: None});
 		}
 
 		self.buf = None;
@@ -1486,6 +3088,7 @@ impl StaticSource {
 	pub fn set_looping(&mut self, value: bool) {
 		let _lock = self.src.ctx.make_current(true);
 		unsafe { self.src.ctx.0.dev.0.alto.0.api.alSourcei(self.src.src, sys::AL_LOOPING, if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint); }
+		record!(self.src.ctx.0, r => Call::Sourcei{src: r.source(self.src.src), param: sys::AL_LOOPING, value: if value { sys::AL_TRUE } else { sys::AL_FALSE } as sys::ALint});
 	}
 }
 
@@ -1493,9 +3096,12 @@ impl StaticSource {
 unsafe impl Source for StaticSource {
 	#[inline] fn context(&self) -> &Context { self.src.context() }
 	#[inline] fn as_raw(&self) -> sys::ALuint { self.src.as_raw() }
+	#[inline] fn raw_handle(&self) -> RawHandle { self.src.raw_handle() }
 
 	#[inline] fn state(&self) -> SourceState { self.src.state() }
+	#[inline] fn read_state(&self) -> AltoResult<SourceSnapshot> { self.src.read_state() }
 	#[inline] fn play(&mut self) -> () { self.src.play() }
+	#[inline] fn play_at(&mut self, device_clock_ns: i64) -> AltoResult<()> { self.src.play_at(device_clock_ns) }
 	#[inline] fn pause(&mut self) -> () { self.src.pause() }
 	#[inline] fn stop(&mut self) -> () { self.src.stop() }
 	#[inline] fn rewind(&mut self) -> () { self.src.rewind() }
@@ -1505,6 +3111,7 @@ unsafe impl Source for StaticSource {
 
 	#[inline] fn gain(&self) -> f32 { self.src.gain() }
 	#[inline] fn set_gain(&mut self, value: f32) -> AltoResult<()> { self.src.set_gain(value) }
+	#[inline] fn fade_out(&self, duration: Duration) -> AltoResult<FadeHandle> { FadeHandle::new(&self.src, duration) }
 
 	#[inline] fn min_gain(&self) -> f32 { self.src.min_gain() }
 	#[inline] fn set_min_gain(&mut self, value: f32) -> AltoResult<()> { self.src.set_min_gain(value) }
@@ -1544,6 +3151,7 @@ unsafe impl Source for StaticSource {
 
 	#[inline] fn sec_offset(&self) -> f32 { self.src.sec_offset() }
 	#[inline] fn set_sec_offset(&mut self, value: f32) -> AltoResult<()> { self.src.set_sec_offset(value) }
+	#[inline] fn progress(&self) -> AltoResult<Duration> { self.src.progress() }
 
 	#[inline] fn sample_offset(&self) -> sys::ALint { self.src.sample_offset() }
 	#[inline] fn set_sample_offset(&mut self, value: sys::ALint) -> AltoResult<()> { self.src.set_sample_offset(value) }
@@ -1569,6 +3177,12 @@ unsafe impl Source for StaticSource {
 
 	#[inline] fn soft_spatialization(&self) -> SoftSourceSpatialization { self.src.soft_spatialization() }
 	#[inline] fn set_soft_spatialization(&mut self, value: SoftSourceSpatialization) -> AltoResult<()> { self.src.set_soft_spatialization(value) }
+	fn set_2d(&mut self, enabled: bool) -> AltoResult<()> {
+		let channels = self.buf.as_ref().map_or(1, |b| b.channels());
+		self.src.set_2d(enabled, channels)
+	}
+	#[inline] fn is_2d(&self) -> bool { self.src.is_2d() }
+	#[inline] fn set_pan(&mut self, pan: f32) -> AltoResult<()> { self.src.set_pan(pan) }
 
 	#[inline] fn soft_resampler(&self) -> AltoResult<sys::ALint> { self.src.soft_resampler() }
 	#[inline] fn set_soft_resampler(&mut self, value: sys::ALint) -> AltoResult<()> { self.src.set_soft_resampler(value) }
@@ -1585,6 +3199,16 @@ unsafe impl Source for StaticSource {
 	#[inline] fn set_aux_send(&mut self, send: sys::ALint, slot: &mut AuxEffectSlot) -> AltoResult<()> { SourceInner::set_aux_send(&self.src, send, slot) }
 	#[inline] fn set_aux_send_filter<F: Filter>(&mut self, send: sys::ALint, slot: &mut AuxEffectSlot, filter: &F) -> AltoResult<()> { SourceInner::set_aux_send_filter(&self.src, send, slot, filter) }
 	#[inline] fn clear_aux_send(&mut self, send: sys::ALint) { self.src.clear_aux_send(send) }
+	#[inline] fn clear_aux_sends(&mut self, max_sends: Option<sys::ALint>) -> AltoResult<()> { self.src.clear_aux_sends(max_sends) }
+	#[inline] fn aux_send(&self, send: sys::ALint) -> Option<SendInfo> { self.src.aux_send(send) }
+	#[inline] fn aux_sends(&self) -> ::std::vec::IntoIter<(sys::ALint, SendInfo)> { self.src.aux_sends() }
+
+	#[inline] fn set_occlusion(&mut self, direct_lf: f32, direct_gain: f32, send_lf: f32, send_gain: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion{direct_lf, direct_gain, send_lf, send_gain}) }
+	#[inline] fn occlude(&mut self, amount: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion::occluded(amount)?) }
+	#[inline] fn obstruct(&mut self, amount: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion::obstructed(amount)?) }
+	#[inline] fn exclude(&mut self, amount: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion::excluded(amount)?) }
+	#[inline] fn clear_occlusion(&mut self) { self.src.clear_occlusion() }
+	#[inline] fn occlusion(&self) -> Option<Occlusion> { self.src.occlusion() }
 
 	#[inline] fn air_absorption_factor(&self) -> f32 { self.src.air_absorption_factor() }
 	#[inline] fn set_air_absorption_factor(&mut self, value: f32) -> AltoResult<()> { self.src.set_air_absorption_factor(value) }
@@ -1614,54 +3238,108 @@ impl StreamingSource {
 		{
 			let _lock = ctx.make_current(true);
 			unsafe { ctx.0.dev.0.alto.0.api.alGenSources(1, &mut src as *mut sys::ALuint); }
+			record!(ctx.0, r => Call::GenSource{src: r.gen_source(src)});
 			ctx.get_error()?;
 		}
-		let sends = iter::repeat(0).take(ctx.0.dev.0.max_aux_sends() as usize).collect();
-		Ok(StreamingSource{src: Arc::new(SourceInner{ctx: ctx, src: src, sends: Mutex::new(sends)}), bufs: VecDeque::new() })
+		let sends = iter::repeat(None).take(ctx.0.dev.0.max_aux_sends() as usize).collect();
+		let src = Arc::new(SourceInner{gen: ctx.0.generation(ObjectKind::Source, src), ctx: ctx, src: src, sends: Mutex::new(sends), saved_3d: Mutex::new(None), occlusion: Mutex::new(None)});
+		src.register();
+		src.apply_defaults()?;
+		Ok(StreamingSource{
+			src,
+			bufs: Arc::new(Mutex::new(StreamQueue{queued: VecDeque::new(), processed: VecDeque::new(), unqueued_nanos: 0})),
+		})
 	}
 
 
 	/// `alGetSourcei(AL_BUFFERS_QUEUED)`
+	/// Includes processed buffers that have not yet been unqueued.
 	pub fn buffers_queued(&self) -> sys::ALint {
-		self.bufs.len() as sys::ALint
+		let bufs = self.bufs.lock();
+		(bufs.queued.len() + bufs.processed.len()) as sys::ALint
 	}
 
 
 	/// `alGetSourcei(AL_BUFFERS_PROCESSED)`
+	/// Includes buffers already unqueued by `Context::process_streams`.
 	pub fn buffers_processed(&self) -> sys::ALint {
+		let bufs = self.bufs.lock();
 		let _lock = self.src.ctx.make_current(true);
 		let mut value = 0;
 		unsafe { self.src.ctx.0.dev.0.alto.0.api.alGetSourcei(self.src.src, sys::AL_BUFFERS_PROCESSED, &mut value); }
-		value
+		value + bufs.processed.len() as sys::ALint
 	}
 
 
 	/// `alSourceQueueBuffers()`
 	pub fn queue_buffer(&mut self, buf: Buffer) -> AltoResult<()> {
 		{
-			if buf.ctx.device().as_raw() != self.src.ctx.device().as_raw() {
-				return Err(AltoError::WrongDevice);
-			}
+			self.src.ctx.check_device(&buf.ctx)?;
 			let _lock = self.src.ctx.make_current(true);
 
 			unsafe { self.src.ctx.0.dev.0.alto.0.api.alSourceQueueBuffers(self.src.src, 1, &buf.buf); }
+			record!(self.src.ctx.0, r => Call::SourceQueueBuffers{src: r.source(self.src.src), bufs: vec![r.buffer(buf.buf)]});
+		}
+
+		self.bufs.lock().queued.push_back(buf);
+		Ok(())
+	}
+
+
+	/// `alSourceQueueBuffers()`
+	/// Queue several buffers with a single call. If OpenAL rejects the batch, none of them are queued.
+	pub fn queue_buffers(&mut self, bufs: Vec<Buffer>) -> AltoResult<()> {
+		if bufs.is_empty() {
+			return Ok(());
+		}
+		for buf in &bufs {
+			self.src.ctx.check_device(&buf.ctx)?;
+		}
+
+		let names: Vec<sys::ALuint> = bufs.iter().map(|b| b.buf).collect();
+		if names.len() > sys::ALsizei::MAX as usize {
+			return Err(AltoError::InvalidValue);
+		}
+		{
+			let _lock = self.src.ctx.make_current(true);
+			unsafe { self.src.ctx.0.dev.0.alto.0.api.alSourceQueueBuffers(self.src.src, names.len() as sys::ALsizei, names.as_ptr()); }
+			record!(self.src.ctx.0, r => Call::SourceQueueBuffers{src: r.source(self.src.src), bufs: names.iter().map(|&b| r.buffer(b)).collect()});
+			self.src.ctx.get_error()?;
 		}
 
-		self.bufs.push_back(buf);
+		self.bufs.lock().queued.extend(bufs);
 		Ok(())
 	}
 
 
 	/// `alSourceUnqueueBuffers()`
+	/// Buffers already unqueued by `Context::process_streams` are returned first.
 	pub fn unqueue_buffer(&mut self) -> AltoResult<Buffer> {
+		let mut bufs = self.bufs.lock();
+		if let Some(buf) = bufs.processed.pop_front() {
+			return Ok(buf);
+		}
+
 		{
 			let _lock = self.src.ctx.make_current(true);
 			let mut buf = 0;
 			unsafe { self.src.ctx.0.dev.0.alto.0.api.alSourceUnqueueBuffers(self.src.src, 1, &mut buf); }
+			record!(self.src.ctx.0, r => Call::SourceUnqueueBuffers{src: r.source(self.src.src), count: 1});
 			self.src.ctx.get_error()?;
 		}
 
-		Ok(self.bufs.pop_front().unwrap())
+		let buf = bufs.queued.pop_front().unwrap();
+		bufs.unqueued_nanos += buffer_nanos(buf.len, buf.freq);
+		Ok(buf)
+	}
+
+
+	/// `alGetSourcef(AL_SEC_OFFSET)`
+	/// The playback position across the whole stream, adding the playing time of every buffer
+	/// unqueued since the source was created to the position within the buffers still queued.
+	pub fn progress_total(&self) -> AltoResult<Duration> {
+		let unqueued = self.bufs.lock().unqueued_nanos;
+		Ok(Duration::from_nanos(unqueued) + self.src.progress()?)
 	}
 }
 
@@ -1669,9 +3347,12 @@ impl StreamingSource {
 unsafe impl Source for StreamingSource {
 	#[inline] fn context(&self) -> &Context { self.src.context() }
 	#[inline] fn as_raw(&self) -> sys::ALuint { self.src.as_raw() }
+	#[inline] fn raw_handle(&self) -> RawHandle { self.src.raw_handle() }
 
 	#[inline] fn state(&self) -> SourceState { self.src.state() }
+	#[inline] fn read_state(&self) -> AltoResult<SourceSnapshot> { self.src.read_state() }
 	#[inline] fn play(&mut self) -> () { self.src.play() }
+	#[inline] fn play_at(&mut self, device_clock_ns: i64) -> AltoResult<()> { self.src.play_at(device_clock_ns) }
 	#[inline] fn pause(&mut self) -> () { self.src.pause() }
 	#[inline] fn stop(&mut self) -> () { self.src.stop() }
 	#[inline] fn rewind(&mut self) -> () { self.src.rewind() }
@@ -1681,6 +3362,7 @@ unsafe impl Source for StreamingSource {
 
 	#[inline] fn gain(&self) -> f32 { self.src.gain() }
 	#[inline] fn set_gain(&mut self, value: f32) -> AltoResult<()> { self.src.set_gain(value) }
+	#[inline] fn fade_out(&self, duration: Duration) -> AltoResult<FadeHandle> { FadeHandle::new(&self.src, duration) }
 
 	#[inline] fn min_gain(&self) -> f32 { self.src.min_gain() }
 	#[inline] fn set_min_gain(&mut self, value: f32) -> AltoResult<()> { self.src.set_min_gain(value) }
@@ -1720,6 +3402,7 @@ unsafe impl Source for StreamingSource {
 
 	#[inline] fn sec_offset(&self) -> f32 { self.src.sec_offset() }
 	#[inline] fn set_sec_offset(&mut self, value: f32) -> AltoResult<()> { self.src.set_sec_offset(value) }
+	#[inline] fn progress(&self) -> AltoResult<Duration> { self.src.progress() }
 
 	#[inline] fn sample_offset(&self) -> sys::ALint { self.src.sample_offset() }
 	#[inline] fn set_sample_offset(&mut self, value: sys::ALint) -> AltoResult<()> { self.src.set_sample_offset(value) }
@@ -1745,6 +3428,12 @@ unsafe impl Source for StreamingSource {
 
 	#[inline] fn soft_spatialization(&self) -> SoftSourceSpatialization { self.src.soft_spatialization() }
 	#[inline] fn set_soft_spatialization(&mut self, value: SoftSourceSpatialization) -> AltoResult<()> { self.src.set_soft_spatialization(value) }
+	fn set_2d(&mut self, enabled: bool) -> AltoResult<()> {
+		let channels = self.bufs.lock().queued.front().map_or(1, |b| b.channels());
+		self.src.set_2d(enabled, channels)
+	}
+	#[inline] fn is_2d(&self) -> bool { self.src.is_2d() }
+	#[inline] fn set_pan(&mut self, pan: f32) -> AltoResult<()> { self.src.set_pan(pan) }
 
 	#[inline] fn soft_resampler(&self) -> AltoResult<sys::ALint> { self.src.soft_resampler() }
 	#[inline] fn set_soft_resampler(&mut self, value: sys::ALint) -> AltoResult<()> { self.src.set_soft_resampler(value) }
@@ -1761,6 +3450,16 @@ unsafe impl Source for StreamingSource {
 	#[inline] fn set_aux_send(&mut self, send: sys::ALint, slot: &mut AuxEffectSlot) -> AltoResult<()> { SourceInner::set_aux_send(&self.src, send, slot) }
 	#[inline] fn set_aux_send_filter<F: Filter>(&mut self, send: sys::ALint, slot: &mut AuxEffectSlot, filter: &F) -> AltoResult<()> { SourceInner::set_aux_send_filter(&self.src, send, slot, filter) }
 	#[inline] fn clear_aux_send(&mut self, send: sys::ALint) { self.src.clear_aux_send(send) }
+	#[inline] fn clear_aux_sends(&mut self, max_sends: Option<sys::ALint>) -> AltoResult<()> { self.src.clear_aux_sends(max_sends) }
+	#[inline] fn aux_send(&self, send: sys::ALint) -> Option<SendInfo> { self.src.aux_send(send) }
+	#[inline] fn aux_sends(&self) -> ::std::vec::IntoIter<(sys::ALint, SendInfo)> { self.src.aux_sends() }
+
+	#[inline] fn set_occlusion(&mut self, direct_lf: f32, direct_gain: f32, send_lf: f32, send_gain: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion{direct_lf, direct_gain, send_lf, send_gain}) }
+	#[inline] fn occlude(&mut self, amount: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion::occluded(amount)?) }
+	#[inline] fn obstruct(&mut self, amount: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion::obstructed(amount)?) }
+	#[inline] fn exclude(&mut self, amount: f32) -> AltoResult<()> { self.src.set_occlusion(Occlusion::excluded(amount)?) }
+	#[inline] fn clear_occlusion(&mut self) { self.src.clear_occlusion() }
+	#[inline] fn occlusion(&self) -> Option<Occlusion> { self.src.occlusion() }
 
 	#[inline] fn air_absorption_factor(&self) -> f32 { self.src.air_absorption_factor() }
 	#[inline] fn set_air_absorption_factor(&mut self, value: f32) -> AltoResult<()> { self.src.set_air_absorption_factor(value) }
@@ -1782,3 +3481,21 @@ impl PartialEq for StreamingSource {
 	}
 }
 impl Eq for StreamingSource { }
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+
+	#[test]
+	fn verify_loop_points() {
+		Buffer::verify_loop_points((10, 200), (10, 200)).unwrap();
+		for &actual in &[(0, 200), (10, 4410), (0, 0)] {
+			match Buffer::verify_loop_points((10, 200), actual) {
+				Err(AltoError::InvalidValue) => (),
+				r => panic!("{:?}", r),
+			}
+		}
+	}
+}
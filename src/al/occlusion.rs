@@ -0,0 +1,112 @@
+use ::{AltoError, AltoResult};
+use efx::LowpassFilter;
+
+
+/// Low-pass settings for the direct path and for aux send 0, as applied by `Source::set_occlusion`.
+/// A `*_lf` value is the high-frequency gain left by the low-pass (`AL_LOWPASS_GAINHF`),
+/// and a `*_gain` value is the broadband gain of the path (`AL_LOWPASS_GAIN`). All values are in `[0, 1]`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Occlusion {
+	pub direct_lf: f32,
+	pub direct_gain: f32,
+	pub send_lf: f32,
+	pub send_gain: f32,
+}
+
+
+/// Reference point for the high-frequency curves: an `amount` of 1 leaves 1% of the high band, or -40dB.
+const MAX_HF_ATTENUATION_DB: f32 = 40.0;
+
+
+impl Occlusion {
+	/// No filtering on either path.
+	pub const NONE: Occlusion = Occlusion{direct_lf: 1.0, direct_gain: 1.0, send_lf: 1.0, send_gain: 1.0};
+
+
+	/// A source behind a wall, cutting off both what reaches the listener directly and what reaches the room.
+	/// The direct path gets `gain = 1 - amount / 2` and `lf = 10^(-2 * amount)`,
+	/// and the send is ducked to `gain = 1 - amount / 2` without low-passing.
+	pub fn occluded(amount: f32) -> AltoResult<Occlusion> {
+		check_amount(amount)?;
+		Ok(Occlusion{
+			direct_lf: hf_curve(amount),
+			direct_gain: 1.0 - amount * 0.5,
+			send_lf: 1.0,
+			send_gain: 1.0 - amount * 0.5,
+		})
+	}
+
+
+	/// An obstacle between source and listener in the same room, muffling only the direct path.
+	/// The direct path gets `gain = 1 - amount / 4` and `lf = 10^(-2 * amount)`, and the send is untouched.
+	pub fn obstructed(amount: f32) -> AltoResult<Occlusion> {
+		check_amount(amount)?;
+		Ok(Occlusion{
+			direct_lf: hf_curve(amount),
+			direct_gain: 1.0 - amount * 0.25,
+			..Occlusion::NONE
+		})
+	}
+
+
+	/// A source heard directly through an opening but isolated from the listener's room, muffling only the send.
+	/// The send gets `gain = 1 - amount / 4` and `lf = 10^(-2 * amount)`, and the direct path is untouched.
+	pub fn excluded(amount: f32) -> AltoResult<Occlusion> {
+		check_amount(amount)?;
+		Ok(Occlusion{
+			send_lf: hf_curve(amount),
+			send_gain: 1.0 - amount * 0.25,
+			..Occlusion::NONE
+		})
+	}
+
+
+	/// Gain applied to the source in place of the direct low-pass when EFX is unavailable.
+	/// This is the direct gain scaled by the mean of the low and high band gains, `direct_gain * (1 + direct_lf) / 2`.
+	pub fn fallback_gain(&self) -> f32 {
+		self.direct_gain * (1.0 + self.direct_lf) * 0.5
+	}
+
+
+	pub(crate) fn validate(&self) -> AltoResult<()> {
+		for &v in &[self.direct_lf, self.direct_gain, self.send_lf, self.send_gain] {
+			if !(0.0 ..= 1.0).contains(&v) {
+				return Err(AltoError::InvalidValue);
+			}
+		}
+		Ok(())
+	}
+}
+
+
+impl Default for Occlusion {
+	fn default() -> Occlusion { Occlusion::NONE }
+}
+
+
+/// What a source is holding on to in order to apply its current occlusion.
+pub(crate) enum OcclusionState {
+	/// Filters owned by the source, one for the direct path and one for send 0.
+	Efx{direct: LowpassFilter, send: LowpassFilter, current: Occlusion},
+	/// No EFX, so only the source gain is lowered. `base` is the gain to restore when cleared.
+	Gain{base: f32, current: Occlusion},
+}
+
+
+impl OcclusionState {
+	pub(crate) fn current(&self) -> Occlusion {
+		match *self {
+			OcclusionState::Efx{current, ..} | OcclusionState::Gain{current, ..} => current,
+		}
+	}
+}
+
+
+fn check_amount(amount: f32) -> AltoResult<()> {
+	if (0.0 ..= 1.0).contains(&amount) { Ok(()) } else { Err(AltoError::InvalidValue) }
+}
+
+
+fn hf_curve(amount: f32) -> f32 {
+	10.0f32.powf(-amount * MAX_HF_ATTENUATION_DB / 20.0)
+}
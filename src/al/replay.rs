@@ -0,0 +1,751 @@
+//! Recording and replay of the AL calls made through a context, for reproducing driver bugs.
+//!
+//! A recording is started with `Context::start_recording` and captures every state-changing AL call
+//! the context makes on behalf of its sources, buffers and listener, along with the error each call
+//! raised when it was checked. Source and buffer names are remapped to stable indices in order of
+//! creation, so a log replays the same way regardless of the names a driver hands out.
+//! Buffer payloads are stored as a content hash, or embedded in full if requested; a hashed payload
+//! replays as silence of the same size.
+//!
+//! Only AL calls on sources, buffers, the listener and global state are recorded. Queries are not,
+//! nor are ALC calls such as device and context creation, or calls on EFX objects. Of the extension
+//! entry points, only `alSourcePlayAtTimeSOFT`, `alBufferSamplesSOFT` and `EAXSetBufferMode` are
+//! recorded. Names of EFX filters and effect slots appear in the log as raw values.
+//! A log therefore replays against whatever device and context it is given, which must be set up
+//! with the same extensions and EFX objects for calls that depend on them to behave the same.
+//!
+//! Logs are validated as they are read and replayed: lengths are capped, and each vector argument
+//! must have the number of elements its parameter takes, so a corrupt log is an error rather than
+//! an out of bounds read in the driver.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use ::{AltoError, AltoResult};
+use sys;
+use al::*;
+
+
+const MAGIC: &[u8; 8] = b"ALTOREC\x01";
+/// Largest buffer payload a log may describe, embedded or not. Logs of larger uploads are rejected when read.
+const MAX_PAYLOAD: u32 = 256 << 20;
+/// Largest number of buffers a single queue or unqueue call may name.
+const MAX_QUEUE: u32 = 1 << 16;
+/// Largest vector argument of any recorded call, which is `AL_ORIENTATION`.
+const MAX_VECTOR: u32 = 6;
+
+
+/// A single recorded AL call.
+/// Source and buffer fields hold indices assigned in order of creation rather than raw names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+	/// `alGenSources()`
+	GenSource{src: u32},
+	/// `alDeleteSources()`
+	DeleteSource{src: u32},
+	/// `alGenBuffers()`
+	GenBuffer{buf: u32},
+	/// `alDeleteBuffers()`
+	DeleteBuffer{buf: u32},
+	/// `alBufferData()`
+	BufferData{buf: u32, format: sys::ALenum, freq: sys::ALsizei, size: u32, hash: u64, data: Option<Vec<u8>>},
+	/// `alBufferSamplesSOFT()`
+	BufferSamples{buf: u32, freq: u32, internal_format: sys::ALenum, frames: u32, channels: sys::ALenum, ty: sys::ALenum, size: u32, hash: u64, data: Option<Vec<u8>>},
+	/// `alBufferiv()`
+	Bufferiv{buf: u32, param: sys::ALenum, values: Vec<sys::ALint>},
+	/// `EAXSetBufferMode()`
+	BufferMode{buf: u32, value: sys::ALint},
+	/// `alSourcei(AL_BUFFER)`
+	SourceBuffer{src: u32, buf: Option<u32>},
+	/// `alSourcei()`
+	Sourcei{src: u32, param: sys::ALenum, value: sys::ALint},
+	/// `alSourceiv()`
+	Sourceiv{src: u32, param: sys::ALenum, values: Vec<sys::ALint>},
+	/// `alSourcef()`
+	Sourcef{src: u32, param: sys::ALenum, value: f32},
+	/// `alSourcefv()`
+	Sourcefv{src: u32, param: sys::ALenum, values: Vec<f32>},
+	/// `alSourcePlay()`
+	SourcePlay{src: u32},
+	/// `alSourcePlayAtTimeSOFT()`
+	SourcePlayAtTime{src: u32, time: i64},
+	/// `alSourcePause()`
+	SourcePause{src: u32},
+	/// `alSourceStop()`
+	SourceStop{src: u32},
+	/// `alSourceRewind()`
+	SourceRewind{src: u32},
+	/// `alSourceQueueBuffers()`
+	SourceQueueBuffers{src: u32, bufs: Vec<u32>},
+	/// `alSourceUnqueueBuffers()`
+	SourceUnqueueBuffers{src: u32, count: u32},
+	/// `alListenerf()`
+	Listenerf{param: sys::ALenum, value: f32},
+	/// `alListenerfv()`
+	Listenerfv{param: sys::ALenum, values: Vec<f32>},
+	/// `alDistanceModel()`
+	DistanceModel{value: sys::ALenum},
+	/// `alDopplerFactor()`
+	DopplerFactor{value: f32},
+	/// `alSpeedOfSound()`
+	SpeedOfSound{value: f32},
+	/// `alEnable()`
+	Enable{cap: sys::ALenum},
+	/// `alDisable()`
+	Disable{cap: sys::ALenum},
+}
+
+
+/// A recorded call along with the error it raised.
+/// The error is `None` if the crate did not check for one after the call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+	pub call: Call,
+	pub error: Option<sys::ALenum>,
+}
+
+
+/// A call whose error on replay differs from the error recorded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+	/// Position of the call within the log.
+	pub index: usize,
+	pub call: Call,
+	pub recorded: sys::ALenum,
+	pub replayed: sys::ALenum,
+}
+
+
+/// A target that recorded calls can be replayed against.
+pub trait Backend {
+	/// Perform a call, returning the error it raised if the backend checks for one.
+	fn execute(&mut self, call: &Call) -> AltoResult<Option<sys::ALenum>>;
+}
+
+
+/// A backend that performs nothing and keeps the calls it receives, for inspecting a log.
+#[derive(Debug, Default)]
+pub struct NullBackend {
+	calls: Vec<Call>,
+}
+
+
+/// A backend that performs calls on a live context.
+/// Objects created during replay are deleted when the backend is dropped.
+pub struct LiveBackend<'c> {
+	ctx: &'c Context,
+	sources: HashMap<u32, sys::ALuint>,
+	buffers: HashMap<u32, sys::ALuint>,
+}
+
+
+pub(crate) struct Recorder {
+	out: BufWriter<File>,
+	embed: bool,
+	sources: HashMap<sys::ALuint, u32>,
+	buffers: HashMap<sys::ALuint, u32>,
+	next_source: u32,
+	next_buffer: u32,
+	pending: Option<Call>,
+	error: Option<io::Error>,
+}
+
+
+/// Read every entry from a log.
+pub fn read<P: AsRef<Path>>(path: P) -> AltoResult<Vec<Entry>> {
+	let mut input = BufReader::new(File::open(path)?);
+	let mut magic = [0; 8];
+	input.read_exact(&mut magic)?;
+	if &magic != MAGIC {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "not an alto recording").into());
+	}
+
+	let mut entries = Vec::new();
+	loop {
+		let mut tag = [0];
+		if input.read(&mut tag)? == 0 {
+			break;
+		}
+		entries.push(read_entry(&mut input, tag[0])?);
+	}
+	Ok(entries)
+}
+
+
+/// Write entries to a new log, replacing any existing file.
+/// Useful for trimming a recording down to a minimal repro.
+pub fn write<P: AsRef<Path>>(path: P, entries: &[Entry]) -> AltoResult<()> {
+	let mut out = BufWriter::new(File::create(path)?);
+	out.write_all(MAGIC)?;
+	for entry in entries {
+		write_entry(&mut out, entry)?;
+	}
+	out.flush()?;
+	Ok(())
+}
+
+
+/// Replay a log against a backend in order, returning every call whose error differs from the one recorded.
+pub fn run<P: AsRef<Path>, B: Backend>(path: P, backend: &mut B) -> AltoResult<Vec<Mismatch>> {
+	let mut mismatches = Vec::new();
+	for (index, entry) in read(path)?.into_iter().enumerate() {
+		let replayed = backend.execute(&entry.call)?;
+		if let (Some(recorded), Some(replayed)) = (entry.error, replayed) {
+			if recorded != replayed {
+				mismatches.push(Mismatch{index, call: entry.call, recorded, replayed});
+			}
+		}
+	}
+	Ok(mismatches)
+}
+
+
+/// FNV-1a hash of a buffer payload, as stored in `Call::BufferData`.
+pub fn payload_hash(data: &[u8]) -> u64 {
+	data.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+
+impl NullBackend {
+	pub fn new() -> NullBackend { NullBackend::default() }
+	/// Every call received so far, in order.
+	pub fn calls(&self) -> &[Call] { &self.calls }
+}
+
+
+impl Backend for NullBackend {
+	fn execute(&mut self, call: &Call) -> AltoResult<Option<sys::ALenum>> {
+		self.calls.push(call.clone());
+		Ok(None)
+	}
+}
+
+
+impl<'c> LiveBackend<'c> {
+	pub fn new(ctx: &'c Context) -> LiveBackend<'c> {
+		LiveBackend{ctx, sources: HashMap::new(), buffers: HashMap::new()}
+	}
+
+
+	fn source(&self, src: u32) -> AltoResult<sys::ALuint> {
+		self.sources.get(&src).cloned().ok_or(AltoError::InvalidName)
+	}
+	fn buffer(&self, buf: u32) -> AltoResult<sys::ALuint> {
+		self.buffers.get(&buf).cloned().ok_or(AltoError::InvalidName)
+	}
+
+
+	/// Check that `values` has exactly as many elements as `param` takes, as the driver will read that many.
+	/// Parameters that were never recorded as vectors are an `InvalidEnum`, and a wrong length an `InvalidValue`.
+	fn vector<'v, T>(&self, param: sys::ALenum, values: &'v [T]) -> AltoResult<&'v [T]> {
+		let exts = &self.ctx.0.exts;
+		let len = match param {
+			sys::AL_POSITION | sys::AL_VELOCITY | sys::AL_DIRECTION => 3,
+			sys::AL_ORIENTATION => 6,
+			_ => {
+				let params = [
+					(exts.AL_SOFT_loop_points().ok().and_then(|e| e.AL_LOOP_POINTS_SOFT.ok()), 2),
+					(exts.AL_SOFT_source_resampler().ok().and_then(|e| e.AL_SOURCE_RESAMPLER_SOFT.ok()), 1),
+					(exts.AL_EXT_STEREO_ANGLES().ok().and_then(|e| e.AL_STEREO_ANGLES.ok()), 2),
+					(exts.AL_EXT_SOURCE_RADIUS().ok().and_then(|e| e.AL_SOURCE_RADIUS.ok()), 1),
+					(self.ctx.0.dev.0.exts.ALC_EXT_EFX().ok().and_then(|e| e.AL_AUXILIARY_SEND_FILTER.ok()), 3),
+				];
+				match params.iter().find(|&&(p, _)| p == Some(param)) {
+					Some(&(_, len)) => len,
+					None => return Err(AltoError::InvalidEnum),
+				}
+			},
+		};
+		if values.len() == len { Ok(values) } else { Err(AltoError::InvalidValue) }
+	}
+
+
+	/// Size in bytes of one frame of `alBufferSamplesSOFT` data, as the driver will read `frames` of them.
+	fn sample_frame_size(&self, channels: sys::ALenum, ty: sys::ALenum) -> AltoResult<usize> {
+		let asbs = self.ctx.0.exts.AL_SOFT_buffer_samples()?;
+		let channels = match channels {
+			c if c == asbs.AL_MONO_SOFT? => 1,
+			c if c == asbs.AL_STEREO_SOFT? || c == asbs.AL_REAR_SOFT? => 2,
+			c if c == asbs.AL_QUAD_SOFT? => 4,
+			c if c == asbs.AL_5POINT1_SOFT? => 6,
+			c if c == asbs.AL_6POINT1_SOFT? => 7,
+			c if c == asbs.AL_7POINT1_SOFT? => 8,
+			_ => return Err(AltoError::InvalidEnum),
+		};
+		let bytes = match ty {
+			t if t == asbs.AL_BYTE_SOFT? || t == asbs.AL_UNSIGNED_BYTE_SOFT? => 1,
+			t if t == asbs.AL_SHORT_SOFT? || t == asbs.AL_UNSIGNED_SHORT_SOFT? => 2,
+			t if t == asbs.AL_BYTE3_SOFT? || t == asbs.AL_UNSIGNED_BYTE3_SOFT? => 3,
+			t if t == asbs.AL_INT_SOFT? || t == asbs.AL_UNSIGNED_INT_SOFT? || t == asbs.AL_FLOAT_SOFT? => 4,
+			t if t == asbs.AL_DOUBLE_SOFT? => 8,
+			_ => return Err(AltoError::InvalidEnum),
+		};
+		Ok(channels * bytes)
+	}
+}
+
+
+impl<'c> Backend for LiveBackend<'c> {
+	fn execute(&mut self, call: &Call) -> AltoResult<Option<sys::ALenum>> {
+		let api = &self.ctx.0.dev.0.alto.0.api;
+		let _lock = self.ctx.make_current(true);
+
+		unsafe {
+			match *call {
+				Call::GenSource{src} => {
+					let mut raw = 0;
+					api.alGenSources(1, &mut raw);
+					self.sources.insert(src, raw);
+				},
+				Call::DeleteSource{src} => {
+					let raw = self.source(src)?;
+					api.alDeleteSources(1, &raw);
+					self.sources.remove(&src);
+				},
+				Call::GenBuffer{buf} => {
+					let mut raw = 0;
+					api.alGenBuffers(1, &mut raw);
+					self.buffers.insert(buf, raw);
+				},
+				Call::DeleteBuffer{buf} => {
+					let raw = self.buffer(buf)?;
+					api.alDeleteBuffers(1, &raw);
+					self.buffers.remove(&buf);
+				},
+				Call::BufferData{buf, format, freq, size, ref data, ..} => {
+					let silence;
+					let data = match *data {
+						Some(ref data) => data,
+						None => {
+							silence = vec![if format == sys::AL_FORMAT_MONO8 || format == sys::AL_FORMAT_STEREO8 { 0x80 } else { 0 }; size as usize];
+							&silence
+						},
+					};
+					if data.len() != size as usize {
+						return Err(AltoError::InvalidValue);
+					}
+					api.alBufferData(self.buffer(buf)?, format, data.as_ptr() as *const sys::ALvoid, size as sys::ALsizei, freq);
+				},
+				Call::BufferSamples{buf, freq, internal_format, frames, channels, ty, size, ref data, ..} => {
+					let absc = self.ctx.0.exts.AL_SOFT_buffer_samples()?.alBufferSamplesSOFT?;
+					if u64::from(frames) * self.sample_frame_size(channels, ty)? as u64 != u64::from(size) {
+						return Err(AltoError::InvalidValue);
+					}
+					let silence;
+					let data = match *data {
+						Some(ref data) => data,
+						None => {
+							silence = vec![if ty == self.ctx.0.exts.AL_SOFT_buffer_samples()?.AL_UNSIGNED_BYTE_SOFT? { 0x80 } else { 0 }; size as usize];
+							&silence
+						},
+					};
+					if data.len() != size as usize {
+						return Err(AltoError::InvalidValue);
+					}
+					absc(self.buffer(buf)?, freq, internal_format, frames as sys::ALsizei, channels, ty, data.as_ptr() as *const sys::ALvoid);
+				},
+				Call::Bufferiv{buf, param, ref values} => api.alBufferiv(self.buffer(buf)?, param, self.vector(param, values)?.as_ptr()),
+				Call::BufferMode{buf, value} => {
+					let esbm = self.ctx.0.exts.EAX_RAM()?.EAXSetBufferMode?;
+					esbm(1, &self.buffer(buf)?, value);
+				},
+				Call::SourceBuffer{src, buf} => {
+					let buf = match buf { Some(buf) => self.buffer(buf)?, None => 0 };
+					api.alSourcei(self.source(src)?, sys::AL_BUFFER, buf as sys::ALint);
+				},
+				Call::Sourcei{src, param, value} => api.alSourcei(self.source(src)?, param, value),
+				Call::Sourceiv{src, param, ref values} => api.alSourceiv(self.source(src)?, param, self.vector(param, values)?.as_ptr()),
+				Call::Sourcef{src, param, value} => api.alSourcef(self.source(src)?, param, value),
+				Call::Sourcefv{src, param, ref values} => api.alSourcefv(self.source(src)?, param, self.vector(param, values)?.as_ptr()),
+				Call::SourcePlay{src} => api.alSourcePlay(self.source(src)?),
+				Call::SourcePlayAtTime{src, time} => self.ctx.0.exts.AL_SOFT_source_start_delay()?.alSourcePlayAtTimeSOFT?(self.source(src)?, time),
+				Call::SourcePause{src} => api.alSourcePause(self.source(src)?),
+				Call::SourceStop{src} => api.alSourceStop(self.source(src)?),
+				Call::SourceRewind{src} => api.alSourceRewind(self.source(src)?),
+				Call::SourceQueueBuffers{src, ref bufs} => {
+					let bufs = bufs.iter().map(|&b| self.buffer(b)).collect::<AltoResult<Vec<_>>>()?;
+					api.alSourceQueueBuffers(self.source(src)?, bufs.len() as sys::ALsizei, bufs.as_ptr());
+				},
+				Call::SourceUnqueueBuffers{src, count} => {
+					if count > MAX_QUEUE {
+						return Err(AltoError::InvalidValue);
+					}
+					let mut bufs = vec![0; count as usize];
+					api.alSourceUnqueueBuffers(self.source(src)?, count as sys::ALsizei, bufs.as_mut_ptr());
+				},
+				Call::Listenerf{param, value} => api.alListenerf(param, value),
+				Call::Listenerfv{param, ref values} => api.alListenerfv(param, self.vector(param, values)?.as_ptr()),
+				Call::DistanceModel{value} => api.alDistanceModel(value),
+				Call::DopplerFactor{value} => api.alDopplerFactor(value),
+				Call::SpeedOfSound{value} => api.alSpeedOfSound(value),
+				Call::Enable{cap} => api.alEnable(cap),
+				Call::Disable{cap} => api.alDisable(cap),
+			}
+
+			Ok(Some(api.alGetError()))
+		}
+	}
+}
+
+
+impl<'c> Drop for LiveBackend<'c> {
+	fn drop(&mut self) {
+		let api = &self.ctx.0.dev.0.alto.0.api;
+		let _lock = self.ctx.make_current(true);
+		for (_, src) in self.sources.drain() {
+			unsafe { api.alDeleteSources(1, &src); }
+		}
+		for (_, buf) in self.buffers.drain() {
+			unsafe { api.alDeleteBuffers(1, &buf); }
+		}
+		unsafe { api.alGetError(); }
+	}
+}
+
+
+impl Recorder {
+	pub(crate) fn create(path: &Path, embed: bool) -> AltoResult<Recorder> {
+		let mut out = BufWriter::new(File::create(path)?);
+		out.write_all(MAGIC)?;
+		Ok(Recorder{
+			out,
+			embed,
+			sources: HashMap::new(),
+			buffers: HashMap::new(),
+			next_source: 0,
+			next_buffer: 0,
+			pending: None,
+			error: None,
+		})
+	}
+
+
+	/// Queue a call, which is written once its error is known or the next call is made.
+	pub(crate) fn push(&mut self, call: Call) {
+		self.flush_pending(None);
+		self.pending = Some(call);
+	}
+	/// Attach the result of `alGetError` to the pending call.
+	pub(crate) fn finish(&mut self, error: sys::ALenum) {
+		self.flush_pending(Some(error));
+	}
+	/// Write everything out, returning the first IO error encountered while recording.
+	pub(crate) fn close(mut self) -> AltoResult<()> {
+		self.flush_pending(None);
+		if let Some(e) = self.error.take() {
+			return Err(e.into());
+		}
+		self.out.flush()?;
+		Ok(())
+	}
+
+
+	pub(crate) fn gen_source(&mut self, raw: sys::ALuint) -> u32 {
+		let src = self.next_source;
+		self.next_source += 1;
+		self.sources.insert(raw, src);
+		src
+	}
+	pub(crate) fn delete_source(&mut self, raw: sys::ALuint) -> u32 {
+		let src = self.source(raw);
+		self.sources.remove(&raw);
+		src
+	}
+	/// Index of a source, introducing it to the log if it predates the recording.
+	pub(crate) fn source(&mut self, raw: sys::ALuint) -> u32 {
+		if let Some(&src) = self.sources.get(&raw) {
+			return src;
+		}
+		let src = self.gen_source(raw);
+		self.emit(&Entry{call: Call::GenSource{src}, error: None});
+		src
+	}
+
+
+	pub(crate) fn gen_buffer(&mut self, raw: sys::ALuint) -> u32 {
+		let buf = self.next_buffer;
+		self.next_buffer += 1;
+		self.buffers.insert(raw, buf);
+		buf
+	}
+	pub(crate) fn delete_buffer(&mut self, raw: sys::ALuint) -> u32 {
+		let buf = self.buffer(raw);
+		self.buffers.remove(&raw);
+		buf
+	}
+	/// Index of a buffer, introducing it to the log if it predates the recording.
+	/// The contents of such a buffer are unknown and will not be replayed.
+	pub(crate) fn buffer(&mut self, raw: sys::ALuint) -> u32 {
+		if let Some(&buf) = self.buffers.get(&raw) {
+			return buf;
+		}
+		let buf = self.gen_buffer(raw);
+		self.emit(&Entry{call: Call::GenBuffer{buf}, error: None});
+		buf
+	}
+
+
+	pub(crate) fn buffer_data(&mut self, raw: sys::ALuint, format: sys::ALenum, data: *const sys::ALvoid, size: usize, freq: sys::ALsizei) -> Call {
+		let (hash, data) = self.payload(data, size);
+		Call::BufferData{buf: self.buffer(raw), format, freq, size: size as u32, hash, data}
+	}
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) fn buffer_samples(&mut self, raw: sys::ALuint, freq: u32, internal_format: sys::ALenum, frames: u32, channels: sys::ALenum, ty: sys::ALenum, data: *const sys::ALvoid, size: usize) -> Call {
+		let (hash, data) = self.payload(data, size);
+		Call::BufferSamples{buf: self.buffer(raw), freq, internal_format, frames, channels, ty, size: size as u32, hash, data}
+	}
+	/// Hash of the `size` bytes at `data`, and the bytes themselves if they are being embedded.
+	fn payload(&self, data: *const sys::ALvoid, size: usize) -> (u64, Option<Vec<u8>>) {
+		let bytes = if data.is_null() || size == 0 { &[][..] } else { unsafe { ::std::slice::from_raw_parts(data as *const u8, size) } };
+		(payload_hash(bytes), if self.embed { Some(bytes.to_vec()) } else { None })
+	}
+
+
+	fn flush_pending(&mut self, error: Option<sys::ALenum>) {
+		if let Some(call) = self.pending.take() {
+			self.emit(&Entry{call, error});
+		}
+	}
+
+
+	fn emit(&mut self, entry: &Entry) {
+		self.flush_pending(None);
+		if self.error.is_none() {
+			if let Err(e) = write_entry(&mut self.out, entry) {
+				self.error = Some(e);
+			}
+		}
+	}
+}
+
+
+fn write_entry<W: Write>(out: &mut W, entry: &Entry) -> io::Result<()> {
+	let tag = match entry.call {
+		Call::GenSource{..} => 0,
+		Call::DeleteSource{..} => 1,
+		Call::GenBuffer{..} => 2,
+		Call::DeleteBuffer{..} => 3,
+		Call::BufferData{..} => 4,
+		Call::Bufferiv{..} => 5,
+		Call::SourceBuffer{..} => 6,
+		Call::Sourcei{..} => 7,
+		Call::Sourceiv{..} => 8,
+		Call::Sourcef{..} => 9,
+		Call::Sourcefv{..} => 10,
+		Call::SourcePlay{..} => 11,
+		Call::SourcePause{..} => 12,
+		Call::SourceStop{..} => 13,
+		Call::SourceRewind{..} => 14,
+		Call::SourceQueueBuffers{..} => 15,
+		Call::SourceUnqueueBuffers{..} => 16,
+		Call::Listenerf{..} => 17,
+		Call::Listenerfv{..} => 18,
+		Call::DistanceModel{..} => 19,
+		Call::DopplerFactor{..} => 20,
+		Call::SpeedOfSound{..} => 21,
+		Call::Enable{..} => 22,
+		Call::Disable{..} => 23,
+		Call::SourcePlayAtTime{..} => 24,
+		Call::BufferSamples{..} => 25,
+		Call::BufferMode{..} => 26,
+	};
+	out.write_all(&[tag])?;
+	match entry.error {
+		Some(e) => { out.write_all(&[1])?; write_i32(out, e)?; },
+		None => out.write_all(&[0])?,
+	}
+
+	match entry.call {
+		Call::GenSource{src} | Call::DeleteSource{src} | Call::SourcePlay{src} | Call::SourcePause{src}
+		| Call::SourceStop{src} | Call::SourceRewind{src} => write_u32(out, src),
+		Call::GenBuffer{buf} | Call::DeleteBuffer{buf} => write_u32(out, buf),
+		Call::BufferData{buf, format, freq, size, hash, ref data} => {
+			write_u32(out, buf)?;
+			write_i32(out, format)?;
+			write_i32(out, freq)?;
+			write_u32(out, size)?;
+			out.write_all(&hash.to_le_bytes())?;
+			match *data {
+				Some(ref data) => { out.write_all(&[1])?; out.write_all(data) },
+				None => out.write_all(&[0]),
+			}
+		},
+		Call::BufferSamples{buf, freq, internal_format, frames, channels, ty, size, hash, ref data} => {
+			write_u32(out, buf)?;
+			write_u32(out, freq)?;
+			write_i32(out, internal_format)?;
+			write_u32(out, frames)?;
+			write_i32(out, channels)?;
+			write_i32(out, ty)?;
+			write_u32(out, size)?;
+			out.write_all(&hash.to_le_bytes())?;
+			match *data {
+				Some(ref data) => { out.write_all(&[1])?; out.write_all(data) },
+				None => out.write_all(&[0]),
+			}
+		},
+		Call::Bufferiv{buf, param, ref values} => { write_u32(out, buf)?; write_i32(out, param)?; write_ints(out, values) },
+		Call::BufferMode{buf, value} => { write_u32(out, buf)?; write_i32(out, value) },
+		Call::SourceBuffer{src, buf} => { write_u32(out, src)?; write_u32(out, buf.map(|b| b + 1).unwrap_or(0)) },
+		Call::Sourcei{src, param, value} => { write_u32(out, src)?; write_i32(out, param)?; write_i32(out, value) },
+		Call::Sourceiv{src, param, ref values} => { write_u32(out, src)?; write_i32(out, param)?; write_ints(out, values) },
+		Call::Sourcef{src, param, value} => { write_u32(out, src)?; write_i32(out, param)?; write_f32(out, value) },
+		Call::Sourcefv{src, param, ref values} => { write_u32(out, src)?; write_i32(out, param)?; write_floats(out, values) },
+		Call::SourceQueueBuffers{src, ref bufs} => {
+			write_u32(out, src)?;
+			write_u32(out, bufs.len() as u32)?;
+			bufs.iter().try_for_each(|&b| write_u32(out, b))
+		},
+		Call::SourceUnqueueBuffers{src, count} => { write_u32(out, src)?; write_u32(out, count) },
+		Call::SourcePlayAtTime{src, time} => { write_u32(out, src)?; out.write_all(&time.to_le_bytes()) },
+		Call::Listenerf{param, value} => { write_i32(out, param)?; write_f32(out, value) },
+		Call::Listenerfv{param, ref values} => { write_i32(out, param)?; write_floats(out, values) },
+		Call::DistanceModel{value} => write_i32(out, value),
+		Call::DopplerFactor{value} | Call::SpeedOfSound{value} => write_f32(out, value),
+		Call::Enable{cap} | Call::Disable{cap} => write_i32(out, cap),
+	}
+}
+
+
+fn read_entry<R: Read>(input: &mut R, tag: u8) -> AltoResult<Entry> {
+	let error = match read_u8(input)? {
+		0 => None,
+		_ => Some(read_i32(input)?),
+	};
+
+	let call = match tag {
+		0 => Call::GenSource{src: read_u32(input)?},
+		1 => Call::DeleteSource{src: read_u32(input)?},
+		2 => Call::GenBuffer{buf: read_u32(input)?},
+		3 => Call::DeleteBuffer{buf: read_u32(input)?},
+		4 => {
+			let buf = read_u32(input)?;
+			let format = read_i32(input)?;
+			let freq = read_i32(input)?;
+			let size = read_len(input, MAX_PAYLOAD)?;
+			let (hash, data) = read_payload(input, size)?;
+			Call::BufferData{buf, format, freq, size, hash, data}
+		},
+		5 => Call::Bufferiv{buf: read_u32(input)?, param: read_i32(input)?, values: read_ints(input)?},
+		6 => {
+			let src = read_u32(input)?;
+			let buf = read_u32(input)?;
+			Call::SourceBuffer{src, buf: if buf == 0 { None } else { Some(buf - 1) }}
+		},
+		7 => Call::Sourcei{src: read_u32(input)?, param: read_i32(input)?, value: read_i32(input)?},
+		8 => Call::Sourceiv{src: read_u32(input)?, param: read_i32(input)?, values: read_ints(input)?},
+		9 => Call::Sourcef{src: read_u32(input)?, param: read_i32(input)?, value: read_f32(input)?},
+		10 => Call::Sourcefv{src: read_u32(input)?, param: read_i32(input)?, values: read_floats(input)?},
+		11 => Call::SourcePlay{src: read_u32(input)?},
+		12 => Call::SourcePause{src: read_u32(input)?},
+		13 => Call::SourceStop{src: read_u32(input)?},
+		14 => Call::SourceRewind{src: read_u32(input)?},
+		15 => {
+			let src = read_u32(input)?;
+			let len = read_len(input, MAX_QUEUE)?;
+			let bufs = (0 .. len).map(|_| read_u32(input)).collect::<io::Result<_>>()?;
+			Call::SourceQueueBuffers{src, bufs}
+		},
+		16 => Call::SourceUnqueueBuffers{src: read_u32(input)?, count: read_len(input, MAX_QUEUE)?},
+		17 => Call::Listenerf{param: read_i32(input)?, value: read_f32(input)?},
+		18 => Call::Listenerfv{param: read_i32(input)?, values: read_floats(input)?},
+		19 => Call::DistanceModel{value: read_i32(input)?},
+		20 => Call::DopplerFactor{value: read_f32(input)?},
+		21 => Call::SpeedOfSound{value: read_f32(input)?},
+		22 => Call::Enable{cap: read_i32(input)?},
+		23 => Call::Disable{cap: read_i32(input)?},
+		24 => {
+			let src = read_u32(input)?;
+			let mut time = [0; 8];
+			input.read_exact(&mut time)?;
+			Call::SourcePlayAtTime{src, time: i64::from_le_bytes(time)}
+		},
+		25 => {
+			let buf = read_u32(input)?;
+			let freq = read_u32(input)?;
+			let internal_format = read_i32(input)?;
+			let frames = read_u32(input)?;
+			let channels = read_i32(input)?;
+			let ty = read_i32(input)?;
+			let size = read_len(input, MAX_PAYLOAD)?;
+			let (hash, data) = read_payload(input, size)?;
+			Call::BufferSamples{buf, freq, internal_format, frames, channels, ty, size, hash, data}
+		},
+		26 => Call::BufferMode{buf: read_u32(input)?, value: read_i32(input)?},
+		_ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown call in alto recording").into()),
+	};
+
+	Ok(Entry{call, error})
+}
+
+
+fn write_u32<W: Write>(out: &mut W, value: u32) -> io::Result<()> { out.write_all(&value.to_le_bytes()) }
+fn write_i32<W: Write>(out: &mut W, value: i32) -> io::Result<()> { out.write_all(&value.to_le_bytes()) }
+fn write_f32<W: Write>(out: &mut W, value: f32) -> io::Result<()> { out.write_all(&value.to_le_bytes()) }
+fn write_ints<W: Write>(out: &mut W, values: &[i32]) -> io::Result<()> {
+	write_u32(out, values.len() as u32)?;
+	values.iter().try_for_each(|&v| write_i32(out, v))
+}
+fn write_floats<W: Write>(out: &mut W, values: &[f32]) -> io::Result<()> {
+	write_u32(out, values.len() as u32)?;
+	values.iter().try_for_each(|&v| write_f32(out, v))
+}
+
+
+fn read_u8<R: Read>(input: &mut R) -> io::Result<u8> {
+	let mut bytes = [0];
+	input.read_exact(&mut bytes)?;
+	Ok(bytes[0])
+}
+fn read_u32<R: Read>(input: &mut R) -> io::Result<u32> {
+	let mut bytes = [0; 4];
+	input.read_exact(&mut bytes)?;
+	Ok(u32::from_le_bytes(bytes))
+}
+fn read_i32<R: Read>(input: &mut R) -> io::Result<i32> {
+	let mut bytes = [0; 4];
+	input.read_exact(&mut bytes)?;
+	Ok(i32::from_le_bytes(bytes))
+}
+fn read_f32<R: Read>(input: &mut R) -> io::Result<f32> {
+	let mut bytes = [0; 4];
+	input.read_exact(&mut bytes)?;
+	Ok(f32::from_le_bytes(bytes))
+}
+/// Read a length, rejecting any above `max` before anything is allocated for it.
+fn read_len<R: Read>(input: &mut R, max: u32) -> io::Result<u32> {
+	let len = read_u32(input)?;
+	if len > max {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "length out of range in alto recording"));
+	}
+	Ok(len)
+}
+/// Read a payload hash, followed by `size` bytes of data if they were embedded.
+fn read_payload<R: Read>(input: &mut R, size: u32) -> io::Result<(u64, Option<Vec<u8>>)> {
+	let mut hash = [0; 8];
+	input.read_exact(&mut hash)?;
+	let data = match read_u8(input)? {
+		0 => None,
+		_ => {
+			// Read no more than the log holds, rather than trusting `size` up front.
+			let mut data = Vec::new();
+			input.by_ref().take(u64::from(size)).read_to_end(&mut data)?;
+			if data.len() != size as usize {
+				return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated buffer data in alto recording"));
+			}
+			Some(data)
+		},
+	};
+	Ok((u64::from_le_bytes(hash), data))
+}
+fn read_ints<R: Read>(input: &mut R) -> io::Result<Vec<i32>> {
+	let len = read_len(input, MAX_VECTOR)?;
+	(0 .. len).map(|_| read_i32(input)).collect()
+}
+fn read_floats<R: Read>(input: &mut R) -> io::Result<Vec<f32>> {
+	let len = read_len(input, MAX_VECTOR)?;
+	(0 .. len).map(|_| read_f32(input)).collect()
+}
+
@@ -0,0 +1,182 @@
+use ::{AltoError, AltoResult, BufferConstraint};
+use conv::{self, ConvertOptions};
+use al::*;
+
+
+/// A destination for interleaved `f32` audio, as produced by callback based audio libraries.
+/// Samples are normalized to `[-1, 1]`.
+pub trait AudioSink {
+	/// Number of interleaved channels expected by `write`.
+	fn channels(&self) -> usize;
+	/// Sample rate expected by `write`.
+	fn frequency(&self) -> u32;
+	/// Play `frames`, a whole number of interleaved frames, after everything written before.
+	fn write(&mut self, frames: &[f32]) -> AltoResult<()>;
+}
+
+
+/// Bookkeeping for buffers handed to a streaming source, so that played buffers are reused rather than reallocated.
+/// Each buffer is either free, waiting to be filled, or in flight on the source.
+pub struct BufferPool<B> {
+	free: Vec<B>,
+	in_flight: usize,
+	allocated: usize,
+}
+
+
+impl<B> BufferPool<B> {
+	pub fn new() -> BufferPool<B> {
+		BufferPool{free: Vec::new(), in_flight: 0, allocated: 0}
+	}
+
+
+	/// Take a free buffer, or allocate one with `alloc` if there are none, and count it as in flight.
+	pub fn acquire<A: FnOnce() -> AltoResult<B>>(&mut self, alloc: A) -> AltoResult<B> {
+		let buf = match self.free.pop() {
+			Some(buf) => buf,
+			None => {
+				let buf = alloc()?;
+				self.allocated += 1;
+				buf
+			},
+		};
+		self.in_flight += 1;
+		Ok(buf)
+	}
+
+
+	/// Return a buffer that is no longer in flight, such as one the source has finished playing.
+	pub fn release(&mut self, buf: B) {
+		self.in_flight = self.in_flight.saturating_sub(1);
+		self.free.push(buf);
+	}
+
+
+	/// Buffers acquired and not yet released.
+	pub fn in_flight(&self) -> usize { self.in_flight }
+	/// Buffers waiting to be reused.
+	pub fn free(&self) -> usize { self.free.len() }
+	/// Buffers allocated over the lifetime of the pool.
+	pub fn allocated(&self) -> usize { self.allocated }
+}
+
+
+impl<B> Default for BufferPool<B> {
+	fn default() -> BufferPool<B> { BufferPool::new() }
+}
+
+
+/// Plays interleaved `f32` audio written to it through a streaming source of its own, as returned by `Context::sink`.
+/// Each write is uploaded to a buffer and queued, reusing buffers the source has finished with.
+/// The source starts playing on the first write, and is restarted by later writes if it runs dry.
+/// A sink that has been paused through its source stays paused.
+pub struct Sink {
+	ctx: Context,
+	src: StreamingSource,
+	format: Format,
+	freq: u32,
+	pool: BufferPool<Buffer>,
+}
+
+
+impl Sink {
+	/// Create a sink on a new streaming source.
+	/// `format` may be mono or stereo, either `StandardFormat::*I16`, which the samples are converted to,
+	/// or `ExtFloat32Format::*`, which they are uploaded as. Other formats are an `InvalidValue`.
+	pub fn new(ctx: &Context, format: Format, freq: u32) -> AltoResult<Sink> {
+		match format {
+			Format::Standard(StandardFormat::MonoI16) | Format::Standard(StandardFormat::StereoI16)
+				| Format::ExtFloat32(ExtFloat32Format::Mono) | Format::ExtFloat32(ExtFloat32Format::Stereo) => (),
+			_ => return Err(AltoError::InvalidValue),
+		}
+		if freq == 0 || freq > sys::ALint::MAX as u32 {
+			return Err(AltoError::InvalidValue);
+		}
+		format.into_raw(Some(ctx))?;
+
+		Ok(Sink{ctx: ctx.clone(), src: ctx.new_streaming_source()?, format, freq, pool: BufferPool::new()})
+	}
+
+
+	/// The underlying streaming source.
+	/// Buffers should not be queued or unqueued through it directly.
+	pub fn source(&self) -> &StreamingSource { &self.src }
+	/// The format audio is uploaded in.
+	pub fn format(&self) -> Format { self.format }
+	/// The buffers in use by this sink.
+	pub fn pool(&self) -> &BufferPool<Buffer> { &self.pool }
+
+
+	fn reclaim(&mut self) -> AltoResult<()> {
+		for _ in 0 .. self.src.buffers_processed() {
+			let buf = self.src.unqueue_buffer()?;
+			self.pool.release(buf);
+		}
+		Ok(())
+	}
+
+
+	fn upload(&self, buf: &mut Buffer, frames: &[f32]) -> AltoResult<()> {
+		let freq = self.freq as sys::ALint;
+		match self.format {
+			Format::Standard(StandardFormat::MonoI16) => {
+				let data: Vec<_> = conv::f32_to_i16(frames, &ConvertOptions::default()).into_iter().map(|center| Mono{center}).collect();
+				buf.set_data(&data[..], freq)
+			},
+			Format::Standard(_) => {
+				let data: Vec<_> = conv::f32_to_i16(frames, &ConvertOptions::default()).chunks(2).map(|f| Stereo{left: f[0], right: f[1]}).collect();
+				buf.set_data(&data[..], freq)
+			},
+			Format::ExtFloat32(ExtFloat32Format::Mono) => {
+				let data: Vec<_> = frames.iter().map(|&center| Mono{center}).collect();
+				buf.set_data(&data[..], freq)
+			},
+			_ => {
+				let data: Vec<_> = frames.chunks(2).map(|f| Stereo{left: f[0], right: f[1]}).collect();
+				buf.set_data(&data[..], freq)
+			},
+		}
+	}
+}
+
+
+impl AudioSink for Sink {
+	fn channels(&self) -> usize {
+		match self.format {
+			Format::Standard(StandardFormat::MonoI16) | Format::ExtFloat32(ExtFloat32Format::Mono) => 1,
+			_ => 2,
+		}
+	}
+	fn frequency(&self) -> u32 { self.freq }
+
+
+	/// `alSourceQueueBuffers()`
+	fn write(&mut self, frames: &[f32]) -> AltoResult<()> {
+		let channels = self.channels();
+		if !frames.len().is_multiple_of(channels) {
+			return Err(AltoError::InvalidBufferData(BufferConstraint::PartialFrame{size: frames.len() * 4, frame_size: channels * 4}));
+		}
+		if frames.is_empty() {
+			return Ok(());
+		}
+
+		self.reclaim()?;
+		let ctx = &self.ctx;
+		let mut buf = self.pool.acquire(|| Buffer::new_empty(ctx.clone()))?;
+		if let Err(e) = self.upload(&mut buf, frames) {
+			self.pool.release(buf);
+			return Err(e);
+		}
+		if let Err(e) = self.src.queue_buffer(buf) {
+			// The buffer is dropped along with the error, so it no longer counts against the pool.
+			self.pool.in_flight -= 1;
+			return Err(e);
+		}
+
+		match self.src.state() {
+			SourceState::Initial | SourceState::Stopped => self.src.play(),
+			_ => (),
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ::{AltoError, AltoResult};
+use al::*;
+
+
+/// A producer of sample frames that can be streamed through a `SourceStream`, such as an audio decoder.
+pub trait SampleSource<F: SampleFrame> {
+	/// Sample rate of the produced frames.
+	fn frequency(&self) -> i32;
+	/// Append up to `max` frames to `out`, returning how many were appended.
+	/// Returning 0 signals the end of the stream.
+	fn read(&mut self, out: &mut Vec<F>, max: usize) -> AltoResult<usize>;
+	/// Reposition so the next read starts at `frame`.
+	/// Positions past the end are clamped, and the frame actually reached is returned.
+	fn seek(&mut self, frame: u64) -> AltoResult<u64>;
+}
+
+
+/// Plays a `SampleSource` through a streaming source, keeping a fixed number of buffers in flight.
+/// `update` must be called regularly to refill the queue as buffers are played.
+pub struct SourceStream<F: SampleFrame, S: SampleSource<F>> {
+	src: StreamingSource,
+	samples: S,
+	chunk: usize,
+	scratch: Vec<F>,
+	queued: VecDeque<usize>,
+	/// Buffers that are not queued, waiting for more frames.
+	spare: Vec<Buffer>,
+	played: u64,
+	ended: bool,
+	paused: bool,
+}
+
+
+impl<F: SampleFrame, S: SampleSource<F>> SourceStream<F, S> {
+	/// Create a stream on a new streaming source and queue up to `bufs` buffers of `chunk` frames each.
+	pub fn new(ctx: &Context, samples: S, chunk: usize, bufs: usize) -> AltoResult<SourceStream<F, S>> {
+		if chunk == 0 || bufs == 0 {
+			return Err(AltoError::InvalidValue);
+		}
+
+		let mut stream = SourceStream{
+			src: ctx.new_streaming_source()?,
+			samples,
+			chunk,
+			scratch: Vec::with_capacity(chunk),
+			queued: VecDeque::with_capacity(bufs),
+			spare: Vec::with_capacity(bufs),
+			played: 0,
+			ended: false,
+			paused: false,
+		};
+		for _ in 0 .. bufs {
+			stream.spare.push(Buffer::new_empty(ctx.clone())?);
+		}
+		stream.refill()?;
+		Ok(stream)
+	}
+
+
+	/// The underlying streaming source.
+	/// Buffers should not be queued or unqueued through it directly.
+	pub fn source(&self) -> &StreamingSource { &self.src }
+	/// The underlying streaming source.
+	/// Buffers should not be queued or unqueued through it directly.
+	pub fn source_mut(&mut self) -> &mut StreamingSource { &mut self.src }
+	/// The producer of frames for this stream.
+	pub fn samples(&self) -> &S { &self.samples }
+	/// Whether the producer has run out of frames.
+	/// Buffers queued before the end may still be playing.
+	pub fn is_ended(&self) -> bool { self.ended }
+	/// Whether the stream was paused with `pause`, and not played since.
+	pub fn is_paused(&self) -> bool { self.paused }
+
+
+	/// `alSourcePlay()`
+	pub fn play(&mut self) {
+		self.paused = false;
+		self.src.play()
+	}
+	/// `alSourcePause()`
+	pub fn pause(&mut self) {
+		self.paused = true;
+		self.src.pause()
+	}
+
+
+	/// `alSourceUnqueueBuffers()`
+	/// Recycle processed buffers, refilling them from the producer, and restart playback if the queue ran dry.
+	/// Buffers left over once the producer is exhausted are kept, so that a later `seek` can refill them.
+	pub fn update(&mut self) -> AltoResult<()> {
+		for _ in 0 .. self.src.buffers_processed() {
+			self.spare.push(self.src.unqueue_buffer()?);
+			self.played += self.queued.pop_front().unwrap_or(0) as u64;
+		}
+		self.refill()?;
+
+		if !self.paused && self.src.state() == SourceState::Stopped && self.src.buffers_queued() > 0 {
+			self.src.play();
+		}
+		Ok(())
+	}
+
+
+	/// `alGetSourcei(AL_SAMPLE_OFFSET)`
+	/// The playback position within the stream.
+	pub fn position(&self) -> Duration {
+		let offset = if self.queued.is_empty() { 0 } else { self.src.sample_offset().max(0) as u64 };
+		frames_to_duration(self.played + offset, self.samples.frequency())
+	}
+
+
+	/// `alSourceStop()`
+	/// Jump to `to` within the stream, restoring the prior playing or paused state afterwards.
+	/// The queue is flushed and refilled from the new position, which `position` reports immediately.
+	/// A paused stream is left rewound rather than paused, and resumes from `to` on the next `play`.
+	/// Seeking past the end clamps to the end and leaves the stream ended.
+	pub fn seek(&mut self, to: Duration) -> AltoResult<()> {
+		let state = self.src.state();
+		self.src.stop();
+
+		while self.src.buffers_queued() > 0 {
+			self.spare.push(self.src.unqueue_buffer()?);
+		}
+		self.queued.clear();
+
+		let freq = self.samples.frequency();
+		let target = to.as_secs() * freq as u64 + u64::from(to.subsec_nanos()) * freq as u64 / 1_000_000_000;
+		self.played = self.samples.seek(target)?;
+		self.ended = false;
+		self.refill()?;
+
+		match state {
+			SourceState::Playing => self.src.play(),
+			SourceState::Paused | SourceState::Initial => self.src.rewind(),
+			_ => (),
+		}
+		Ok(())
+	}
+
+
+	/// Fill and queue spare buffers until they run out or the producer is exhausted.
+	fn refill(&mut self) -> AltoResult<()> {
+		while !self.ended {
+			match self.spare.pop() {
+				Some(buf) => if !self.fill(buf)? { break; },
+				None => break,
+			}
+		}
+		Ok(())
+	}
+
+
+	/// Fill `buf` with the next chunk and queue it, returning `false` once the producer is exhausted.
+	/// An unused buffer is returned to the spare list.
+	fn fill(&mut self, mut buf: Buffer) -> AltoResult<bool> {
+		if self.ended {
+			self.spare.push(buf);
+			return Ok(false);
+		}
+
+		self.scratch.clear();
+		while self.scratch.len() < self.chunk {
+			let max = self.chunk - self.scratch.len();
+			if self.samples.read(&mut self.scratch, max)? == 0 {
+				self.ended = true;
+				break;
+			}
+		}
+		if self.scratch.is_empty() {
+			self.spare.push(buf);
+			return Ok(false);
+		}
+
+		buf.set_data(&self.scratch[..], self.samples.frequency())?;
+		self.src.queue_buffer(buf)?;
+		self.queued.push_back(self.scratch.len());
+		Ok(true)
+	}
+}
+
+
+fn frames_to_duration(frames: u64, freq: i32) -> Duration {
+	if freq <= 0 {
+		return Duration::from_secs(0);
+	}
+
+	let freq = freq as u64;
+	Duration::new(frames / freq, ((frames % freq) * 1_000_000_000 / freq) as u32)
+}
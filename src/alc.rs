@@ -1,18 +1,29 @@
 use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::error::Error as StdError;
 use std::any::Any;
 use std::ptr;
 use std::mem;
+use std::slice;
 use std::ffi::{CString, CStr};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::path::Path;
 use std::marker::PhantomData;
+use std::time::Duration;
+use parking_lot::Mutex;
 
-use ::{AltoError, AltoResult};
+use ::{AltoError, AltoResult, Version};
 use sys;
 use al::*;
 use ext;
 
 
+static NEXT_DEVICE_ID: AtomicUsize = AtomicUsize::new(0);
+
+
 /// Attributes that may be supplied during context creation.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
 pub struct ContextAttrs {
@@ -39,6 +50,16 @@ pub struct ContextAttrs {
 }
 
 
+impl ContextAttrs {
+	/// Request `sends` auxiliary sends per source with `ALC_MAX_AUXILIARY_SENDS`.
+	/// Counts beyond what an `ALCint` can hold are saturated.
+	pub fn max_aux_sends(mut self, sends: u32) -> ContextAttrs {
+		self.max_aux_sends = Some(sends.min(sys::ALCint::MAX as u32) as sys::ALCint);
+		self
+	}
+}
+
+
 /// Attributes that may be supplied during context creation from a loopback device.
 /// Requires `ALC_SOFT_loopback`
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
@@ -94,6 +115,154 @@ pub enum LoopbackFormatType {
 }
 
 
+/// Complete output format of a loopback device, as taken by `LoopbackDevice::open_with_format`.
+/// Requires `ALC_SOFT_loopback`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LoopbackFormat {
+	/// `ALC_FREQUENCY`
+	pub freq: sys::ALCint,
+	/// `ALC_FORMAT_CHANNELS_SOFT`
+	pub channels: LoopbackFormatChannels,
+	/// `ALC_FORMAT_TYPE_SOFT`
+	pub sample_ty: LoopbackFormatType,
+}
+
+
+/// Error returned when a loopback channel layout or sample type name is not recognized.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseFormatError {
+	what: &'static str,
+	input: String,
+}
+
+
+impl fmt::Display for LoopbackFormatChannels {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match *self {
+			LoopbackFormatChannels::Mono => "mono",
+			LoopbackFormatChannels::Stereo => "stereo",
+			LoopbackFormatChannels::Quad => "quad",
+			LoopbackFormatChannels::Mc51 => "5.1",
+			LoopbackFormatChannels::Mc61 => "6.1",
+			LoopbackFormatChannels::Mc71 => "7.1",
+		})
+	}
+}
+
+
+/// Accepts the names produced by `Display`, ignoring case, plus `1.0`, `2.0` and `4.0`.
+impl FromStr for LoopbackFormatChannels {
+	type Err = ParseFormatError;
+
+	fn from_str(s: &str) -> Result<LoopbackFormatChannels, ParseFormatError> {
+		match &*s.trim().to_ascii_lowercase() {
+			"mono" | "1.0" => Ok(LoopbackFormatChannels::Mono),
+			"stereo" | "2.0" => Ok(LoopbackFormatChannels::Stereo),
+			"quad" | "4.0" => Ok(LoopbackFormatChannels::Quad),
+			"5.1" => Ok(LoopbackFormatChannels::Mc51),
+			"6.1" => Ok(LoopbackFormatChannels::Mc61),
+			"7.1" => Ok(LoopbackFormatChannels::Mc71),
+			_ => Err(ParseFormatError{what: "channel layout", input: s.to_owned()}),
+		}
+	}
+}
+
+
+impl fmt::Display for LoopbackFormatType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match *self {
+			LoopbackFormatType::U8 => "uint8",
+			LoopbackFormatType::I16 => "int16",
+			LoopbackFormatType::F32 => "float32",
+		})
+	}
+}
+
+
+/// Accepts the names produced by `Display`, ignoring case, plus `u8`, `i16` and `f32`.
+impl FromStr for LoopbackFormatType {
+	type Err = ParseFormatError;
+
+	fn from_str(s: &str) -> Result<LoopbackFormatType, ParseFormatError> {
+		match &*s.trim().to_ascii_lowercase() {
+			"uint8" | "u8" => Ok(LoopbackFormatType::U8),
+			"int16" | "i16" => Ok(LoopbackFormatType::I16),
+			"float32" | "f32" => Ok(LoopbackFormatType::F32),
+			_ => Err(ParseFormatError{what: "sample type", input: s.to_owned()}),
+		}
+	}
+}
+
+
+impl LoopbackFormatChannels {
+	pub fn into_raw(self, asl: &ext::ALC_SOFT_loopback) -> AltoResult<sys::ALCint> {
+		Ok(match self {
+			LoopbackFormatChannels::Mono => asl.ALC_MONO_SOFT?,
+			LoopbackFormatChannels::Stereo => asl.ALC_STEREO_SOFT?,
+			LoopbackFormatChannels::Quad => asl.ALC_QUAD_SOFT?,
+			LoopbackFormatChannels::Mc51 => asl.ALC_5POINT1_SOFT?,
+			LoopbackFormatChannels::Mc61 => asl.ALC_6POINT1_SOFT?,
+			LoopbackFormatChannels::Mc71 => asl.ALC_7POINT1_SOFT?,
+		})
+	}
+}
+
+
+impl LoopbackFormatType {
+	pub fn into_raw(self, asl: &ext::ALC_SOFT_loopback) -> AltoResult<sys::ALCint> {
+		Ok(match self {
+			LoopbackFormatType::U8 => asl.ALC_UNSIGNED_BYTE_SOFT?,
+			LoopbackFormatType::I16 => asl.ALC_SHORT_SOFT?,
+			LoopbackFormatType::F32 => asl.ALC_FLOAT_SOFT?,
+		})
+	}
+}
+
+
+impl LoopbackFormat {
+	/// The loopback format that renders `format` at `freq`, or `None` if `format` can't be rendered by a loopback device.
+	pub fn from_format(freq: sys::ALCint, format: Format) -> Option<LoopbackFormat> {
+		use self::LoopbackFormatChannels as C;
+		use self::LoopbackFormatType as T;
+
+		let (channels, sample_ty) = match format {
+			Format::Standard(StandardFormat::MonoU8) => (C::Mono, T::U8),
+			Format::Standard(StandardFormat::MonoI16) => (C::Mono, T::I16),
+			Format::Standard(StandardFormat::StereoU8) => (C::Stereo, T::U8),
+			Format::Standard(StandardFormat::StereoI16) => (C::Stereo, T::I16),
+			Format::ExtFloat32(ExtFloat32Format::Mono) => (C::Mono, T::F32),
+			Format::ExtFloat32(ExtFloat32Format::Stereo) => (C::Stereo, T::F32),
+			Format::ExtMcFormats(ExtMcFormat::QuadU8) => (C::Quad, T::U8),
+			Format::ExtMcFormats(ExtMcFormat::QuadI16) => (C::Quad, T::I16),
+			Format::ExtMcFormats(ExtMcFormat::QuadF32) => (C::Quad, T::F32),
+			Format::ExtMcFormats(ExtMcFormat::Mc51ChnU8) => (C::Mc51, T::U8),
+			Format::ExtMcFormats(ExtMcFormat::Mc51ChnI16) => (C::Mc51, T::I16),
+			Format::ExtMcFormats(ExtMcFormat::Mc51ChnF32) => (C::Mc51, T::F32),
+			Format::ExtMcFormats(ExtMcFormat::Mc61ChnU8) => (C::Mc61, T::U8),
+			Format::ExtMcFormats(ExtMcFormat::Mc61ChnI16) => (C::Mc61, T::I16),
+			Format::ExtMcFormats(ExtMcFormat::Mc61ChnF32) => (C::Mc61, T::F32),
+			Format::ExtMcFormats(ExtMcFormat::Mc71ChnU8) => (C::Mc71, T::U8),
+			Format::ExtMcFormats(ExtMcFormat::Mc71ChnI16) => (C::Mc71, T::I16),
+			Format::ExtMcFormats(ExtMcFormat::Mc71ChnF32) => (C::Mc71, T::F32),
+			_ => return None,
+		};
+		Some(LoopbackFormat{freq, channels, sample_ty})
+	}
+}
+
+
+impl fmt::Display for ParseFormatError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "ALTO ERROR: Unknown loopback {} {:?}", self.what, self.input)
+	}
+}
+
+
+impl StdError for ParseFormatError {
+	fn description(&self) -> &str { "ALTO ERROR: Unknown loopback format name" }
+}
+
+
 /// The current HRTF mode of a device.
 /// Requires `ALC_SOFT_HRTF`
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
@@ -115,9 +284,119 @@ pub enum SoftHrtfStatus {
 }
 
 
+/// How a device is mixing its output, as reported by `DeviceObject::output_mode`.
+/// Requires `ALC_SOFT_output_mode`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum OutputMode {
+	/// `ALC_ANY_SOFT`
+	Any,
+	/// `ALC_MONO_SOFT`
+	Mono,
+	/// `ALC_STEREO_SOFT`
+	/// Stereo of an unspecified kind.
+	Stereo,
+	/// `ALC_STEREO_BASIC_SOFT`
+	/// Plain panned stereo for speakers.
+	StereoBasic,
+	/// `ALC_STEREO_UHJ_SOFT`
+	/// Stereo-compatible UHJ encoding, which can be decoded back to surround.
+	StereoUhj,
+	/// `ALC_STEREO_HRTF_SOFT`
+	/// Binaural stereo for headphones.
+	StereoHrtf,
+	/// `ALC_QUAD_SOFT`
+	Quad,
+	/// `ALC_SURROUND_5_1_SOFT`
+	Surround5_1,
+	/// `ALC_SURROUND_6_1_SOFT`
+	Surround6_1,
+	/// `ALC_SURROUND_7_1_SOFT`
+	Surround7_1,
+
+	Unknown(sys::ALCint),
+}
+
+
+impl OutputMode {
+	/// Decode an `ALC_OUTPUT_MODE_SOFT` token, using the values reported by the implementation.
+	pub fn from_raw(asom: &ext::ALC_SOFT_output_mode, value: sys::ALCint) -> OutputMode {
+		let modes = [
+			(&asom.ALC_ANY_SOFT, OutputMode::Any),
+			(&asom.ALC_MONO_SOFT, OutputMode::Mono),
+			(&asom.ALC_STEREO_SOFT, OutputMode::Stereo),
+			(&asom.ALC_STEREO_BASIC_SOFT, OutputMode::StereoBasic),
+			(&asom.ALC_STEREO_UHJ_SOFT, OutputMode::StereoUhj),
+			(&asom.ALC_STEREO_HRTF_SOFT, OutputMode::StereoHrtf),
+			(&asom.ALC_QUAD_SOFT, OutputMode::Quad),
+			(&asom.ALC_SURROUND_5_1_SOFT, OutputMode::Surround5_1),
+			(&asom.ALC_SURROUND_6_1_SOFT, OutputMode::Surround6_1),
+			(&asom.ALC_SURROUND_7_1_SOFT, OutputMode::Surround7_1),
+		];
+		modes.iter()
+			.find(|&&(token, _)| *token == Ok(value))
+			.map(|&(_, mode)| mode)
+			.unwrap_or(OutputMode::Unknown(value))
+	}
+}
+
+
+/// Which OpenAL implementation a device appears to belong to, as guessed by `DeviceObject::implementation`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Implementation {
+	/// OpenAL Soft, either directly or through a router.
+	Soft,
+	/// Creative's own implementation, including the hardware drivers and the "Generic Software" device.
+	CreativeHardware,
+	/// Anything else, such as Apple's implementation, or a name that could not be recognized.
+	Unknown,
+}
+
+
+impl Implementation {
+	/// Recognize an implementation from a device specifier or `AL_RENDERER` string.
+	/// OpenAL Soft names itself "OpenAL Soft" in its renderer string and in the device names of older versions,
+	/// and Creative's drivers use "Generic Hardware", "Generic Software" or the name of a Sound Blaster card.
+	/// Newer OpenAL Soft versions name devices after the system output alone, which gives `Unknown` here.
+	pub fn from_name(name: &str) -> Implementation {
+		let name = name.to_ascii_lowercase();
+		if name.contains("openal soft") {
+			Implementation::Soft
+		} else if ["generic hardware", "generic software", "sb x-fi", "sound blaster", "audigy", "creative"].iter().any(|n| name.contains(n)) {
+			Implementation::CreativeHardware
+		} else {
+			Implementation::Unknown
+		}
+	}
+}
+
+
 pub(crate) struct AltoInner {
 	pub(crate) api: sys::AlApi,
 	pub(crate) exts: ::ext::AlcNullCache,
+	devices: Mutex<Vec<Weak<DeviceInner>>>,
+	captures: AtomicUsize,
+	shut_down: AtomicBool,
+	allow_legacy: bool,
+	legacy: AtomicBool,
+	outputs: Mutex<Option<Vec<CString>>>,
+	output_queries: AtomicUsize,
+}
+
+
+/// Objects that were still referenced when `Alto::shutdown` ran.
+/// Their handles remain safe to use and drop, but any operation on them fails with `AltoError::ShutDown`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct ShutdownReport {
+	/// Output and loopback devices.
+	pub devices: usize,
+	/// Contexts, including those kept alive only by their sources or buffers.
+	pub contexts: usize,
+	/// Static and streaming sources.
+	pub sources: usize,
+	/// Buffers.
+	pub buffers: usize,
+	/// Capture devices, which are left open.
+	pub captures: usize,
 }
 
 
@@ -134,6 +413,8 @@ pub unsafe trait DeviceObject: Any {
 	fn specifier(&self) -> Option<&CStr>;
 	/// Raw handle as exposed by OpenAL.
 	fn as_raw(&self) -> *mut sys::ALCdevice;
+	/// Process-unique id of this device. Unlike the raw handle, an id is never reused after the device is closed.
+	fn id(&self) -> usize;
 	/// `alcIsExtensionPresent()`
 	fn is_extension_present(&self, ext::Alc) -> bool;
 	/// `alcGetIntegerv(ALC_CONNECTED)`
@@ -151,6 +432,32 @@ pub unsafe trait DeviceObject: Any {
 	/// `alcGetIntegerv(ALC_MAX_AUXILIARY_SENDS)`
 	/// Requires `ALC_EXT_EFX`
 	fn max_aux_sends(&self) -> sys::ALCint;
+	/// `alcGetIntegerv(ALC_FREQUENCY)`, `alcGetIntegerv(ALC_REFRESH)`
+	/// Estimated smallest achievable output latency, being the duration of one mixing period.
+	/// Actual latency also includes any buffering done by the backend and the OS, so will usually be higher.
+	fn min_latency(&self) -> AltoResult<Duration>;
+	/// `alcGetInteger64vSOFT(ALC_DEVICE_CLOCK_SOFT)`
+	/// Nanoseconds of audio the device has mixed, as used by `Source::play_at`.
+	/// Requires `ALC_SOFT_device_clock`
+	fn clock_time(&self) -> AltoResult<i64>;
+	/// `alcGetInteger64vSOFT(ALC_DEVICE_CLOCK_LATENCY_SOFT)`
+	/// The device clock together with the latency of the output, both in nanoseconds and read atomically,
+	/// so `clock_ns - latency_ns` is the clock time of the sample frame currently being heard.
+	/// Requires `ALC_SOFT_device_clock`
+	fn clock_latency(&self) -> AltoResult<(i64, i64)>;
+	/// `alcGetIntegerv(ALC_OUTPUT_MODE_SOFT)`
+	/// How the device is mixing its output, such as to plain stereo, HRTF or surround.
+	/// Requires `ALC_SOFT_output_mode`
+	fn output_mode(&self) -> AltoResult<OutputMode>;
+	/// `alcGetString(ALC_DEVICE_SPECIFIER)`
+	/// Guess which implementation this device belongs to, see `Implementation::from_name` for the heuristic.
+	/// If the name is not recognized, the presence of any `ALC_SOFT_*` extension identifies OpenAL Soft.
+	/// A router such as the Windows `OpenAL32.dll` may hide the underlying implementation,
+	/// and a third party implementation exposing OpenAL Soft's extensions will be mistaken for it,
+	/// so this should only be used to pick defaults or work around known quirks.
+	fn implementation(&self) -> AltoResult<Implementation>;
+	/// Whether `implementation` reports `Implementation::Soft`.
+	fn is_soft(&self) -> AltoResult<bool>;
 	/// Return a new handle to this device.
 	fn to_device(&self) -> Device;
 }
@@ -160,7 +467,14 @@ pub(crate) struct DeviceInner {
 	pub(crate) alto: Alto,
 	spec: Option<CString>,
 	pub(crate) dev: *mut sys::ALCdevice,
+	pub(crate) id: usize,
 	pub(crate) exts: ext::AlcCache,
+	pub(crate) contexts: Mutex<Vec<Weak<ContextInner>>>,
+	/// Current generation of every buffer, effect and filter name that has been deleted at least once.
+	/// These names belong to the device rather than to any one context.
+	pub(crate) generations: Mutex<HashMap<(ObjectKind, sys::ALuint), u32>>,
+	closed: AtomicBool,
+	pub(crate) loopback: bool,
 }
 
 
@@ -190,6 +504,8 @@ pub struct Capture<F: StandardFrame> {
 	alto: Alto,
 	spec: Option<CString>,
 	dev: *mut sys::ALCdevice,
+	/// Whether the device is closed when this handle is dropped.
+	owned: bool,
 	marker: PhantomData<F>,
 }
 
@@ -198,22 +514,37 @@ impl Alto {
 	/// Load the default OpenAL implementation for the platform.
 	/// This will prefer OpenAL-Soft if it is present, otherwise it will search for a generic implementation.
 	pub fn load_default() -> AltoResult<Alto> {
-		let api = sys::AlApi::load_default()?;
-		let exts = unsafe { ext::AlcNullCache::new(&api, ptr::null_mut()) };
-		Ok(Alto(Arc::new(AltoInner{
-			api: api,
-			exts: exts,
-		}))).and_then(|a| a.check_version(ptr::null_mut()).map(|_| a))
+		Alto::from_api(sys::AlApi::load_default()?, false)
 	}
 
 
 	/// Loads a specific OpenAL implementation from a specififed path.
 	pub fn load<P: AsRef<Path>>(path: P) -> AltoResult<Alto> {
-		let api = sys::AlApi::load(path)?;
+		Alto::from_api(sys::AlApi::load(path)?, false)
+	}
+
+
+	/// Like `load_default`, but also accepts implementations that only report ALC 1.0.
+	/// Such legacy drivers are usually fine for basic playback, but anything introduced in 1.1 is unavailable:
+	/// `open_capture` fails with `UnsupportedVersion`, and the 1.1 source offsets, distance models and
+	/// `AL_SPEED_OF_SOUND` may fail with `InvalidEnum`. Use `is_legacy` to find out whether a 1.0 driver was accepted.
+	pub fn load_allow_legacy() -> AltoResult<Alto> {
+		Alto::from_api(sys::AlApi::load_default()?, true)
+	}
+
+
+	fn from_api(api: sys::AlApi, allow_legacy: bool) -> AltoResult<Alto> {
 		let exts = unsafe { ext::AlcNullCache::new(&api, ptr::null_mut()) };
 		Ok(Alto(Arc::new(AltoInner{
 			api: api,
 			exts: exts,
+			devices: Mutex::new(Vec::new()),
+			captures: AtomicUsize::new(0),
+			shut_down: AtomicBool::new(false),
+			allow_legacy,
+			legacy: AtomicBool::new(false),
+			outputs: Mutex::new(None),
+			output_queries: AtomicUsize::new(0),
 		}))).and_then(|a| a.check_version(ptr::null_mut()).map(|_| a))
 	}
 
@@ -224,16 +555,38 @@ impl Alto {
 		let mut minor = 0;
 		unsafe { self.0.api.alcGetIntegerv(dev, sys::ALC_MINOR_VERSION, 1, &mut minor); }
 
-		if (major == 1 && minor >= 1)
-			|| (dev == ptr::null_mut() && major == 0 && minor == 0) // Creative's buggy router DLL won't report a version until you open a device
-		{
-			Ok(())
-		} else {
-			Err(AltoError::UnsupportedVersion{major, minor})
+		if dev.is_null() && major == 0 && minor == 0 {
+			// Creative's buggy router DLL won't report a version until you open a device
+			return Ok(());
+		}
+
+		let legacy = Alto::check_version_numbers(major, minor, self.0.allow_legacy)?;
+		if legacy {
+			self.0.legacy.store(true, Ordering::SeqCst);
+		}
+		Ok(())
+	}
+
+
+	/// Check an ALC version as reported by an implementation.
+	/// Versions from 1.1 up are always accepted. If `allow_legacy` is set, 1.0 is accepted as well,
+	/// in which case `Ok(true)` is returned to indicate that 1.1 features are unavailable.
+	pub(crate) fn check_version_numbers(major: sys::ALCint, minor: sys::ALCint, allow_legacy: bool) -> AltoResult<bool> {
+		match (major, minor) {
+			(1, minor) if minor >= 1 => Ok(false),
+			(1, 0) if allow_legacy => Ok(true),
+			_ => Err(AltoError::UnsupportedVersion{
+				found: Version::new(major, minor),
+				required: if allow_legacy { Version::new(1, 0) } else { Version::new(1, 1) },
+			}),
 		}
 	}
 
 
+	/// Whether a legacy ALC 1.0 implementation was accepted by `load_allow_legacy`.
+	pub fn is_legacy(&self) -> bool { self.0.legacy.load(Ordering::SeqCst) }
+
+
 	/// Raw entry points of the OpenAL API.
 	pub fn raw_api(&self) -> &sys::AlApi { &self.0.api }
 
@@ -246,11 +599,7 @@ impl Alto {
 			unsafe { self.0.api.alcGetString(ptr::null_mut(), sys::ALC_DEFAULT_DEVICE_SPECIFIER) }
 		};
 
-		if spec == ptr::null() {
-			None
-		} else {
-			unsafe { Some(CStr::from_ptr(spec).to_owned()) }
-		}
+		unsafe { Alto::string_from_ptr(spec) }.ok()
 	}
 
 
@@ -258,16 +607,26 @@ impl Alto {
 	pub fn default_capture(&self) -> Option<CString> {
 		let spec = unsafe { self.0.api.alcGetString(ptr::null_mut(), sys::ALC_CAPTURE_DEFAULT_DEVICE_SPECIFIER) };
 
-		if spec == ptr::null() {
-			None
-		} else {
-			unsafe { Some(CStr::from_ptr(spec).to_owned()) }
-		}
+		unsafe { Alto::string_from_ptr(spec) }.ok()
+	}
+
+
+	/// `alcIsExtensionPresent(ALC_ENUMERATION_EXT)`
+	/// Whether the implementation can list its devices. Without it, `enumerate_outputs` and
+	/// `enumerate_captures` only report the default device, so a device picker has nothing to offer.
+	pub fn enumeration_supported(&self) -> bool {
+		self.0.exts.ALC_ENUMERATION_EXT().is_ok() || self.0.exts.ALC_ENUMERATE_ALL_EXT().is_ok()
 	}
 
 
 	/// `alcGetString(ALC_DEVICE_SPECIFIER)`
+	/// If `ALC_ENUMERATION_EXT` is unavailable, contains only the default output, if any.
 	pub fn enumerate_outputs(&self) -> Vec<CString> {
+		self.0.output_queries.fetch_add(1, Ordering::Relaxed);
+		if !self.enumeration_supported() {
+			return self.default_output().into_iter().collect();
+		}
+
 		let spec = if let Ok(ext::ALC_ENUMERATE_ALL_EXT{ALC_ALL_DEVICES_SPECIFIER: Ok(ads), ..}) = self.0.exts.ALC_ENUMERATE_ALL_EXT {
 			unsafe { self.0.api.alcGetString(ptr::null_mut(), ads) }
 		} else {
@@ -277,18 +636,73 @@ impl Alto {
 	}
 
 
+	/// `alcGetString(ALC_DEVICE_SPECIFIER)`
+	/// Like `enumerate_outputs`, but the driver is only queried on the first call and after `refresh_device_cache`.
+	/// The cache is shared by all clones of this `Alto` and guarded by a mutex, so it may be used from any thread.
+	pub fn enumerate_outputs_cached(&self) -> Vec<CString> {
+		let mut outputs = self.0.outputs.lock();
+		if let Some(ref outputs) = *outputs {
+			return outputs.clone();
+		}
+
+		let list = self.enumerate_outputs();
+		*outputs = Some(list.clone());
+		list
+	}
+
+
+	/// Discard the list cached by `enumerate_outputs_cached`, such as after a device has been plugged in or removed.
+	/// The next call will query the driver again.
+	pub fn refresh_device_cache(&self) {
+		*self.0.outputs.lock() = None;
+	}
+
+
+	/// Number of times the list of outputs has been queried from the driver.
+	#[cfg(test)]
+	pub(crate) fn output_query_count(&self) -> usize { self.0.output_queries.load(Ordering::Relaxed) }
+
+
 	/// `alcGetString(ALC_CAPTURE_DEVICE_SPECIFIER)`
+	/// If `ALC_ENUMERATION_EXT` is unavailable, contains only the default capture device, if any.
 	pub fn enumerate_captures(&self) -> Vec<CString> {
+		if !self.enumeration_supported() {
+			return self.default_capture().into_iter().collect();
+		}
+
 		let spec = unsafe { self.0.api.alcGetString(ptr::null_mut(), sys::ALC_CAPTURE_DEVICE_SPECIFIER) };
 		Alto::parse_enum_spec(spec as *const u8)
 	}
 
 
-	fn parse_enum_spec(spec: *const u8) -> Vec<CString> {
-		let mut specs = Vec::with_capacity(0);
+	/// `alcGetString(ALC_CAPTURE_DEVICE_SPECIFIER)`
+	/// Like `enumerate_captures`, but with the names converted to strings, replacing invalid UTF-8.
+	pub fn enumerate_captures_lossy(&self) -> AltoResult<Vec<String>> {
+		self.check_shut_down()?;
+		Ok(self.enumerate_captures().iter().map(|n| n.to_string_lossy().into_owned()).collect())
+	}
+
+
+	/// `alcGetString(ALC_CAPTURE_DEVICE_SPECIFIER)`
+	/// Like `enumerate_captures_lossy`, with each name paired with whether it is the `default_capture`.
+	/// Without `ALC_ENUMERATION_EXT`, this is just the default device, if any.
+	pub fn enumerate_captures_annotated(&self) -> AltoResult<Vec<(String, bool)>> {
+		self.check_shut_down()?;
+		Ok(Alto::annotate_specifiers(&self.enumerate_captures(), self.default_capture().as_deref()))
+	}
 
+
+	/// Convert device names to strings, replacing invalid UTF-8, and pair each with whether it is `default`.
+	pub(crate) fn annotate_specifiers(names: &[CString], default: Option<&CStr>) -> Vec<(String, bool)> {
+		names.iter()
+			.map(|n| (n.to_string_lossy().into_owned(), Some(n.as_c_str()) == default))
+			.collect()
+	}
+
+
+	fn parse_enum_spec(spec: *const u8) -> Vec<CString> {
 		if spec == ptr::null() {
-			return specs;
+			return Vec::with_capacity(0);
 		}
 
 		let mut i = 0;
@@ -300,15 +714,42 @@ impl Alto {
 			i += 1;
 		}
 
-		specs.extend(unsafe { ::std::slice::from_raw_parts(spec as *const u8, i as usize) }.split(|c| *c == 0).map(|d| CString::new(d).unwrap()));
+		Alto::parse_specifier_list(unsafe { ::std::slice::from_raw_parts(spec, i as usize + 2) }, true)
+	}
+
 
-		specs
+	/// Copy a string returned by `alcGetString` or `alGetString`, which is NULL on failure.
+	/// A NULL string is a `NullError` rather than being dereferenced.
+	///
+	/// # Safety
+	/// `spec` must be NULL or point to a null terminated string.
+	pub(crate) unsafe fn string_from_ptr(spec: *const sys::ALCchar) -> AltoResult<CString> {
+		if spec.is_null() {
+			Err(AltoError::NullError)
+		} else {
+			Ok(CStr::from_ptr(spec).to_owned())
+		}
+	}
+
+
+	/// Split a specifier string as returned by `alcGetString`.
+	/// With `multi` set, `list` holds null separated names ending in an empty name, as with `ALC_ENUMERATION_EXT`.
+	/// Otherwise it holds a single name, ending at the first null if there is one.
+	pub(crate) fn parse_specifier_list(list: &[u8], multi: bool) -> Vec<CString> {
+		let mut names = list.split(|c| *c == 0);
+		if multi {
+			names.take_while(|n| !n.is_empty()).map(|n| CString::new(n).unwrap()).collect()
+		} else {
+			names.next().into_iter().filter(|n| !n.is_empty()).map(|n| CString::new(n).unwrap()).collect()
+		}
 	}
 
 
 	/// `alcOpenDevice()`
+	/// An empty `spec` is treated as `None`, opening the default output.
 	pub fn open(&self, spec: Option<&CStr>) -> AltoResult<OutputDevice> {
-		let spec = spec.map(|s| s.to_owned()).or_else(|| self.default_output());
+		self.check_shut_down()?;
+		let spec = Alto::device_spec(spec).map(|s| s.to_owned()).or_else(|| self.default_output());
 		let dev = unsafe { self.0.api.alcOpenDevice(spec.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null())) };
 
 		if dev == ptr::null_mut() {
@@ -318,20 +759,29 @@ impl Alto {
 				alto: Alto(self.0.clone()),
 				spec: spec,
 				dev: dev,
+				id: NEXT_DEVICE_ID.fetch_add(1, Ordering::Relaxed) + 1,
 				exts: unsafe { ext::AlcCache::new(&self.0.api, dev) },
+				contexts: Mutex::new(Vec::new()),
+				generations: Mutex::new(HashMap::new()),
+				closed: AtomicBool::new(false),
+				loopback: false,
 			}));
-			self.check_version(dev.0.dev).map(|_| dev)
+			self.check_version(dev.0.dev)?;
+			self.register_device(&dev.0);
+			Ok(dev)
 		}
 	}
 
 
 	/// `alcLoopbackOpenDeviceSOFT()`
 	/// Requires `ALC_SOFT_loopback`
+	/// An empty `spec` is treated as `None`.
 	pub fn open_loopback<F: LoopbackFrame>(&self, spec: Option<&CStr>) -> AltoResult<LoopbackDevice<F>> {
+		self.check_shut_down()?;
 		let asl = self.0.exts.ALC_SOFT_loopback()?;
 		asl.alcRenderSamplesSOFT?;
 
-		let spec = spec.map(|s| s.to_owned());//.or_else(|| self.default_output());
+		let spec = Alto::device_spec(spec).map(|s| s.to_owned());//.or_else(|| self.default_output());
 		let dev = unsafe { asl.alcLoopbackOpenDeviceSOFT?(spec.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null())) };
 
 		if dev == ptr::null_mut() {
@@ -342,30 +792,196 @@ impl Alto {
 					alto: Alto(self.0.clone()),
 					spec: spec,
 					dev: dev,
+					id: NEXT_DEVICE_ID.fetch_add(1, Ordering::Relaxed) + 1,
 					exts: unsafe { ext::AlcCache::new(&self.0.api, dev) },
+					contexts: Mutex::new(Vec::new()),
+					generations: Mutex::new(HashMap::new()),
+					closed: AtomicBool::new(false),
+					loopback: true,
 				}),
 				PhantomData,
 			);
-			self.check_version(dev.0.dev).map(|_| dev)
+			self.check_version(dev.0.dev)?;
+			self.register_device(&dev.0);
+			Ok(dev)
 		}
 	}
 
 
 	/// `alcCaptureOpenDevice()`
+	/// An empty `spec` is treated as `None`, opening the default capture device.
 	pub fn open_capture<F: StandardFrame>(&self, spec: Option<&CStr>, freq: sys::ALCuint, len: sys::ALCsizei) -> AltoResult<Capture<F>> {
-		let spec = spec.map(|s| s.to_owned()).or_else(|| self.default_capture());
+		self.check_shut_down()?;
+		if self.is_legacy() {
+			return Err(AltoError::UnsupportedVersion{found: Version::new(1, 0), required: Version::new(1, 1)});
+		}
+		let minimum = Alto::min_capture_len(freq);
+		if len < minimum {
+			return Err(AltoError::CaptureBufferTooSmall{requested: len, minimum});
+		}
+		let spec = Alto::device_spec(spec).map(|s| s.to_owned()).or_else(|| self.default_capture());
 		let dev = unsafe { self.0.api.alcCaptureOpenDevice(spec.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null()), freq, F::format().into_raw(None)?, len) };
 
 		if dev == ptr::null_mut() {
 			Err(AltoError::InvalidDevice)
 		} else {
-			let dev = Capture{alto: Alto(self.0.clone()), spec: spec, dev: dev, marker: PhantomData};
+			let dev = Capture{alto: Alto(self.0.clone()), spec: spec, dev: dev, owned: true, marker: PhantomData};
+			self.0.captures.fetch_add(1, Ordering::Relaxed);
 			//self.check_version(dev.dev).map(|_| dev)
 			Ok(dev)
 		}
 	}
 
 
+	/// The specifier actually used to open a device. Drivers disagree on what an empty name means,
+	/// so it is taken to mean the default device, just like `None`.
+	pub(crate) fn device_spec(spec: Option<&CStr>) -> Option<&CStr> {
+		spec.filter(|s| !s.to_bytes().is_empty())
+	}
+
+
+	/// `alcCaptureOpenDevice()`
+	/// Find the first of `candidates` that the capture device named by `spec` can record at `freq`,
+	/// by briefly opening the device in each format in turn. Every probe device is closed again before returning.
+	/// Formats are tried in order, so the most desirable should come first. Formats that are not
+	/// `Format::is_capture_compatible` are skipped without opening the device.
+	/// If none of them work, the error from the last attempt is returned.
+	pub fn best_capture_format(&self, spec: Option<&CStr>, freq: sys::ALCuint, candidates: &[Format]) -> AltoResult<Format> {
+		self.check_shut_down()?;
+		let spec = Alto::device_spec(spec).map(|s| s.to_owned()).or_else(|| self.default_capture());
+		let len = Alto::min_capture_len(freq);
+
+		Alto::negotiate_format(candidates, |fmt| {
+			if !fmt.is_capture_compatible() {
+				return Err(AltoError::InvalidValue);
+			}
+			let fmt = fmt.into_raw(None)?;
+			let dev = unsafe { self.0.api.alcCaptureOpenDevice(spec.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null()), freq, fmt, len) };
+			if dev.is_null() { Err(AltoError::InvalidDevice) } else { Ok(dev) }
+		}, |dev| unsafe { self.0.api.alcCaptureCloseDevice(dev); })
+	}
+
+
+	/// The negotiation behind `best_capture_format`. `open` is tried with each candidate until it succeeds,
+	/// at which point the probe it returned is passed to `close` and that candidate is returned.
+	/// Empty `candidates` are an `InvalidValue`.
+	pub(crate) fn negotiate_format<D, O, C>(candidates: &[Format], mut open: O, close: C) -> AltoResult<Format>
+		where O: FnMut(Format) -> AltoResult<D>, C: FnOnce(D)
+	{
+		let mut last = AltoError::InvalidValue;
+		for &fmt in candidates {
+			match open(fmt) {
+				Ok(probe) => {
+					close(probe);
+					return Ok(fmt);
+				},
+				Err(e) => last = e,
+			}
+		}
+		Err(last)
+	}
+
+
+	/// Smallest capture ring buffer `open_capture` accepts at the given frequency, in sample frames.
+	/// This is 10ms of audio, below which implementations tend to overrun between polls or reject the size outright.
+	pub(crate) fn min_capture_len(freq: sys::ALCuint) -> sys::ALCsizei {
+		cmp::max(freq / 100, 1) as sys::ALCsizei
+	}
+
+
+	/// Duration of one mixing period for a device running at `freq` with `refresh` updates per second.
+	/// The period is a whole number of sample frames, so this is `freq / refresh` frames rounded down.
+	/// Returns `None` if either value is zero.
+	pub(crate) fn period_duration(freq: sys::ALCuint, refresh: sys::ALCuint) -> Option<Duration> {
+		if freq == 0 || refresh == 0 {
+			return None;
+		}
+		let frames = cmp::max(freq / refresh, 1) as u64;
+		Some(Duration::from_nanos(frames * 1_000_000_000 / freq as u64))
+	}
+
+
+	/// Split the two values written by `alcGetInteger64vSOFT(ALC_DEVICE_CLOCK_LATENCY_SOFT)` into
+	/// `(clock_ns, latency_ns)`. Anything but two non-negative values is rejected with `AltoError::InvalidValue`.
+	pub(crate) fn parse_clock_latency(values: &[i64]) -> AltoResult<(i64, i64)> {
+		match *values {
+			[clock, latency] if clock >= 0 && latency >= 0 => Ok((clock, latency)),
+			_ => Err(AltoError::InvalidValue),
+		}
+	}
+
+
+	/// `alcMakeContextCurrent(NULL)`
+	/// Leave no context current, including the calling thread's own with `ALC_EXT_thread_local_context`,
+	/// for tearing down without relying on drop order or before handing OpenAL to other code.
+	/// Alto makes the right context current on each call, so its own objects keep working afterwards.
+	pub fn clear_current_context(&self) -> AltoResult<()> {
+		self.check_shut_down()?;
+		if let Ok(&ext::ALC_EXT_thread_local_context{alcSetThreadContext: Ok(astc), ..}) = self.0.exts.ALC_EXT_thread_local_context() {
+			if unsafe { astc(ptr::null_mut()) } == sys::ALC_FALSE {
+				return self.get_error(ptr::null_mut()).and(Err(AltoError::InvalidContext));
+			}
+		}
+		if unsafe { self.0.api.alcMakeContextCurrent(ptr::null_mut()) } == sys::ALC_FALSE {
+			return self.get_error(ptr::null_mut()).and(Err(AltoError::InvalidContext));
+		}
+		self.get_error(ptr::null_mut())
+	}
+
+
+	/// `alcGetCurrentContext()`
+	/// Whether any context is current, either process-wide or, with `ALC_EXT_thread_local_context`, on the calling thread.
+	pub fn is_any_context_current(&self) -> bool {
+		if let Ok(&ext::ALC_EXT_thread_local_context{alcGetThreadContext: Ok(agtc), ..}) = self.0.exts.ALC_EXT_thread_local_context() {
+			if !unsafe { agtc() }.is_null() {
+				return true;
+			}
+		}
+		!unsafe { self.0.api.alcGetCurrentContext() }.is_null()
+	}
+
+
+	/// Stop all playing sources, destroy all contexts and close all output and loopback devices
+	/// opened through this implementation, then report anything still referenced by user code.
+	/// Source and buffer names still held by user code are deleted along with their contexts.
+	/// Handles that outlive the shutdown, even on other threads, become inert: their operations
+	/// fail with `AltoError::ShutDown` and dropping them no longer touches OpenAL state.
+	/// Capture devices are counted but left open, since they are owned outright by their handles.
+	pub fn shutdown(self) -> ShutdownReport {
+		self.0.shut_down.store(true, Ordering::SeqCst);
+		let mut report = ShutdownReport::default();
+
+		let devices: Vec<_> = self.0.devices.lock().drain(..).filter_map(|d| d.upgrade()).collect();
+		for dev in &devices {
+			let contexts: Vec<_> = dev.contexts.lock().drain(..).filter_map(|c| c.upgrade()).collect();
+			for ctx in &contexts {
+				ctx.shut_down(&mut report);
+			}
+			report.contexts += contexts.len();
+
+			if !dev.closed.swap(true, Ordering::SeqCst) {
+				unsafe { self.0.api.alcCloseDevice(dev.dev); }
+			}
+		}
+		report.devices = devices.len();
+		report.captures = self.0.captures.load(Ordering::SeqCst);
+
+		report
+	}
+
+
+	fn check_shut_down(&self) -> AltoResult<()> {
+		if self.0.shut_down.load(Ordering::SeqCst) { Err(AltoError::ShutDown) } else { Ok(()) }
+	}
+
+
+	fn register_device(&self, dev: &Arc<DeviceInner>) {
+		let mut devices = self.0.devices.lock();
+		devices.retain(|d| Weak::strong_count(d) > 0);
+		devices.push(Arc::downgrade(dev));
+	}
+
+
 	#[doc(hidden)]
 	pub fn get_error(&self, dev: *mut sys::ALCdevice) -> AltoResult<()> {
 		match unsafe { self.0.api.alcGetError(dev)} {
@@ -396,6 +1012,8 @@ impl DeviceInner {
 			ext::Alc::SoftHrtf => self.exts.ALC_SOFT_HRTF().is_ok(),
 			ext::Alc::SoftOutputLimiter => self.exts.ALC_SOFT_output_limiter().is_ok(),
 			ext::Alc::SoftPauseDevice => self.exts.ALC_SOFT_pause_device().is_ok(),
+			ext::Alc::SoftDeviceClock => self.exts.ALC_SOFT_device_clock().is_ok(),
+			ext::Alc::SoftOutputMode => self.exts.ALC_SOFT_output_mode().is_ok(),
 		}
 	}
 
@@ -403,6 +1021,7 @@ impl DeviceInner {
 	/// `alcGetIntegerv(ALC_CONNECTED)`
 	/// Requires `ALC_EXT_DISCONNECT`
 	pub fn connected(&self) -> AltoResult<bool> {
+		self.check_closed()?;
 		let mut value = 0;
 		unsafe { self.alto.0.api.alcGetIntegerv(self.dev, self.exts.ALC_EXT_DISCONNECT()?.ALC_CONNECTED?, 1, &mut value); }
 		Ok(value == sys::ALC_TRUE as sys::ALCint)
@@ -415,14 +1034,16 @@ impl DeviceInner {
 		let mut spec_vec = Vec::with_capacity(0);
 
 		let _ = (|| -> AltoResult<_> {
+			self.check_closed()?;
 			let ash = self.exts.ALC_SOFT_HRTF()?;
 			let mut value = 0;
 			unsafe { self.alto.0.api.alcGetIntegerv(self.dev, ash.ALC_NUM_HRTF_SPECIFIERS_SOFT?, 1, &mut value); }
 
 			for i in 0 .. value {
 				unsafe {
-					let spec = ash.alcGetStringiSOFT?(self.dev, ash.ALC_HRTF_SPECIFIER_SOFT?, i) as *mut _;
-					spec_vec.push(self.alto.get_error(self.dev).map(|_| CStr::from_ptr(spec).to_owned())?);
+					let spec = ash.alcGetStringiSOFT?(self.dev, ash.ALC_HRTF_SPECIFIER_SOFT?, i);
+					self.alto.get_error(self.dev)?;
+					spec_vec.push(Alto::string_from_ptr(spec)?);
 				}
 			}
 
@@ -437,6 +1058,7 @@ impl DeviceInner {
 	/// Requires `ALC_SOFT_HRTF`
 	pub fn soft_hrtf_status(&self) -> SoftHrtfStatus {
 		(|| -> AltoResult<_> {
+			self.check_closed()?;
 			let ash = self.exts.ALC_SOFT_HRTF()?;
 
 			let mut value = 0;
@@ -458,6 +1080,7 @@ impl DeviceInner {
 	/// Requires `ALC_SOFT_output_limiter`
 	pub fn soft_output_limiter(&self) -> bool {
 		(|| -> AltoResult<_> {
+			self.check_closed()?;
 			let asol = self.exts.ALC_SOFT_output_limiter()?;
 
 			let mut value = 0;
@@ -472,20 +1095,99 @@ impl DeviceInner {
 	pub fn max_aux_sends(&self) -> sys::ALCint {
 		let mut value = 0;
 		let _ = (|| -> AltoResult<_> {
+			self.check_closed()?;
 			unsafe { self.alto.0.api.alcGetIntegerv(self.dev, self.exts.ALC_EXT_EFX()?.ALC_MAX_AUXILIARY_SENDS?, 1, &mut value); }
 			Ok(())
 		})();
 		value
 	}
+
+
+	pub fn min_latency(&self) -> AltoResult<Duration> {
+		self.check_closed()?;
+		let mut attrs = [0; 2];
+		unsafe {
+			self.alto.0.api.alcGetIntegerv(self.dev, sys::ALC_FREQUENCY, 1, &mut attrs[0]);
+			self.alto.0.api.alcGetIntegerv(self.dev, sys::ALC_REFRESH, 1, &mut attrs[1]);
+		}
+		self.alto.get_error(self.dev)?;
+		if attrs[0] <= 0 || attrs[1] <= 0 {
+			return Err(AltoError::InvalidValue);
+		}
+		Alto::period_duration(attrs[0] as sys::ALCuint, attrs[1] as sys::ALCuint).ok_or(AltoError::InvalidValue)
+	}
+
+
+	pub fn implementation(&self) -> AltoResult<Implementation> {
+		self.check_closed()?;
+		let spec = unsafe { self.alto.0.api.alcGetString(self.dev, sys::ALC_DEVICE_SPECIFIER) };
+		self.alto.get_error(self.dev)?;
+
+		let from_name = if spec.is_null() {
+			Implementation::Unknown
+		} else {
+			Implementation::from_name(&unsafe { CStr::from_ptr(spec) }.to_string_lossy())
+		};
+		if from_name != Implementation::Unknown {
+			return Ok(from_name);
+		}
+
+		let soft_ext = self.exts.ALC_SOFT_HRTF().is_ok()
+			|| self.exts.ALC_SOFT_output_limiter().is_ok()
+			|| self.exts.ALC_SOFT_pause_device().is_ok()
+			|| self.exts.ALC_SOFT_device_clock().is_ok()
+			|| self.alto.0.exts.ALC_SOFT_loopback().is_ok();
+		Ok(if soft_ext { Implementation::Soft } else { Implementation::Unknown })
+	}
+
+
+	/// `alcGetInteger64vSOFT(ALC_DEVICE_CLOCK_SOFT)`
+	/// Requires `ALC_SOFT_device_clock`
+	pub fn soft_device_clock(&self) -> AltoResult<i64> {
+		self.check_closed()?;
+		let asdc = self.exts.ALC_SOFT_device_clock()?;
+		let mut value = 0;
+		unsafe { asdc.alcGetInteger64vSOFT?(self.dev, asdc.ALC_DEVICE_CLOCK_SOFT?, 1, &mut value); }
+		self.alto.get_error(self.dev).map(|_| value)
+	}
+
+
+	pub fn clock_latency(&self) -> AltoResult<(i64, i64)> {
+		self.check_closed()?;
+		let asdc = self.exts.ALC_SOFT_device_clock()?;
+		let mut values = [0; 2];
+		unsafe { asdc.alcGetInteger64vSOFT?(self.dev, asdc.ALC_DEVICE_CLOCK_LATENCY_SOFT?, 2, values.as_mut_ptr()); }
+		self.alto.get_error(self.dev)?;
+		Alto::parse_clock_latency(&values)
+	}
+
+
+	pub fn output_mode(&self) -> AltoResult<OutputMode> {
+		self.check_closed()?;
+		let asom = self.exts.ALC_SOFT_output_mode()?;
+		let mut value = 0;
+		unsafe { self.alto.0.api.alcGetIntegerv(self.dev, asom.ALC_OUTPUT_MODE_SOFT?, 1, &mut value); }
+		self.alto.get_error(self.dev).map(|_| OutputMode::from_raw(asom, value))
+	}
+
+
+	pub(crate) fn check_closed(&self) -> AltoResult<()> {
+		if self.closed.load(Ordering::SeqCst) { Err(AltoError::ShutDown) } else { Ok(()) }
+	}
 }
 
 
 impl Drop for DeviceInner {
 	fn drop(&mut self) {
-		unsafe { self.alto.0.api.alcCloseDevice(self.dev); }
+		if !self.closed.load(Ordering::SeqCst) {
+			unsafe { self.alto.0.api.alcCloseDevice(self.dev); }
+		}
 	}
 }
 
+unsafe impl Send for DeviceInner { }
+unsafe impl Sync for DeviceInner { }
+
 
 impl OutputDevice {
 	fn make_attrs_vec(&self, attrs: Option<ContextAttrs>) -> AltoResult<Option<Vec<sys::ALCint>>> {
@@ -535,6 +1237,7 @@ impl OutputDevice {
 
 	/// `alcCreateContext()`
 	pub fn new_context(&self, attrs: Option<ContextAttrs>) -> AltoResult<Context> {
+		self.0.check_closed()?;
 		let attrs_vec = self.make_attrs_vec(attrs)?;
 		let ctx = unsafe { self.0.alto.0.api.alcCreateContext(self.0.dev, attrs_vec.map(|a| a.as_slice().as_ptr()).unwrap_or(ptr::null())) };
 		if ctx == ptr::null_mut() {
@@ -548,9 +1251,20 @@ impl OutputDevice {
 	}
 
 
+	/// `alcCreateContext()`
+	/// Create a context requesting `sends` auxiliary sends per source, so that EFX can be used right away.
+	/// Requires `ALC_EXT_EFX`; without it, no context is created.
+	pub fn new_efx_context(&self, sends: u32) -> AltoResult<Context> {
+		self.0.check_closed()?;
+		self.0.exts.ALC_EXT_EFX()?;
+		self.new_context(Some(ContextAttrs::default().max_aux_sends(sends)))
+	}
+
+
 	/// `alcDevicePauseSOFT()`
 	/// Requires `ALC_SOFT_pause_device`
 	pub fn soft_pause(&self) -> AltoResult<()> {
+		self.0.check_closed()?;
 		let adps = self.0.exts.ALC_SOFT_pause_device()?.alcDevicePauseSOFT?;
 
 		unsafe { adps(self.0.dev) }
@@ -565,6 +1279,9 @@ impl OutputDevice {
 	/// `alcDeviceResumeSOFT()`
 	/// Requires `ALC_SOFT_pause_device`
 	pub fn soft_resume(&self) {
+		if self.0.check_closed().is_err() {
+			return;
+		}
 		if let Ok(aspd) = self.0.exts.ALC_SOFT_pause_device() {
 			if let Ok(adrs) = aspd.alcDeviceResumeSOFT {
 				unsafe { adrs(self.0.dev); }
@@ -576,11 +1293,28 @@ impl OutputDevice {
 	/// `alcDevicePauseSOFT()`
 	/// Requires `ALC_SOFT_HRTF`
 	pub fn soft_reset(&self, attrs: Option<ContextAttrs>) -> AltoResult<()> {
+		self.0.check_closed()?;
 		let ards = self.0.exts.ALC_SOFT_HRTF()?.alcResetDeviceSOFT?;
 		let attrs_vec = self.make_attrs_vec(attrs.into())?;
 		unsafe { ards(self.0.dev, attrs_vec.map(|a| a.as_slice().as_ptr()).unwrap_or(ptr::null())) };
 		self.0.alto.get_error(self.0.dev)
 	}
+
+
+	/// `alcResetDeviceSOFT()`
+	/// Like `soft_reset`, but the global state and listener of `ctx` are captured beforehand and restored afterwards,
+	/// even if the reset fails, in case the implementation reinitialized them.
+	/// Sources, buffers and EFX objects are not touched by the reset: every source keeps its properties, buffers,
+	/// filters and sends, and playing sources carry on from where they were.
+	/// Requires `ALC_SOFT_HRTF`
+	pub fn reset_preserving(&self, attrs: Option<ContextAttrs>, ctx: &Context) -> AltoResult<()> {
+		if ctx.device().id() != self.id() {
+			return Err(AltoError::WrongDevice);
+		}
+		self.0.exts.ALC_SOFT_HRTF()?.alcResetDeviceSOFT?;
+
+		ctx.preserving_global(|| self.soft_reset(attrs))
+	}
 }
 
 
@@ -588,6 +1322,7 @@ unsafe impl DeviceObject for OutputDevice {
 	#[inline] fn alto(&self) -> &Alto { self.0.alto() }
 	#[inline] fn specifier(&self) -> Option<&CStr> { self.0.specifier() }
 	#[inline] fn as_raw(&self) -> *mut sys::ALCdevice { self.0.as_raw() }
+	#[inline] fn id(&self) -> usize { self.0.id }
 	#[inline] fn connected(&self) -> AltoResult<bool> { self.0.connected() }
 
 	#[inline] fn is_extension_present(&self, ext: ext::Alc) -> bool { self.0.is_extension_present(ext) }
@@ -595,6 +1330,12 @@ unsafe impl DeviceObject for OutputDevice {
 	#[inline] fn soft_hrtf_status(&self) -> SoftHrtfStatus { self.0.soft_hrtf_status() }
 	#[inline] fn soft_output_limiter(&self) -> bool { self.0.soft_output_limiter() }
 	#[inline] fn max_aux_sends(&self) -> sys::ALCint { self.0.max_aux_sends() }
+	#[inline] fn min_latency(&self) -> AltoResult<Duration> { self.0.min_latency() }
+	#[inline] fn clock_time(&self) -> AltoResult<i64> { self.0.soft_device_clock() }
+	#[inline] fn clock_latency(&self) -> AltoResult<(i64, i64)> { self.0.clock_latency() }
+	#[inline] fn output_mode(&self) -> AltoResult<OutputMode> { self.0.output_mode() }
+	#[inline] fn implementation(&self) -> AltoResult<Implementation> { self.0.implementation() }
+	#[inline] fn is_soft(&self) -> AltoResult<bool> { self.0.implementation().map(|i| i == Implementation::Soft) }
 	#[inline] fn to_device(&self) -> Device { Device(self.0.clone()) }
 }
 
@@ -653,8 +1394,36 @@ impl<F: LoopbackFrame> LoopbackDevice<F> {
 	}
 
 
+	/// `alcLoopbackOpenDeviceSOFT()`, `alcIsRenderFormatSupportedSOFT()`, `alcCreateContext()`
+	/// Open a loopback device and create its context in one step, rendering in `fmt`.
+	/// `fmt` must describe `F` and be supported by the implementation, otherwise `AltoError::InvalidValue` is returned,
+	/// and a device that was already opened is closed again rather than being handed back without a context.
+	pub fn open_with_format(alto: &Alto, spec: Option<&CStr>, fmt: &LoopbackFormat, attrs: Option<LoopbackAttrs>) -> AltoResult<(LoopbackDevice<F>, Context)> {
+		if fmt.freq <= 0 || LoopbackFormat::from_format(fmt.freq, F::format()) != Some(*fmt) {
+			return Err(AltoError::InvalidValue);
+		}
+
+		let dev = alto.open_loopback::<F>(spec)?;
+		if !dev.is_render_format_supported(fmt)? {
+			return Err(AltoError::InvalidValue);
+		}
+		let ctx = dev.new_context(fmt.freq, attrs)?;
+		Ok((dev, ctx))
+	}
+
+
+	/// `alcIsRenderFormatSupportedSOFT()`
+	pub fn is_render_format_supported(&self, fmt: &LoopbackFormat) -> AltoResult<bool> {
+		self.0.check_closed()?;
+		let asl = self.0.alto.0.exts.ALC_SOFT_loopback()?;
+		let supported = unsafe { asl.alcIsRenderFormatSupportedSOFT?(self.0.dev, fmt.freq, fmt.channels.into_raw(asl)?, fmt.sample_ty.into_raw(asl)?) };
+		self.0.alto.get_error(self.0.dev).map(|_| supported == sys::ALC_TRUE)
+	}
+
+
 	/// `alcCreateContext()`
 	pub fn new_context(&self, freq: sys::ALCint, attrs: Option<LoopbackAttrs>) -> AltoResult<Context> {
+		self.0.check_closed()?;
 		let attrs_vec = self.make_attrs_vec(freq, attrs.into())?;
 		let ctx = unsafe { self.0.alto.0.api.alcCreateContext(self.0.dev, attrs_vec.as_slice().as_ptr()) };
 		if ctx == ptr::null_mut() {
@@ -668,26 +1437,100 @@ impl<F: LoopbackFrame> LoopbackDevice<F> {
 	}
 
 
+	/// `alcGetIntegerv(ALC_FREQUENCY)`
+	pub fn frequency(&self) -> AltoResult<u32> {
+		self.get_uint(sys::ALC_FREQUENCY)
+	}
+
+
+	/// `alcGetIntegerv(ALC_REFRESH)`
+	pub fn refresh(&self) -> AltoResult<u32> {
+		self.get_uint(sys::ALC_REFRESH)
+	}
+
+
+	fn get_uint(&self, param: sys::ALCenum) -> AltoResult<u32> {
+		self.0.check_closed()?;
+		let mut value = 0;
+		unsafe { self.0.alto.0.api.alcGetIntegerv(self.0.dev, param, 1, &mut value); }
+		self.0.alto.get_error(self.0.dev)?;
+		if value < 0 { Err(AltoError::InvalidValue) } else { Ok(value as u32) }
+	}
+
+
 	/// `alcRenderSamplesSOFT()`
 	/// Returns the number of sample frames rendered to the slice.
-	pub fn soft_render_samples<R: AsBufferDataMut<F>>(&mut self, mut data: R) -> usize {
+	/// OpenAL expects a count of frames rather than bytes, so for a slice of frames this is always its length,
+	/// unless the device has been closed by `Alto::shutdown`, in which case nothing is rendered.
+	pub fn soft_render_samples<R: AsBufferDataMut<F>>(&mut self, data: R) -> usize {
+		self.render_samples(data).unwrap_or(0)
+	}
+
+
+	fn render_samples<R: AsBufferDataMut<F>>(&mut self, mut data: R) -> AltoResult<usize> {
+		self.0.check_closed()?;
 		let (data, size) = data.as_buffer_data_mut();
+		debug_assert_eq!(size % mem::size_of::<F>(), 0, "render buffer is not a whole number of frames");
 		let len = cmp::min(size / mem::size_of::<F>(), sys::ALCsizei::max_value() as usize);
 		if len == 0 {
-			return 0;
+			return Ok(0);
 		}
 
-		let asl = self.0.alto.0.exts.ALC_SOFT_loopback().unwrap();
+		let arss = self.0.alto.0.exts.ALC_SOFT_loopback()?.alcRenderSamplesSOFT?;
+		unsafe { arss(self.0.dev, data, len as sys::ALCsizei); }
+
+		Ok(len)
+	}
+
+
+	/// Render `total_frames` sample frames in blocks of at most `chunk` frames, passing the bytes of each block to `sink`.
+	/// Rendering stops at the first error returned by `sink`, which is then returned,
+	/// or with `AltoError::ShutDown` if the device is closed by `Alto::shutdown` partway through.
+	pub fn render_callback<S: FnMut(&[u8]) -> AltoResult<()>>(&mut self, total_frames: usize, chunk: usize, mut sink: S) -> AltoResult<()> {
+		if chunk == 0 {
+			return Err(AltoError::InvalidValue);
+		}
+		self.0.check_closed()?;
+
+		// Sample frames are plain data, so all zeroes is a valid frame.
+		let mut block: Vec<F> = vec![unsafe { mem::zeroed() }; cmp::min(chunk, total_frames)];
+		let mut remaining = total_frames;
+		while remaining > 0 {
+			let len = cmp::min(chunk, remaining);
+			let len = self.render_samples(&mut block[.. len])?;
+			let bytes = unsafe { slice::from_raw_parts(block.as_ptr() as *const u8, len * mem::size_of::<F>()) };
+			sink(bytes)?;
+			remaining -= len;
+		}
+		Ok(())
+	}
 
-		unsafe { asl.alcRenderSamplesSOFT.unwrap()(self.0.dev, data, len as sys::ALCsizei); }
 
-		len as usize
+	/// `alcCreateContext()`, `alcRenderSamplesSOFT()`
+	/// Create a context at `freq`, let `setup` create and start whatever sources and buffers should be heard,
+	/// then render `frames` sample frames and return them.
+	/// Whatever `setup` returns is kept alive while rendering, and is dropped along with the context afterward.
+	pub fn render_with_sources<S, R>(&mut self, freq: sys::ALCint, attrs: Option<LoopbackAttrs>, frames: usize, setup: S) -> AltoResult<Vec<F>>
+		where S: FnOnce(&Context) -> AltoResult<R>
+	{
+		let ctx = self.new_context(freq, attrs)?;
+		let objects = setup(&ctx)?;
+
+		// Sample frames are plain data, so all zeroes is a valid frame.
+		let mut output: Vec<F> = vec![unsafe { mem::zeroed() }; frames];
+		let len = self.render_samples(&mut output[..])?;
+		output.truncate(len);
+
+		drop(objects);
+		drop(ctx);
+		Ok(output)
 	}
 
 
 	/// `alcDevicePauseSOFT()`
 	/// Requires `ALC_SOFT_HRTF`
 	pub fn soft_reset(&self, freq: sys::ALCint, attrs: Option<LoopbackAttrs>) -> AltoResult<()> {
+		self.0.check_closed()?;
 		let ards = self.0.exts.ALC_SOFT_HRTF()?.alcResetDeviceSOFT?;
 
 		let attrs_vec = self.make_attrs_vec(freq, attrs.into());
@@ -701,6 +1544,7 @@ unsafe impl<F: LoopbackFrame> DeviceObject for LoopbackDevice<F> {
 	#[inline] fn alto(&self) -> &Alto { self.0.alto() }
 	#[inline] fn specifier(&self) -> Option<&CStr> { self.0.specifier() }
 	#[inline] fn as_raw(&self) -> *mut sys::ALCdevice { self.0.as_raw() }
+	#[inline] fn id(&self) -> usize { self.0.id }
 	#[inline] fn connected(&self) -> AltoResult<bool> { self.0.connected() }
 
 	#[inline] fn is_extension_present(&self, ext: ext::Alc) -> bool { self.0.is_extension_present(ext) }
@@ -708,6 +1552,12 @@ unsafe impl<F: LoopbackFrame> DeviceObject for LoopbackDevice<F> {
 	#[inline] fn soft_hrtf_status(&self) -> SoftHrtfStatus { self.0.soft_hrtf_status() }
 	#[inline] fn soft_output_limiter(&self) -> bool { self.0.soft_output_limiter() }
 	#[inline] fn max_aux_sends(&self) -> sys::ALCint { self.0.max_aux_sends() }
+	#[inline] fn min_latency(&self) -> AltoResult<Duration> { self.0.min_latency() }
+	#[inline] fn clock_time(&self) -> AltoResult<i64> { self.0.soft_device_clock() }
+	#[inline] fn clock_latency(&self) -> AltoResult<(i64, i64)> { self.0.clock_latency() }
+	#[inline] fn output_mode(&self) -> AltoResult<OutputMode> { self.0.output_mode() }
+	#[inline] fn implementation(&self) -> AltoResult<Implementation> { self.0.implementation() }
+	#[inline] fn is_soft(&self) -> AltoResult<bool> { self.0.implementation().map(|i| i == Implementation::Soft) }
 	#[inline] fn to_device(&self) -> Device { Device(self.0.clone()) }
 }
 
@@ -728,6 +1578,7 @@ unsafe impl DeviceObject for Device {
 	#[inline] fn alto(&self) -> &Alto { self.0.alto() }
 	#[inline] fn specifier(&self) -> Option<&CStr> { self.0.specifier() }
 	#[inline] fn as_raw(&self) -> *mut sys::ALCdevice { self.0.as_raw() }
+	#[inline] fn id(&self) -> usize { self.0.id }
 	#[inline] fn connected(&self) -> AltoResult<bool> { self.0.connected() }
 
 	#[inline] fn is_extension_present(&self, ext: ext::Alc) -> bool { self.0.is_extension_present(ext) }
@@ -735,6 +1586,12 @@ unsafe impl DeviceObject for Device {
 	#[inline] fn soft_hrtf_status(&self) -> SoftHrtfStatus { self.0.soft_hrtf_status() }
 	#[inline] fn soft_output_limiter(&self) -> bool { self.0.soft_output_limiter() }
 	#[inline] fn max_aux_sends(&self) -> sys::ALCint { self.0.max_aux_sends() }
+	#[inline] fn min_latency(&self) -> AltoResult<Duration> { self.0.min_latency() }
+	#[inline] fn clock_time(&self) -> AltoResult<i64> { self.0.soft_device_clock() }
+	#[inline] fn clock_latency(&self) -> AltoResult<(i64, i64)> { self.0.clock_latency() }
+	#[inline] fn output_mode(&self) -> AltoResult<OutputMode> { self.0.output_mode() }
+	#[inline] fn implementation(&self) -> AltoResult<Implementation> { self.0.implementation() }
+	#[inline] fn is_soft(&self) -> AltoResult<bool> { self.0.implementation().map(|i| i == Implementation::Soft) }
 	#[inline] fn to_device(&self) -> Device { Device(self.0.clone()) }
 }
 
@@ -754,6 +1611,39 @@ impl<F: StandardFrame> Capture<F> {
 	#[inline] pub fn specifier(&self) -> Option<&CStr> { self.spec.as_ref().map(|s| s.as_ref()) }
 	/// Raw device handle as reported by OpenAL.
 	#[inline] pub fn as_raw(&self) -> *mut sys::ALCdevice { self.dev }
+	/// Whether the device will be closed when this handle is dropped.
+	#[inline] pub fn is_owned(&self) -> bool { self.owned }
+
+
+	/// Wrap a capture device opened outside of alto, such as by another library sharing the same implementation.
+	/// The device must have been opened with the format of `F`, since that is what sample counts and reads assume.
+	/// If `owned` is true the device is closed when the handle is dropped and it counts toward `ShutdownReport::captures`,
+	/// otherwise it is left open for whoever opened it. The specifier is not known for adopted devices.
+	///
+	/// # Safety
+	/// `dev` must be a capture device opened through the same implementation as `alto` and must outlive the handle.
+	/// At most one owning handle may exist for it.
+	pub unsafe fn from_raw(alto: &Alto, dev: *mut sys::ALCdevice, owned: bool) -> AltoResult<Capture<F>> {
+		alto.check_shut_down()?;
+		if dev.is_null() {
+			return Err(AltoError::InvalidDevice);
+		}
+
+		if owned {
+			alto.0.captures.fetch_add(1, Ordering::Relaxed);
+		}
+		Ok(Capture{alto: alto.clone(), spec: None, dev, owned, marker: PhantomData})
+	}
+
+
+	/// Release the raw device handle without closing it. The caller becomes responsible for closing it if it was owned.
+	pub fn into_raw(mut self) -> *mut sys::ALCdevice {
+		if self.owned {
+			self.alto.0.captures.fetch_sub(1, Ordering::Relaxed);
+			self.owned = false;
+		}
+		self.dev
+	}
 
 
 	/// `alcCaptureStart()`
@@ -778,9 +1668,10 @@ impl<F: StandardFrame> Capture<F> {
 
 	/// `alcCaptureSamples()`
 	/// Returns the number of sample-frames captured to the slice.
+	/// A byte slice that ends partway through a frame is an `InvalidValue`.
 	pub fn capture_samples<R: AsBufferDataMut<F>>(&mut self, mut data: R) -> AltoResult<usize> {
 		let (data, size) = data.as_buffer_data_mut();
-		let len = cmp::min(size / mem::size_of::<F>(), self.samples_len() as usize);
+		let len = cmp::min(F::format().frames_in(size)?, self.samples_len() as usize);
 		if len == 0 {
 			return Ok(0);
 		}
@@ -800,8 +1691,184 @@ impl<F: StandardFrame> Eq for Capture<F> { }
 
 impl<F: StandardFrame> Drop for Capture<F> {
 	fn drop(&mut self) {
-		unsafe { self.alto.0.api.alcCaptureCloseDevice(self.dev); }
+		if self.owned {
+			unsafe { self.alto.0.api.alcCaptureCloseDevice(self.dev); }
+			self.alto.0.captures.fetch_sub(1, Ordering::Relaxed);
+		}
 	}
 }
 
 unsafe impl<F: StandardFrame> Send for Capture<F> { }
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+
+	#[test]
+	fn enumerate_outputs_cached() {
+		let a = Alto::load_default().unwrap();
+
+		let base = a.output_query_count();
+		let first = a.enumerate_outputs_cached();
+		assert_eq!(a.output_query_count(), base + 1);
+		assert_eq!(a.enumerate_outputs_cached(), first);
+		assert_eq!(a.output_query_count(), base + 1);
+
+		a.refresh_device_cache();
+		assert_eq!(a.enumerate_outputs_cached(), a.enumerate_outputs());
+		assert_eq!(a.output_query_count(), base + 3);
+	}
+
+
+	#[test]
+	fn parse_specifier_list() {
+		let names = |v: Vec<CString>| v.into_iter().map(|s| s.into_string().unwrap()).collect::<Vec<_>>();
+
+		// Without ALC_ENUMERATION_EXT, a single name; nothing past its terminator is read.
+		assert_eq!(names(Alto::parse_specifier_list(b"Generic Software\0garbage\0", false)), vec!["Generic Software"]);
+		assert_eq!(names(Alto::parse_specifier_list(b"Generic Software", false)), vec!["Generic Software"]);
+		assert!(Alto::parse_specifier_list(b"\0", false).is_empty());
+		assert!(Alto::parse_specifier_list(b"", false).is_empty());
+
+		// With it, a double-null terminated list.
+		assert_eq!(names(Alto::parse_specifier_list(b"Speakers\0Headphones\0\0garbage\0", true)), vec!["Speakers", "Headphones"]);
+		assert!(Alto::parse_specifier_list(b"\0\0", true).is_empty());
+	}
+
+
+	#[test]
+	fn annotate_specifiers() {
+		let names = Alto::parse_specifier_list(b"Mic\0Line In\0Bad \xff\0\0", true);
+		let default = CString::new("Line In").unwrap();
+		assert_eq!(Alto::annotate_specifiers(&names, Some(&default)), vec![
+			("Mic".to_string(), false),
+			("Line In".to_string(), true),
+			("Bad \u{fffd}".to_string(), false),
+		]);
+		assert!(Alto::annotate_specifiers(&names, None).iter().all(|&(_, d)| !d));
+
+		// Without ALC_ENUMERATION_EXT, the list is just the default device.
+		let single = Alto::parse_specifier_list(b"Mic\0", false);
+		let default = CString::new("Mic").unwrap();
+		assert_eq!(Alto::annotate_specifiers(&single, Some(&default)), vec![("Mic".to_string(), true)]);
+		assert!(Alto::annotate_specifiers(&[], Some(&default)).is_empty());
+	}
+
+
+	#[test]
+	fn legacy_version_check() {
+		match Alto::check_version_numbers(1, 0, false) {
+			Err(AltoError::UnsupportedVersion{found, required}) => {
+				assert_eq!((found, required), (Version::new(1, 0), Version::new(1, 1)));
+			},
+			r => panic!("{:?}", r),
+		}
+		assert_eq!(
+			Alto::check_version_numbers(1, 0, false).unwrap_err().to_string(),
+			"ALTO ERROR: Unsupported Version (found 1.0, required 1.1)",
+		);
+		match Alto::check_version_numbers(0, 9, true) {
+			Err(AltoError::UnsupportedVersion{found, required}) => {
+				assert_eq!((found, required), (Version::new(0, 9), Version::new(1, 0)));
+			},
+			r => panic!("{:?}", r),
+		}
+		assert!(Alto::check_version_numbers(1, 0, true).unwrap());
+		assert!(!Alto::check_version_numbers(1, 1, false).unwrap());
+		assert!(!Alto::check_version_numbers(1, 1, true).unwrap());
+		assert!(Alto::check_version_numbers(0, 9, true).is_err());
+		assert!(Alto::check_version_numbers(2, 0, true).is_err());
+	}
+
+
+	#[test]
+	fn capture_buffer_minimum() {
+		assert_eq!(Alto::min_capture_len(44_100), 441);
+		assert_eq!(Alto::min_capture_len(50), 1);
+		assert_eq!(Alto::min_capture_len(0), 1);
+	}
+
+
+	#[test]
+	fn period_duration() {
+		assert_eq!(Alto::period_duration(44_100, 50), Some(Duration::from_millis(20)));
+		assert_eq!(Alto::period_duration(48_000, 60), Some(Duration::from_nanos(16_666_666)));
+		// 44100 / 60 truncates to 735 frames.
+		assert_eq!(Alto::period_duration(44_100, 60), Some(Duration::from_nanos(735 * 1_000_000_000 / 44_100)));
+		assert_eq!(Alto::period_duration(100, 1000), Some(Duration::from_millis(10)));
+		assert_eq!(Alto::period_duration(0, 50), None);
+		assert_eq!(Alto::period_duration(44_100, 0), None);
+	}
+
+
+	#[test]
+	fn parse_clock_latency() {
+		assert_eq!(Alto::parse_clock_latency(&[5_000_000, 20_000]).unwrap(), (5_000_000, 20_000));
+		assert_eq!(Alto::parse_clock_latency(&[0, 0]).unwrap(), (0, 0));
+		for values in &[&[1i64][..], &[1, 2, 3][..], &[-1, 0][..], &[0, -1][..], &[][..]] {
+			match Alto::parse_clock_latency(values) {
+				Err(AltoError::InvalidValue) => (),
+				r => panic!("{:?}: {:?}", values, r),
+			}
+		}
+	}
+
+
+	#[test]
+	fn device_spec() {
+		let empty = CStr::from_bytes_with_nul(b"\0").unwrap();
+		let named = CStr::from_bytes_with_nul(b"Speakers\0").unwrap();
+		assert_eq!(Alto::device_spec(Some(empty)), None);
+		assert_eq!(Alto::device_spec(None), None);
+		assert_eq!(Alto::device_spec(Some(named)), Some(named));
+	}
+
+
+	#[test]
+	fn negotiate_format() {
+		use std::cell::Cell;
+
+		let candidates = [
+			Format::Standard(StandardFormat::StereoU8),
+			Format::Standard(StandardFormat::StereoI16),
+			Format::Standard(StandardFormat::MonoI16),
+		];
+		let opened = Cell::new(0);
+		let closed = Cell::new(0);
+		let open = |fmt: Format| -> Result<Format, AltoError> {
+			match fmt {
+				Format::Standard(StandardFormat::StereoU8) => Err(AltoError::InvalidDevice),
+				_ => { opened.set(opened.get() + 1); Ok(fmt) },
+			}
+		};
+
+		// The first format that opens wins, and its probe is closed.
+		let best = Alto::negotiate_format(&candidates, open, |_| closed.set(closed.get() + 1)).unwrap();
+		assert_eq!(best, Format::Standard(StandardFormat::StereoI16));
+		assert_eq!((opened.get(), closed.get()), (1, 1));
+
+		// Failed attempts leave nothing to close, and the last error is reported.
+		match Alto::negotiate_format(&candidates[.. 1], open, |_| closed.set(closed.get() + 1)) {
+			Err(AltoError::InvalidDevice) => (),
+			r => panic!("{:?}", r),
+		}
+		assert_eq!((opened.get(), closed.get()), (1, 1));
+		match Alto::negotiate_format(&[], open, |_: Format| panic!()) {
+			Err(AltoError::InvalidValue) => (),
+			r => panic!("{:?}", r),
+		}
+	}
+
+
+	#[test]
+	fn string_from_ptr() {
+		match unsafe { Alto::string_from_ptr(ptr::null()) } {
+			Err(AltoError::NullError) => (),
+			r => panic!("expected NullError, got {:?}", r),
+		}
+		let name = CStr::from_bytes_with_nul(b"OpenAL Soft\0").unwrap();
+		assert_eq!(unsafe { Alto::string_from_ptr(name.as_ptr()) }.unwrap().as_c_str(), name);
+	}
+}
@@ -0,0 +1,204 @@
+//! Sample conversion from high bit depths down to 16-bit integer buffer data.
+
+
+use AltoResult;
+use al::{Mono, Stereo, SampleSource};
+
+
+/// How quantization error is shaped when reducing bit depth.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DitherMode {
+	/// Round to the nearest representable value.
+	None,
+	/// Add triangular noise spanning ±1 LSB before rounding, decorrelating the error from the signal.
+	Tpdf,
+}
+
+
+/// Options accepted by every converter in this module.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ConvertOptions {
+	pub dither: DitherMode,
+	/// Seed for the dither noise generator. The same seed always produces the same output.
+	pub seed: u64,
+}
+
+
+impl Default for ConvertOptions {
+	fn default() -> ConvertOptions {
+		ConvertOptions{dither: DitherMode::None, seed: 0}
+	}
+}
+
+
+/// Deterministic triangular noise source.
+#[derive(Clone, Debug)]
+struct Tpdf(u64);
+
+
+impl Tpdf {
+	fn new(seed: u64) -> Tpdf {
+		// splitmix64 scrambles the seed so that small or zero seeds still give a usable xorshift state.
+		let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^= z >> 31;
+		Tpdf(if z == 0 { 1 } else { z })
+	}
+
+
+	fn uniform(&mut self) -> f64 {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		let x = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+		(x >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+
+	fn next(&mut self) -> f64 {
+		self.uniform() - self.uniform()
+	}
+}
+
+
+#[derive(Clone, Debug)]
+struct Quantizer {
+	noise: Option<Tpdf>,
+}
+
+
+impl Quantizer {
+	fn new(opts: &ConvertOptions) -> Quantizer {
+		Quantizer{noise: match opts.dither {
+			DitherMode::None => None,
+			DitherMode::Tpdf => Some(Tpdf::new(opts.seed)),
+		}}
+	}
+
+
+	/// Quantize a value expressed in 16-bit LSBs.
+	fn quantize(&mut self, lsb: f64) -> i16 {
+		let lsb = match self.noise {
+			Some(ref mut n) => lsb + n.next(),
+			None => lsb,
+		};
+		lsb.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16
+	}
+}
+
+
+/// Iterator adapter converting normalized `f32` samples to `i16`.
+#[derive(Clone, Debug)]
+pub struct F32ToI16<I> {
+	iter: I,
+	quant: Quantizer,
+}
+
+
+/// Iterator adapter converting full-range `i32` samples to `i16`.
+#[derive(Clone, Debug)]
+pub struct I32ToI16<I> {
+	iter: I,
+	quant: Quantizer,
+}
+
+
+impl<I: Iterator<Item = f32>> Iterator for F32ToI16<I> {
+	type Item = i16;
+
+	fn next(&mut self) -> Option<i16> {
+		self.iter.next().map(|v| self.quant.quantize(v as f64 * 32768.0))
+	}
+	fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+
+impl<I: Iterator<Item = i32>> Iterator for I32ToI16<I> {
+	type Item = i16;
+
+	fn next(&mut self) -> Option<i16> {
+		self.iter.next().map(|v| self.quant.quantize(v as f64 / 65536.0))
+	}
+	fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+
+/// Convert normalized `f32` samples in `[-1, 1]` to `i16`. Out of range values are clamped.
+pub fn f32_to_i16_iter<I: IntoIterator<Item = f32>>(iter: I, opts: &ConvertOptions) -> F32ToI16<I::IntoIter> {
+	F32ToI16{iter: iter.into_iter(), quant: Quantizer::new(opts)}
+}
+
+
+/// Convert full-range `i32` samples to `i16`.
+pub fn i32_to_i16_iter<I: IntoIterator<Item = i32>>(iter: I, opts: &ConvertOptions) -> I32ToI16<I::IntoIter> {
+	I32ToI16{iter: iter.into_iter(), quant: Quantizer::new(opts)}
+}
+
+
+/// Convert a slice of normalized `f32` samples to `i16`.
+pub fn f32_to_i16(src: &[f32], opts: &ConvertOptions) -> Vec<i16> {
+	f32_to_i16_iter(src.iter().cloned(), opts).collect()
+}
+
+
+/// Convert a slice of full-range `i32` samples to `i16`.
+pub fn i32_to_i16(src: &[i32], opts: &ConvertOptions) -> Vec<i16> {
+	i32_to_i16_iter(src.iter().cloned(), opts).collect()
+}
+
+
+/// Adapts a `SampleSource` producing `f32` frames, such as a float decoder, into one producing `i16` frames.
+/// Channels share one noise generator, so a given seed yields the same output regardless of read sizes.
+pub struct ConvertSource<F, S> {
+	samples: S,
+	quant: Quantizer,
+	scratch: Vec<F>,
+}
+
+
+impl<F, S> ConvertSource<F, S> {
+	pub fn new(samples: S, opts: &ConvertOptions) -> ConvertSource<F, S> {
+		ConvertSource{samples, quant: Quantizer::new(opts), scratch: Vec::new()}
+	}
+
+
+	/// The wrapped source.
+	pub fn samples(&self) -> &S { &self.samples }
+	/// Unwrap the source.
+	pub fn into_inner(self) -> S { self.samples }
+}
+
+
+impl<S: SampleSource<Mono<f32>>> SampleSource<Mono<i16>> for ConvertSource<Mono<f32>, S> {
+	fn frequency(&self) -> i32 { self.samples.frequency() }
+
+	fn read(&mut self, out: &mut Vec<Mono<i16>>, max: usize) -> AltoResult<usize> {
+		self.scratch.clear();
+		let len = self.samples.read(&mut self.scratch, max)?;
+		for f in &self.scratch {
+			out.push(Mono{center: self.quant.quantize(f.center as f64 * 32768.0)});
+		}
+		Ok(len)
+	}
+
+	fn seek(&mut self, frame: u64) -> AltoResult<u64> { self.samples.seek(frame) }
+}
+
+
+impl<S: SampleSource<Stereo<f32>>> SampleSource<Stereo<i16>> for ConvertSource<Stereo<f32>, S> {
+	fn frequency(&self) -> i32 { self.samples.frequency() }
+
+	fn read(&mut self, out: &mut Vec<Stereo<i16>>, max: usize) -> AltoResult<usize> {
+		self.scratch.clear();
+		let len = self.samples.read(&mut self.scratch, max)?;
+		for f in &self.scratch {
+			let left = self.quant.quantize(f.left as f64 * 32768.0);
+			let right = self.quant.quantize(f.right as f64 * 32768.0);
+			out.push(Stereo{left, right});
+		}
+		Ok(len)
+	}
+
+	fn seek(&mut self, frame: u64) -> AltoResult<u64> { self.samples.seek(frame) }
+}
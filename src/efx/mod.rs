@@ -1,4 +1,5 @@
-use std::sync::Weak;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::Ordering;
 
 use ::{AltoError, AltoResult};
 use sys;
@@ -15,10 +16,24 @@ pub use self::presets::*;
 pub struct AuxEffectSlot {
 	ctx: al::Context,
 	slot: sys::ALuint,
+	gen: u32,
+	alive: Arc<()>,
 	inputs: Vec<Weak<al::SourceInner>>,
 }
 
 
+/// What was last attached to one of a source's aux sends.
+/// EFX cannot report this back, so it is tracked by the source itself.
+#[derive(Clone, Debug)]
+pub struct SendInfo {
+	slot: sys::ALuint,
+	slot_gen: u32,
+	slot_ctx: usize,
+	slot_alive: Weak<()>,
+	filter: Option<(al::RawHandle, Weak<()>)>,
+}
+
+
 /// Implemented for effects defined by EFX.
 pub unsafe trait Effect: Sized {
 	#[doc(hidden)]
@@ -27,7 +42,10 @@ pub unsafe trait Effect: Sized {
 	/// Context from which this effect was created.
 	fn context(&self) -> &al::Context;
 	/// Raw handle as provided by OpenAL.
+	/// The name is handed out again once this object is dropped, so keep a `raw_handle` instead to be able to tell.
 	fn as_raw(&self) -> sys::ALuint;
+	/// Name and generation of this object, which `Context::check_handle` rejects once the object is dropped.
+	fn raw_handle(&self) -> al::RawHandle;
 }
 
 
@@ -35,6 +53,7 @@ pub unsafe trait Effect: Sized {
 pub struct EaxReverbEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -42,6 +61,7 @@ pub struct EaxReverbEffect {
 pub struct ReverbEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -49,6 +69,7 @@ pub struct ReverbEffect {
 pub struct ChorusEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -64,6 +85,7 @@ pub enum ChorusWaveform {
 pub struct DistortionEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -71,6 +93,7 @@ pub struct DistortionEffect {
 pub struct EchoEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -78,6 +101,7 @@ pub struct EchoEffect {
 pub struct FlangerEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -93,6 +117,7 @@ pub enum FlangerWaveform {
 pub struct FrequencyShifterEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -109,6 +134,7 @@ pub enum FrequencyShifterDirection {
 pub struct VocalMorpherEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -161,6 +187,7 @@ pub enum VocalMorpherWaveform {
 pub struct PitchShifterEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -168,6 +195,7 @@ pub struct PitchShifterEffect {
 pub struct RingModulatorEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -184,6 +212,7 @@ pub enum RingModulatorWaveform {
 pub struct AutowahEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -191,6 +220,7 @@ pub struct AutowahEffect {
 pub struct CompressorEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -198,6 +228,7 @@ pub struct CompressorEffect {
 pub struct EqualizerEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -206,6 +237,7 @@ pub struct EqualizerEffect {
 pub struct DedicatedLowFrequencyEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -214,6 +246,7 @@ pub struct DedicatedLowFrequencyEffect {
 pub struct DedicatedDialogueEffect {
 	ctx: al::Context,
 	effect: sys::ALuint,
+	gen: u32,
 }
 
 
@@ -225,7 +258,12 @@ pub unsafe trait Filter: Sized {
 	/// Context from which this effect was created.
 	fn context(&self) -> &al::Context;
 	/// Raw handle as provided by OpenAL.
+	/// The name is handed out again once this object is dropped, so keep a `raw_handle` instead to be able to tell.
 	fn as_raw(&self) -> sys::ALuint;
+	/// Name and generation of this object, which `Context::check_handle` rejects once the object is dropped.
+	fn raw_handle(&self) -> al::RawHandle;
+	#[doc(hidden)]
+	fn alive(&self) -> Weak<()>;
 }
 
 
@@ -233,6 +271,8 @@ pub unsafe trait Filter: Sized {
 pub struct LowpassFilter {
 	ctx: al::Context,
 	filter: sys::ALuint,
+	gen: u32,
+	alive: Arc<()>,
 }
 
 
@@ -240,6 +280,8 @@ pub struct LowpassFilter {
 pub struct HighpassFilter {
 	ctx: al::Context,
 	filter: sys::ALuint,
+	gen: u32,
+	alive: Arc<()>,
 }
 
 
@@ -247,6 +289,8 @@ pub struct HighpassFilter {
 pub struct BandpassFilter {
 	ctx: al::Context,
 	filter: sys::ALuint,
+	gen: u32,
+	alive: Arc<()>,
 }
 
 
@@ -449,7 +493,8 @@ impl AuxEffectSlot {
 			unsafe { efx.alGenAuxiliaryEffectSlots?(1, &mut slot); }
 			ctx.get_error()?;
 		}
-		Ok(AuxEffectSlot{ctx: ctx, slot: slot, inputs: Vec::new()})
+		ctx.0.slots.fetch_add(1, Ordering::Relaxed);
+		Ok(AuxEffectSlot{gen: ctx.0.generation(al::ObjectKind::AuxEffectSlot, slot), ctx: ctx, slot: slot, alive: Arc::new(()), inputs: Vec::new()})
 	}
 
 
@@ -458,14 +503,34 @@ impl AuxEffectSlot {
 			self.inputs.retain(|s| s.upgrade().is_some());
 		}
 
-		self.inputs.push(src);
+		if !self.inputs.iter().any(|s| Weak::ptr_eq(s, &src)) {
+			self.inputs.push(src);
+		}
+	}
+
+
+	pub(crate) fn send_info(&self, filter: Option<(al::RawHandle, Weak<()>)>) -> SendInfo {
+		SendInfo{slot: self.slot, slot_gen: self.gen, slot_ctx: self.ctx.0.id, slot_alive: Arc::downgrade(&self.alive), filter}
+	}
+
+
+	/// Raw source names and send indices currently routed into this slot.
+	pub fn attached_sources(&self) -> Vec<(sys::ALuint, sys::ALint)> {
+		self.inputs.iter()
+			.filter_map(|s| s.upgrade())
+			.flat_map(|s| s.aux_sends_to(self.raw_handle()))
+			.collect()
 	}
 
 
 	#[inline]
 	pub fn context(&self) -> &al::Context { &self.ctx }
+	/// The name is handed out again once this slot is dropped, so keep a `raw_handle` instead to be able to tell.
 	#[inline]
 	pub fn as_raw(&self) -> sys::ALuint { self.slot }
+	/// Name and generation of this slot, which `Context::check_handle` rejects once the slot is dropped.
+	#[inline]
+	pub fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::AuxEffectSlot, name: self.slot, generation: self.gen, owner: self.ctx.0.id} }
 
 
 	/// `alAuxiliaryEffectSloti(AL_EFFECTSLOT_EFFECT)`
@@ -523,16 +588,46 @@ impl AuxEffectSlot {
 
 impl Drop for AuxEffectSlot {
 	fn drop(&mut self) {
+		self.ctx.0.slots.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
+		let handle = self.raw_handle();
 		for src in self.inputs.drain(..) {
 			if let Some(src) = src.upgrade() {
-				src.clear_aux_effect_slot(self.slot);
+				src.clear_aux_effect_slot(handle);
 			}
 		}
 
 		let adaes = efx.alDeleteAuxiliaryEffectSlots.unwrap();
 		unsafe { adaes(1, &mut self.slot as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::AuxEffectSlot, self.slot);
+	}
+}
+
+
+impl SendInfo {
+	/// Raw name of the attached aux effect slot.
+	#[inline]
+	pub fn slot(&self) -> sys::ALuint { self.slot }
+	/// Whether the attached aux effect slot still exists.
+	#[inline]
+	pub fn is_slot_alive(&self) -> bool { self.slot_alive.upgrade().is_some() }
+	/// Name and generation of the attached aux effect slot, for use with `Context::check_handle`.
+	#[inline]
+	pub fn slot_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::AuxEffectSlot, name: self.slot, generation: self.slot_gen, owner: self.slot_ctx} }
+	/// Raw name of the send filter, if one was attached.
+	#[inline]
+	pub fn filter(&self) -> Option<sys::ALuint> { self.filter.as_ref().map(|&(f, _)| f.name) }
+	/// Name and generation of the send filter, if one was attached, for use with `Context::check_handle`.
+	#[inline]
+	pub fn filter_handle(&self) -> Option<al::RawHandle> { self.filter.as_ref().map(|&(f, _)| f) }
+	/// Whether the send filter still exists. `false` if no filter was attached.
+	#[inline]
+	pub fn is_filter_alive(&self) -> bool { self.filter.as_ref().is_some_and(|(_, a)| a.upgrade().is_some()) }
+
+
+	pub(crate) fn with_filter(&self, filter: Option<(al::RawHandle, Weak<()>)>) -> SendInfo {
+		SendInfo{slot: self.slot, slot_gen: self.slot_gen, slot_ctx: self.slot_ctx, slot_alive: self.slot_alive.clone(), filter}
 	}
 }
 
@@ -587,7 +682,8 @@ unsafe impl Effect for EaxReverbEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(EaxReverbEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(EaxReverbEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -595,11 +691,14 @@ unsafe impl Effect for EaxReverbEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
 impl EaxReverbEffect {
 	/// Set all effect properties based on a reverb preset.
+	/// Call again with a new preset, such as one blended with `EaxReverbProperties::lerp`, to re-apply.
 	pub fn set_preset(&mut self, preset: &EaxReverbProperties) -> AltoResult<()> {
 		let mut r = Ok(());
 
@@ -1025,10 +1124,12 @@ impl EaxReverbEffect {
 
 impl Drop for EaxReverbEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1059,7 +1160,8 @@ unsafe impl Effect for ReverbEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(ReverbEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(ReverbEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1067,11 +1169,14 @@ unsafe impl Effect for ReverbEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
 impl ReverbEffect {
 	/// Set all effect properties based on a reverb preset.
+	/// Call again with a new preset, such as one blended with `EaxReverbProperties::lerp`, to re-apply.
 	pub fn set_preset(&mut self, preset: &EaxReverbProperties) -> AltoResult<()> {
 		let mut r = Ok(());
 
@@ -1317,10 +1422,12 @@ impl ReverbEffect {
 
 impl Drop for ReverbEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1344,7 +1451,8 @@ unsafe impl Effect for ChorusEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(ChorusEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(ChorusEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1352,6 +1460,8 @@ unsafe impl Effect for ChorusEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -1461,10 +1571,12 @@ impl ChorusEffect {
 
 impl Drop for ChorusEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1487,7 +1599,8 @@ unsafe impl Effect for DistortionEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(DistortionEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(DistortionEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1495,6 +1608,8 @@ unsafe impl Effect for DistortionEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -1570,10 +1685,12 @@ impl DistortionEffect {
 
 impl Drop for DistortionEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1596,7 +1713,8 @@ unsafe impl Effect for EchoEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(EchoEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(EchoEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1604,6 +1722,8 @@ unsafe impl Effect for EchoEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -1696,10 +1816,12 @@ impl EchoEffect {
 
 impl Drop for EchoEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1723,7 +1845,8 @@ unsafe impl Effect for FlangerEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(FlangerEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(FlangerEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1731,6 +1854,8 @@ unsafe impl Effect for FlangerEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -1840,10 +1965,12 @@ impl FlangerEffect {
 
 impl Drop for FlangerEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1864,7 +1991,8 @@ unsafe impl Effect for FrequencyShifterEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(FrequencyShifterEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(FrequencyShifterEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1872,6 +2000,8 @@ unsafe impl Effect for FrequencyShifterEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -1930,10 +2060,12 @@ impl FrequencyShifterEffect {
 
 impl Drop for FrequencyShifterEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -1957,7 +2089,8 @@ unsafe impl Effect for VocalMorpherEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(VocalMorpherEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(VocalMorpherEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -1965,6 +2098,8 @@ unsafe impl Effect for VocalMorpherEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2074,10 +2209,12 @@ impl VocalMorpherEffect {
 
 impl Drop for VocalMorpherEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2097,7 +2234,8 @@ unsafe impl Effect for PitchShifterEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(PitchShifterEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(PitchShifterEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2105,6 +2243,8 @@ unsafe impl Effect for PitchShifterEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2146,10 +2286,12 @@ impl PitchShifterEffect {
 
 impl Drop for PitchShifterEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2170,7 +2312,8 @@ unsafe impl Effect for RingModulatorEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(RingModulatorEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(RingModulatorEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2178,6 +2321,8 @@ unsafe impl Effect for RingModulatorEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2236,10 +2381,12 @@ impl RingModulatorEffect {
 
 impl Drop for RingModulatorEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2261,7 +2408,8 @@ unsafe impl Effect for AutowahEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(AutowahEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(AutowahEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2269,6 +2417,8 @@ unsafe impl Effect for AutowahEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2344,10 +2494,12 @@ impl AutowahEffect {
 
 impl Drop for AutowahEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2366,7 +2518,8 @@ unsafe impl Effect for CompressorEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(CompressorEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(CompressorEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2374,6 +2527,8 @@ unsafe impl Effect for CompressorEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2398,10 +2553,12 @@ impl CompressorEffect {
 
 impl Drop for CompressorEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2429,7 +2586,8 @@ unsafe impl Effect for EqualizerEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(EqualizerEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(EqualizerEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2437,6 +2595,8 @@ unsafe impl Effect for EqualizerEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2614,10 +2774,12 @@ impl EqualizerEffect {
 
 impl Drop for EqualizerEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2637,7 +2799,8 @@ unsafe impl Effect for DedicatedLowFrequencyEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(DedicatedLowFrequencyEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(DedicatedLowFrequencyEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2645,6 +2808,8 @@ unsafe impl Effect for DedicatedLowFrequencyEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2671,10 +2836,12 @@ impl DedicatedLowFrequencyEffect {
 
 impl Drop for DedicatedLowFrequencyEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2694,7 +2861,8 @@ unsafe impl Effect for DedicatedDialogueEffect {
 			}
 			ctx.get_error()?;
 		}
-		Ok(DedicatedDialogueEffect{ctx: ctx, effect: effect})
+		ctx.0.effects.fetch_add(1, Ordering::Relaxed);
+		Ok(DedicatedDialogueEffect{gen: ctx.0.generation(al::ObjectKind::Effect, effect), ctx: ctx, effect: effect})
 	}
 
 
@@ -2702,6 +2870,8 @@ unsafe impl Effect for DedicatedDialogueEffect {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.effect }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Effect, name: self.effect, generation: self.gen, owner: self.ctx.0.dev.0.id} }
 }
 
 
@@ -2728,10 +2898,12 @@ impl DedicatedDialogueEffect {
 
 impl Drop for DedicatedDialogueEffect {
 	fn drop(&mut self) {
+		self.ctx.0.effects.fetch_sub(1, Ordering::Relaxed);
 		let efx = self.ctx.0.dev.0.exts.ALC_EXT_EFX().unwrap();
 		let _lock = self.ctx.make_current(true);
 		let ade = efx.alDeleteEffects.unwrap();
 		unsafe { ade(1, &mut self.effect as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Effect, self.effect);
 	}
 }
 
@@ -2765,7 +2937,7 @@ unsafe impl Filter for LowpassFilter {
 			}
 			ctx.get_error()?;
 		}
-		Ok(LowpassFilter{ctx: ctx, filter: filter})
+		Ok(LowpassFilter{gen: ctx.0.generation(al::ObjectKind::Filter, filter), ctx: ctx, filter: filter, alive: Arc::new(())})
 	}
 
 
@@ -2773,6 +2945,10 @@ unsafe impl Filter for LowpassFilter {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.filter }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Filter, name: self.filter, generation: self.gen, owner: self.ctx.0.dev.0.id} }
+	#[inline]
+	fn alive(&self) -> Weak<()> { Arc::downgrade(&self.alive) }
 }
 
 
@@ -2818,6 +2994,7 @@ impl Drop for LowpassFilter {
 		let _lock = self.ctx.make_current(true);
 		let adf = efx.alDeleteFilters.unwrap();
 		unsafe { adf(1, &mut self.filter as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Filter, self.filter);
 	}
 }
 
@@ -2837,7 +3014,7 @@ unsafe impl Filter for HighpassFilter {
 			}
 			ctx.get_error()?;
 		}
-		Ok(HighpassFilter{ctx: ctx, filter: filter})
+		Ok(HighpassFilter{gen: ctx.0.generation(al::ObjectKind::Filter, filter), ctx: ctx, filter: filter, alive: Arc::new(())})
 	}
 
 
@@ -2845,6 +3022,10 @@ unsafe impl Filter for HighpassFilter {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.filter }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Filter, name: self.filter, generation: self.gen, owner: self.ctx.0.dev.0.id} }
+	#[inline]
+	fn alive(&self) -> Weak<()> { Arc::downgrade(&self.alive) }
 }
 
 
@@ -2890,6 +3071,7 @@ impl Drop for HighpassFilter {
 		let _lock = self.ctx.make_current(true);
 		let adf = efx.alDeleteFilters.unwrap();
 		unsafe { adf(1, &mut self.filter as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Filter, self.filter);
 	}
 }
 
@@ -2910,7 +3092,7 @@ unsafe impl Filter for BandpassFilter {
 			}
 			ctx.get_error()?;
 		}
-		Ok(BandpassFilter{ctx: ctx, filter: filter})
+		Ok(BandpassFilter{gen: ctx.0.generation(al::ObjectKind::Filter, filter), ctx: ctx, filter: filter, alive: Arc::new(())})
 	}
 
 
@@ -2918,6 +3100,10 @@ unsafe impl Filter for BandpassFilter {
 	fn context(&self) -> &al::Context { &self.ctx }
 	#[inline]
 	fn as_raw(&self) -> sys::ALuint { self.filter }
+	#[inline]
+	fn raw_handle(&self) -> al::RawHandle { al::RawHandle{kind: al::ObjectKind::Filter, name: self.filter, generation: self.gen, owner: self.ctx.0.dev.0.id} }
+	#[inline]
+	fn alive(&self) -> Weak<()> { Arc::downgrade(&self.alive) }
 }
 
 
@@ -2980,5 +3166,6 @@ impl Drop for BandpassFilter {
 		let _lock = self.ctx.make_current(true);
 		let adf = efx.alDeleteFilters.unwrap();
 		unsafe { adf(1, &mut self.filter as *mut sys::ALuint); }
+		self.ctx.0.retire_name(al::ObjectKind::Filter, self.filter);
 	}
 }
@@ -1,4 +1,5 @@
 /// Represents a preset for the `AL_EFFECT_REVERB` and `AL_EFFECT_EAXREVERB` effects.
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub struct EaxReverbProperties {
 	pub density: f32,
 	pub diffusion: f32,
@@ -81,6 +82,45 @@ macro_rules! reverb_preset {
 }
 
 
+impl EaxReverbProperties {
+	/// Blend towards `other` by `t`, for moving smoothly between reverb zones.
+	/// Every numeric property, including each component of the pan vectors, is interpolated linearly,
+	/// while `decay_hflimit` switches over at the halfway point. `t` is clamped to `[0, 1]`,
+	/// so `0` gives `self` and `1` gives `other`. Apply the result with `set_preset`.
+	pub fn lerp(&self, other: &EaxReverbProperties, t: f32) -> EaxReverbProperties {
+		let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+		let mix = |a: f32, b: f32| if t == 1.0 { b } else { a + (b - a) * t };
+		let mix3 = |a: [f32; 3], b: [f32; 3]| [mix(a[0], b[0]), mix(a[1], b[1]), mix(a[2], b[2])];
+
+		EaxReverbProperties{
+			density: mix(self.density, other.density),
+			diffusion: mix(self.diffusion, other.diffusion),
+			gain: mix(self.gain, other.gain),
+			gainhf: mix(self.gainhf, other.gainhf),
+			gainlf: mix(self.gainlf, other.gainlf),
+			decay_time: mix(self.decay_time, other.decay_time),
+			decay_hfratio: mix(self.decay_hfratio, other.decay_hfratio),
+			decay_lfratio: mix(self.decay_lfratio, other.decay_lfratio),
+			reflections_gain: mix(self.reflections_gain, other.reflections_gain),
+			reflections_delay: mix(self.reflections_delay, other.reflections_delay),
+			reflections_pan: mix3(self.reflections_pan, other.reflections_pan),
+			late_reverb_gain: mix(self.late_reverb_gain, other.late_reverb_gain),
+			late_reverb_delay: mix(self.late_reverb_delay, other.late_reverb_delay),
+			late_reverb_pan: mix3(self.late_reverb_pan, other.late_reverb_pan),
+			echo_time: mix(self.echo_time, other.echo_time),
+			echo_depth: mix(self.echo_depth, other.echo_depth),
+			modulation_time: mix(self.modulation_time, other.modulation_time),
+			modulation_depth: mix(self.modulation_depth, other.modulation_depth),
+			air_absorption_gainhf: mix(self.air_absorption_gainhf, other.air_absorption_gainhf),
+			hfreference: mix(self.hfreference, other.hfreference),
+			lfreference: mix(self.lfreference, other.lfreference),
+			room_rolloff_factor: mix(self.room_rolloff_factor, other.room_rolloff_factor),
+			decay_hflimit: if t < 0.5 { self.decay_hflimit } else { other.decay_hflimit },
+		}
+	}
+}
+
+
 pub static REVERB_PRESET_GENERIC: EaxReverbProperties = reverb_preset! { 1.0000, 1.0000, 0.3162, 0.8913, 1.0000, 1.4900, 0.8300, 1.0000, 0.0500, 0.0070, [ 0.0000, 0.0000, 0.0000 ], 1.2589, 0.0110, [ 0.0000, 0.0000, 0.0000 ], 0.2500, 0.0000, 0.2500, 0.0000, 0.9943, 5000.0000, 250.0000, 0.0000, true };
 pub static REVERB_PRESET_PADDEDCELL: EaxReverbProperties = reverb_preset! { 0.1715, 1.0000, 0.3162, 0.0010, 1.0000, 0.1700, 0.1000, 1.0000, 0.2500, 0.0010, [ 0.0000, 0.0000, 0.0000 ], 1.2691, 0.0020, [ 0.0000, 0.0000, 0.0000 ], 0.2500, 0.0000, 0.2500, 0.0000, 0.9943, 5000.0000, 250.0000, 0.0000, true };
 pub static REVERB_PRESET_ROOM: EaxReverbProperties = reverb_preset! { 0.4287, 1.0000, 0.3162, 0.5929, 1.0000, 0.4000, 0.8300, 1.0000, 0.1503, 0.0020, [ 0.0000, 0.0000, 0.0000 ], 1.0629, 0.0030, [ 0.0000, 0.0000, 0.0000 ], 0.2500, 0.0000, 0.2500, 0.0000, 0.9943, 5000.0000, 250.0000, 0.0000, true };
@@ -129,7 +129,12 @@ macro_rules! al_ext {
 		impl $ext {
 			pub fn load(api: &AlApi) -> ExtResult<$ext> {
 				unsafe { api.alGetError(); }
-				if unsafe { api.alIsExtensionPresent(concat!(stringify!($ext), "\0").as_bytes().as_ptr() as *const ALchar) } == AL_TRUE {
+				let name: &[u8] = match stringify!($ext) {
+					// Workaround for extension names that are not valid identifiers
+					"EAX_RAM" => b"EAX-RAM\0",
+					_ => concat!(stringify!($ext), "\0").as_bytes(),
+				};
+				if unsafe { api.alIsExtensionPresent(name.as_ptr() as *const ALchar) } == AL_TRUE {
 					Ok($ext{
 						$($const_: {
 							let e = unsafe { api.alGetEnumValue(concat!(stringify!($const_), "\0").as_bytes().as_ptr() as *const ALchar) };
@@ -168,6 +173,8 @@ pub type ExtResult<T> = ::std::result::Result<T, ExtensionError>;
 
 #[derive(Copy, Clone, PartialEq, Hash, Eq, Debug)]
 pub enum AlcNull {
+	/// `ALC_ENUMERATION_EXT`
+	Enumeration,
 	/// `ALC_ENUMERATE_ALL_EXT`
 	EnumerateAll,
 	/// `ALC_SOFT_loopback`
@@ -191,6 +198,10 @@ pub enum Alc {
 	SoftPauseDevice,
 	/// `ALC_SOFT_output_limiter`
 	SoftOutputLimiter,
+	/// `ALC_SOFT_device_clock`
+	SoftDeviceClock,
+	/// `ALC_SOFT_output_mode`
+	SoftOutputMode,
 }
 
 
@@ -216,7 +227,8 @@ pub enum Al {
 	MuLawMcFormats,
 	/// `AL_SOFT_block_alignment`
 	SoftBlockAlignment,
-//	SoftBufferSamples,
+	/// `AL_SOFT_buffer_samples`
+	SoftBufferSamples,
 //	SoftBufferSubData,
 	/// `AL_SOFT_deferred_updates`
 	SoftDeferredUpdates,
@@ -236,12 +248,16 @@ pub enum Al {
 	SoftSourceSpatialize,
 	/// `AL_SOFT_source_resampler`
 	SoftSourceResampler,
+	/// `AL_SOFT_source_start_delay`
+	SoftSourceStartDelay,
 	/// `AL_SOFT_gain_clamp_ex`
 	SoftGainClampEx,
 	/// `AL_EXT_STEREO_ANGLES`
 	StereoAngles,
 	/// `AL_EXT_SOURCE_RADIUS`
 	SourceRadius,
+	/// `EAX-RAM`
+	XRam,
 }
 
 
@@ -255,6 +271,10 @@ alc_ext! {
 	}
 
 
+	pub ext ALC_ENUMERATION_EXT {
+	}
+
+
 	pub ext ALC_SOFT_loopback {
 		pub const ALC_BYTE_SOFT,
 		pub const ALC_UNSIGNED_BYTE_SOFT,
@@ -505,9 +525,34 @@ alc_ext! {
 
 		pub fn alcResetDeviceSOFT: unsafe extern "C" fn(dev: *mut ALCdevice, attrList: *const ALCint) -> ALCboolean,
 	}
+
+
+	pub ext ALC_SOFT_device_clock {
+		pub const ALC_DEVICE_CLOCK_SOFT,
+		pub const ALC_DEVICE_LATENCY_SOFT,
+		pub const ALC_DEVICE_CLOCK_LATENCY_SOFT,
+
+		pub fn alcGetInteger64vSOFT: unsafe extern "C" fn(dev: *mut ALCdevice, pname: ALCenum, size: ALCsizei, values: *mut ALCint64SOFT),
+	}
+
+
+	pub ext ALC_SOFT_output_mode {
+		pub const ALC_OUTPUT_MODE_SOFT,
+		pub const ALC_ANY_SOFT,
+		pub const ALC_MONO_SOFT,
+		pub const ALC_STEREO_SOFT,
+		pub const ALC_STEREO_BASIC_SOFT,
+		pub const ALC_STEREO_UHJ_SOFT,
+		pub const ALC_STEREO_HRTF_SOFT,
+		pub const ALC_QUAD_SOFT,
+		pub const ALC_SURROUND_5_1_SOFT,
+		pub const ALC_SURROUND_6_1_SOFT,
+		pub const ALC_SURROUND_7_1_SOFT,
+	}
 }
 
 
+pub type ALCint64SOFT = i64;
 pub type ALint64SOFT = i64;
 pub type ALuint64SOFT = u64;
 
@@ -598,60 +643,60 @@ al_ext! {
 	}
 
 
-//	pub ext AL_SOFT_buffer_samples {
-//		pub const AL_MONO_SOFT,
-//		pub const AL_STEREO_SOFT,
-//		pub const AL_REAR_SOFT,
-//		pub const AL_QUAD_SOFT,
-//		pub const AL_5POINT1_SOFT,
-//		pub const AL_6POINT1_SOFT,
-//		pub const AL_7POINT1_SOFT,
-//
-//		pub const AL_BYTE_SOFT,
-//		pub const AL_UNSIGNED_BYTE_SOFT,
-//		pub const AL_SHORT_SOFT,
-//		pub const AL_UNSIGNED_SHORT_SOFT,
-//		pub const AL_INT_SOFT,
-//		pub const AL_UNSIGNED_INT_SOFT,
-//		pub const AL_FLOAT_SOFT,
-//		pub const AL_DOUBLE_SOFT,
-//		pub const AL_BYTE3_SOFT,
-//		pub const AL_UNSIGNED_BYTE3_SOFT,
-//
-//		pub const AL_MONO8_SOFT,
-//		pub const AL_MONO16_SOFT,
-//		pub const AL_MONO32F_SOFT,
-//		pub const AL_STEREO8_SOFT,
-//		pub const AL_STEREO16_SOFT,
-//		pub const AL_STEREO32F_SOFT,
-//		pub const AL_QUAD8_SOFT,
-//		pub const AL_QUAD16_SOFT,
-//		pub const AL_QUAD32F_SOFT,
-//		pub const AL_REAR8_SOFT,
-//		pub const AL_REAR16_SOFT,
-//		pub const AL_REAR32F_SOFT,
-//		pub const AL_5POINT1_8_SOFT,
-//		pub const AL_5POINT1_16_SOFT,
-//		pub const AL_5POINT1_32F_SOFT,
-//		pub const AL_6POINT1_8_SOFT,
-//		pub const AL_6POINT1_16_SOFT,
-//		pub const AL_6POINT1_32F_SOFT,
-//		pub const AL_7POINT1_8_SOFT,
-//		pub const AL_7POINT1_16_SOFT,
-//		pub const AL_7POINT1_32F_SOFT,
-//
-//		pub const AL_INTERNAL_FORMAT_SOFT,
-//		pub const AL_BYTE_LENGTH_SOFT,
-//		pub const AL_SAMPLE_LENGTH_SOFT,
-//		pub const AL_SEC_LENGTH_SOFT,
-//
-//		pub fn alBufferSamplesSOFT: unsafe extern "C" fn(buffer: ALuint, samplerate: ALuint, internalformat: ALenum, samples: ALsizei, channels: ALenum, type_: ALenum, data: *const ALvoid),
-//		pub fn alBufferSubSamplesSOFT: unsafe extern "C" fn(buffer: ALuint, offset: ALsizei, samples: ALsizei, channels: ALenum, type_: ALenum, data: *const ALvoid),
-//		pub fn alGetBufferSamplesSOFT: unsafe extern "C" fn(buffer: ALuint, offset: ALsizei, samples: ALsizei, channels: ALenum, type_: ALenum, data: *mut ALvoid),
-//		pub fn alIsBufferFormatSupportedSOFT: unsafe extern "C" fn(format: ALenum) -> ALboolean,
-//	}
-//
-//
+	pub ext AL_SOFT_buffer_samples {
+		pub const AL_MONO_SOFT,
+		pub const AL_STEREO_SOFT,
+		pub const AL_REAR_SOFT,
+		pub const AL_QUAD_SOFT,
+		pub const AL_5POINT1_SOFT,
+		pub const AL_6POINT1_SOFT,
+		pub const AL_7POINT1_SOFT,
+
+		pub const AL_BYTE_SOFT,
+		pub const AL_UNSIGNED_BYTE_SOFT,
+		pub const AL_SHORT_SOFT,
+		pub const AL_UNSIGNED_SHORT_SOFT,
+		pub const AL_INT_SOFT,
+		pub const AL_UNSIGNED_INT_SOFT,
+		pub const AL_FLOAT_SOFT,
+		pub const AL_DOUBLE_SOFT,
+		pub const AL_BYTE3_SOFT,
+		pub const AL_UNSIGNED_BYTE3_SOFT,
+
+		pub const AL_MONO8_SOFT,
+		pub const AL_MONO16_SOFT,
+		pub const AL_MONO32F_SOFT,
+		pub const AL_STEREO8_SOFT,
+		pub const AL_STEREO16_SOFT,
+		pub const AL_STEREO32F_SOFT,
+		pub const AL_QUAD8_SOFT,
+		pub const AL_QUAD16_SOFT,
+		pub const AL_QUAD32F_SOFT,
+		pub const AL_REAR8_SOFT,
+		pub const AL_REAR16_SOFT,
+		pub const AL_REAR32F_SOFT,
+		pub const AL_5POINT1_8_SOFT,
+		pub const AL_5POINT1_16_SOFT,
+		pub const AL_5POINT1_32F_SOFT,
+		pub const AL_6POINT1_8_SOFT,
+		pub const AL_6POINT1_16_SOFT,
+		pub const AL_6POINT1_32F_SOFT,
+		pub const AL_7POINT1_8_SOFT,
+		pub const AL_7POINT1_16_SOFT,
+		pub const AL_7POINT1_32F_SOFT,
+
+		pub const AL_INTERNAL_FORMAT_SOFT,
+		pub const AL_BYTE_LENGTH_SOFT,
+		pub const AL_SAMPLE_LENGTH_SOFT,
+		pub const AL_SEC_LENGTH_SOFT,
+
+		pub fn alBufferSamplesSOFT: unsafe extern "C" fn(buffer: ALuint, samplerate: ALuint, internalformat: ALenum, samples: ALsizei, channels: ALenum, type_: ALenum, data: *const ALvoid),
+		pub fn alBufferSubSamplesSOFT: unsafe extern "C" fn(buffer: ALuint, offset: ALsizei, samples: ALsizei, channels: ALenum, type_: ALenum, data: *const ALvoid),
+		pub fn alGetBufferSamplesSOFT: unsafe extern "C" fn(buffer: ALuint, offset: ALsizei, samples: ALsizei, channels: ALenum, type_: ALenum, data: *mut ALvoid),
+		pub fn alIsBufferFormatSupportedSOFT: unsafe extern "C" fn(format: ALenum) -> ALboolean,
+	}
+
+
 //	pub ext AL_SOFT_buffer_sub_data {
 //		pub const AL_BYTE_RW_OFFSETS_SOFT,
 //		pub const AL_SAMPLE_RW_OFFSETS_SOFT,
@@ -740,10 +785,28 @@ al_ext! {
 	}
 
 
+	pub ext AL_SOFT_source_start_delay {
+		pub fn alSourcePlayAtTimeSOFT: unsafe extern "C" fn(source: ALuint, start_time: ALint64SOFT),
+		pub fn alSourcePlayAtTimevSOFT: unsafe extern "C" fn(n: ALsizei, sources: *const ALuint, start_time: ALint64SOFT),
+	}
+
+
 	pub ext AL_SOFT_source_spatialize {
 		pub const AL_SOURCE_SPATIALIZE_SOFT,
 		pub const AL_AUTO_SOFT,
 	}
+
+
+	pub ext EAX_RAM {
+		pub const AL_EAX_RAM_SIZE,
+		pub const AL_EAX_RAM_FREE,
+		pub const AL_STORAGE_AUTOMATIC,
+		pub const AL_STORAGE_HARDWARE,
+		pub const AL_STORAGE_ACCESSIBLE,
+
+		pub fn EAXSetBufferMode: unsafe extern "C" fn(n: ALsizei, buffers: *const ALuint, value: ALint) -> ALboolean,
+		pub fn EAXGetBufferMode: unsafe extern "C" fn(buffer: ALuint, reserved: *mut ALint) -> ALenum,
+	}
 }
 
 
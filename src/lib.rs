@@ -17,6 +17,10 @@
 extern crate lazy_static;
 extern crate parking_lot;
 extern crate al_sys;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "wav")]
+extern crate hound;
 
 use std::error::Error as StdError;
 use std::fmt;
@@ -37,13 +41,27 @@ pub mod ext;
 pub mod efx;
 
 
+pub mod conv;
+
+
+pub mod util;
+
+
+#[cfg(feature = "wav")]
+pub mod wav;
+
+
 pub mod sys {
 	pub use al_sys::*;
 }
 
 
 /// An error as reported by `alcGetError` or `alGetError`, plus some Alto specific variants.
+/// More variants may be added as extensions are supported, so matches outside of Alto need a wildcard arm.
+/// To handle every error without one, match on `kind` instead.
+/// Errors can be cloned, although a cloned `Io` error keeps only the kind and message of the original.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum AltoError {
 	/// `ALC_INVALID_DEVICE`
 	InvalidDevice,
@@ -59,11 +77,14 @@ pub enum AltoError {
 	InvalidOperation,
 	/// `ALC/AL_OUT_OF_MEMORY`
 	OutOfMemory,
+	/// An `alcGetError` code not covered by the variants above.
 	UnknownAlcError(sys::ALCint),
+	/// An `alGetError` code not covered by the variants above.
 	UnknownAlError(sys::ALint),
 
 	/// The underlying implementation is not compatible with the 1.1 spec. Alto specific.
-	UnsupportedVersion{major: sys::ALCint, minor: sys::ALCint},
+	/// Carries the ALC version the implementation reported and the version that was required.
+	UnsupportedVersion{found: Version, required: Version},
 	/// The requested action can't be performed because the required extension is unavaiable. Alto specific.
 	ExtensionNotPresent,
 	/// Resource creation failed without setting an error code.
@@ -71,7 +92,17 @@ pub enum AltoError {
 	/// A resource belongs to another device and is not eligible.
 	WrongDevice,
 	/// A resource belongs to another context and is not eligible.
-	WrongContext,
+	/// Carries the id of the context the operation required and the id of the context that owns the resource.
+	WrongContext{expected: usize, found: usize},
+	/// The implementation was shut down with `Alto::shutdown` and the resource is no longer usable. Alto specific.
+	ShutDown,
+	/// A `RawHandle` refers to an object that has since been deleted, even if its name now belongs to another object. Alto specific.
+	StaleHandle,
+	/// A capture device was opened with a ring buffer too short for its frequency, in sample frames.
+	/// The minimum is 10ms of audio. Alto specific.
+	CaptureBufferTooSmall{requested: sys::ALCsizei, minimum: sys::ALCsizei},
+	/// Buffer data was rejected before being uploaded, as it would fail with `AL_INVALID_VALUE`. Alto specific.
+	InvalidBufferData(BufferConstraint),
 	/// There was an underlying IO error, usually from a failure when loading the OpenAL dylib. Alto specific.
 	Io(io::Error),
 }
@@ -80,6 +111,72 @@ pub enum AltoError {
 pub type AltoResult<T> = ::std::result::Result<T, AltoError>;
 
 
+/// A broad classification of an `AltoError`, as returned by `AltoError::kind`.
+/// Unlike `AltoError`, this is not extended over time, so it can be matched exhaustively.
+/// New error variants are classified under one of these kinds.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ErrorKind {
+	/// A device, context, or object was invalid, deleted, or no longer usable.
+	InvalidHandle,
+	/// An argument or the data passed along with it was rejected.
+	InvalidArgument,
+	/// The operation is not allowed in the current state, or on the object it was given.
+	InvalidState,
+	/// The implementation or device lacks the required version or extension.
+	Unsupported,
+	/// The implementation ran out of memory.
+	OutOfMemory,
+	/// An IO error, such as a failure to load the OpenAL dylib.
+	Io,
+	/// An error code Alto does not recognize, or a failure without any error code.
+	Unknown,
+}
+
+
+/// An ALC version, as reported by `ALC_MAJOR_VERSION` and `ALC_MINOR_VERSION`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Version {
+	pub major: sys::ALCint,
+	pub minor: sys::ALCint,
+}
+
+
+impl Version {
+	pub fn new(major: sys::ALCint, minor: sys::ALCint) -> Version { Version{major, minor} }
+}
+
+
+impl fmt::Display for Version {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}.{}", self.major, self.minor)
+	}
+}
+
+
+/// The requirement on buffer data that was not met, as carried by `AltoError::InvalidBufferData`.
+/// A format whose extension is missing is reported as `AltoError::ExtensionNotPresent` instead.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BufferConstraint {
+	/// The data holds no sample frames.
+	Empty,
+	/// The data ends partway through a sample frame.
+	PartialFrame{size: usize, frame_size: usize},
+	/// The data is larger than an `ALsizei` can describe.
+	TooLarge{size: usize},
+}
+
+
+impl fmt::Display for BufferConstraint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			BufferConstraint::Empty => write!(f, "data is empty"),
+			BufferConstraint::PartialFrame{size, frame_size} => write!(f, "{} bytes is not a whole number of {} byte frames", size, frame_size),
+			BufferConstraint::TooLarge{size} => write!(f, "{} bytes is more than OpenAL can accept", size),
+		}
+	}
+}
+
+
 impl AltoError {
 	fn from_alc(alc: sys::ALCenum) -> AltoError {
 		match alc {
@@ -103,12 +200,111 @@ impl AltoError {
 			e => AltoError::UnknownAlError(e),
 		}
 	}
+
+
+	/// The `alcGetError` token this error was made from, or `None` if it can't come from ALC.
+	pub fn raw_alc_code(&self) -> Option<sys::ALCenum> {
+		match *self {
+			AltoError::InvalidDevice => Some(sys::ALC_INVALID_DEVICE),
+			AltoError::InvalidContext => Some(sys::ALC_INVALID_CONTEXT),
+			AltoError::InvalidEnum => Some(sys::ALC_INVALID_ENUM),
+			AltoError::InvalidValue => Some(sys::ALC_INVALID_VALUE),
+			AltoError::OutOfMemory => Some(sys::ALC_OUT_OF_MEMORY),
+			AltoError::UnknownAlcError(e) => Some(e),
+			_ => None,
+		}
+	}
+
+
+	/// The `alGetError` token this error was made from, or `None` if it can't come from AL.
+	pub fn raw_al_code(&self) -> Option<sys::ALenum> {
+		match *self {
+			AltoError::InvalidName => Some(sys::AL_INVALID_NAME),
+			AltoError::InvalidEnum => Some(sys::AL_INVALID_ENUM),
+			AltoError::InvalidValue => Some(sys::AL_INVALID_VALUE),
+			AltoError::InvalidOperation => Some(sys::AL_INVALID_OPERATION),
+			AltoError::OutOfMemory => Some(sys::AL_OUT_OF_MEMORY),
+			AltoError::UnknownAlError(e) => Some(e),
+			_ => None,
+		}
+	}
+
+
+	/// The broad kind of this error, for matching exhaustively without a wildcard arm.
+	pub fn kind(&self) -> ErrorKind {
+		match *self {
+			AltoError::InvalidDevice | AltoError::InvalidContext | AltoError::InvalidName
+				| AltoError::ShutDown | AltoError::StaleHandle => ErrorKind::InvalidHandle,
+			AltoError::InvalidEnum | AltoError::InvalidValue
+				| AltoError::CaptureBufferTooSmall{..} | AltoError::InvalidBufferData(..) => ErrorKind::InvalidArgument,
+			AltoError::InvalidOperation | AltoError::WrongDevice | AltoError::WrongContext{..} => ErrorKind::InvalidState,
+			AltoError::UnsupportedVersion{..} | AltoError::ExtensionNotPresent => ErrorKind::Unsupported,
+			AltoError::OutOfMemory => ErrorKind::OutOfMemory,
+			AltoError::Io(..) => ErrorKind::Io,
+			AltoError::UnknownAlcError(..) | AltoError::UnknownAlError(..) | AltoError::NullError => ErrorKind::Unknown,
+		}
+	}
+
+
+	/// Common causes of this error, for errors reported by OpenAL whose name alone says little.
+	/// This is included in the `Display` output when present.
+	pub fn hint(&self) -> Option<&'static str> {
+		match *self {
+			AltoError::InvalidDevice => Some("device may have been closed or disconnected"),
+			AltoError::InvalidContext => Some("context may have been destroyed, or no context is current"),
+			AltoError::InvalidName => Some("source, buffer, effect or filter may have been deleted or belong to another context"),
+			AltoError::InvalidEnum => Some("parameter may require an extension that is not present"),
+			AltoError::InvalidValue => Some("value may be out of range for the parameter, such as a negative gain or an unsupported frequency"),
+			AltoError::InvalidOperation => Some("source may be playing, or buffer is attached"),
+			_ => None,
+		}
+	}
+}
+
+
+// `io::Error` is not `Clone`, so neither can this be derived.
+impl Clone for AltoError {
+	fn clone(&self) -> AltoError {
+		match *self {
+			AltoError::InvalidDevice => AltoError::InvalidDevice,
+			AltoError::InvalidContext => AltoError::InvalidContext,
+			AltoError::InvalidName => AltoError::InvalidName,
+			AltoError::InvalidEnum => AltoError::InvalidEnum,
+			AltoError::InvalidValue => AltoError::InvalidValue,
+			AltoError::InvalidOperation => AltoError::InvalidOperation,
+			AltoError::OutOfMemory => AltoError::OutOfMemory,
+			AltoError::UnknownAlcError(e) => AltoError::UnknownAlcError(e),
+			AltoError::UnknownAlError(e) => AltoError::UnknownAlError(e),
+			AltoError::UnsupportedVersion{found, required} => AltoError::UnsupportedVersion{found, required},
+			AltoError::ExtensionNotPresent => AltoError::ExtensionNotPresent,
+			AltoError::NullError => AltoError::NullError,
+			AltoError::WrongDevice => AltoError::WrongDevice,
+			AltoError::WrongContext{expected, found} => AltoError::WrongContext{expected, found},
+			AltoError::ShutDown => AltoError::ShutDown,
+			AltoError::StaleHandle => AltoError::StaleHandle,
+			AltoError::CaptureBufferTooSmall{requested, minimum} => AltoError::CaptureBufferTooSmall{requested, minimum},
+			AltoError::InvalidBufferData(constraint) => AltoError::InvalidBufferData(constraint),
+			AltoError::Io(ref io) => AltoError::Io(io::Error::new(io.kind(), io.to_string())),
+		}
+	}
 }
 
 
 impl fmt::Display for AltoError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "{}", self.description())
+		match *self {
+			AltoError::WrongContext{expected, found} => write!(f, "ALTO ERROR: Resource used on wrong context (expected context {}, found context {})", expected, found),
+			AltoError::CaptureBufferTooSmall{requested, minimum} => write!(f, "ALTO ERROR: Capture buffer too small (requested {} frames, minimum {})", requested, minimum),
+			AltoError::UnsupportedVersion{found, required} => write!(f, "ALTO ERROR: Unsupported Version (found {}, required {})", found, required),
+			AltoError::InvalidBufferData(constraint) => write!(f, "ALTO ERROR: Invalid buffer data ({})", constraint),
+			_ => {
+				let desc = self.description();
+				match self.hint() {
+					Some(hint) => write!(f, "{} ({})", desc, hint),
+					None => write!(f, "{}", desc),
+				}
+			},
+		}
 	}
 }
 
@@ -130,7 +326,11 @@ impl StdError for AltoError {
 			AltoError::ExtensionNotPresent => "ALTO ERROR: Extension Not Present",
 			AltoError::NullError => "ALTO ERROR: Return value is NULL with no error code",
 			AltoError::WrongDevice => "ALTO ERROR: Resource used on wrong device",
-			AltoError::WrongContext => "ALTO ERROR: Resource used on wrong device",
+			AltoError::WrongContext{..} => "ALTO ERROR: Resource used on wrong context",
+			AltoError::ShutDown => "ALTO ERROR: Implementation has been shut down",
+			AltoError::StaleHandle => "ALTO ERROR: Handle refers to a deleted object",
+			AltoError::CaptureBufferTooSmall{..} => "ALTO ERROR: Capture buffer too small",
+			AltoError::InvalidBufferData(..) => "ALTO ERROR: Invalid buffer data",
 			AltoError::Io(ref io) => io.description(),
 		}
 	}
@@ -149,3 +349,13 @@ impl From<ext::ExtensionError> for AltoError {
 		AltoError::ExtensionNotPresent
 	}
 }
+
+
+impl From<AltoError> for io::Error {
+	fn from(alto: AltoError) -> io::Error {
+		match alto {
+			AltoError::Io(io) => io,
+			e => io::Error::other(e),
+		}
+	}
+}
@@ -0,0 +1,83 @@
+//! Small helpers for preparing sample data before it is uploaded to a buffer.
+
+
+use std::slice;
+
+use al::{SampleFrame, Int24Sample};
+
+
+/// A sample type that can be linearly interpolated.
+/// Companded formats such as A-law and mu-law are not, as their codes are not linear in amplitude.
+pub trait LinearSample: Copy {
+	/// The sample value, at its native scale.
+	fn to_f64(self) -> f64;
+	/// Round and clamp a value at the native scale back to a sample.
+	fn from_f64(value: f64) -> Self;
+}
+
+
+impl LinearSample for u8 {
+	fn to_f64(self) -> f64 { self as f64 }
+	fn from_f64(value: f64) -> u8 { value.round().clamp(0.0, u8::MAX as f64) as u8 }
+}
+impl LinearSample for i16 {
+	fn to_f64(self) -> f64 { self as f64 }
+	fn from_f64(value: f64) -> i16 { value.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16 }
+}
+impl LinearSample for Int24Sample {
+	fn to_f64(self) -> f64 { self.value() as f64 }
+	fn from_f64(value: f64) -> Int24Sample { Int24Sample::new(value.round().clamp(-8_388_608.0, 8_388_607.0) as i32) }
+}
+impl LinearSample for f32 {
+	fn to_f64(self) -> f64 { self as f64 }
+	fn from_f64(value: f64) -> f32 { value as f32 }
+}
+impl LinearSample for f64 {
+	fn to_f64(self) -> f64 { self }
+	fn from_f64(value: f64) -> f64 { value }
+}
+
+
+/// Resample `input` from `in_rate` to `out_rate` by linear interpolation between neighbouring frames, channel by channel.
+///
+/// Output frame `i` is taken from input position `i * in_rate / out_rate`, so the first frame is kept as is.
+/// Positions past the last input frame hold its value. The output has `ceil(len * out_rate / in_rate)` frames,
+/// and is empty if either rate is zero.
+pub fn resample_linear<S: SampleFrame>(input: &[S], in_rate: u32, out_rate: u32) -> Vec<S> where S::Sample: LinearSample {
+	if in_rate == 0 || out_rate == 0 || input.is_empty() {
+		return Vec::new();
+	}
+	if in_rate == out_rate {
+		return input.to_vec();
+	}
+
+	let (in_rate, out_rate) = (in_rate as u64, out_rate as u64);
+	let out_len = (input.len() as u64 * out_rate).div_ceil(in_rate) as usize;
+	let last = input.len() - 1;
+	let mut output = Vec::with_capacity(out_len);
+	for i in 0 .. out_len as u64 {
+		let pos = i * in_rate;
+		let idx = (pos / out_rate) as usize;
+		let t = (pos % out_rate) as f64 / out_rate as f64;
+
+		let mut frame = input[idx.min(last)];
+		if idx < last && t > 0.0 {
+			let next = samples(&input[idx + 1]);
+			for (s, &n) in samples_mut(&mut frame).iter_mut().zip(next) {
+				let a = s.to_f64();
+				*s = S::Sample::from_f64(a + (n.to_f64() - a) * t);
+			}
+		}
+		output.push(frame);
+	}
+	output
+}
+
+
+// Every frame type is a `repr(C)` struct made up of exactly `len()` samples.
+fn samples<S: SampleFrame>(frame: &S) -> &[S::Sample] {
+	unsafe { slice::from_raw_parts(frame as *const S as *const S::Sample, S::len()) }
+}
+fn samples_mut<S: SampleFrame>(frame: &mut S) -> &mut [S::Sample] {
+	unsafe { slice::from_raw_parts_mut(frame as *mut S as *mut S::Sample, S::len()) }
+}
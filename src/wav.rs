@@ -0,0 +1,157 @@
+//! Recording capture devices to WAV files. Requires the `wav` feature.
+
+
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, Write};
+use std::path::Path;
+
+use hound::{self, SampleFormat, WavSpec, WavWriter};
+
+use ::{AltoError, AltoResult};
+use alc::Capture;
+use al::{Mono, Stereo, StandardFrame};
+
+
+/// Somewhere captured sample frames can be drained from, such as a `Capture` device.
+pub trait CaptureSource<F: StandardFrame> {
+	/// `alcCaptureStart()`
+	fn start(&mut self);
+	/// `alcCaptureStop()`
+	fn stop(&mut self);
+	/// `alcGetIntegerv(ALC_CAPTURE_SAMPLES)`
+	/// Number of frames ready to be read.
+	fn available(&self) -> usize;
+	/// `alcCaptureSamples()`
+	/// Read up to `out.len()` frames, returning how many were read.
+	fn read(&mut self, out: &mut [F]) -> AltoResult<usize>;
+}
+
+
+impl<F: StandardFrame> CaptureSource<F> for Capture<F> {
+	fn start(&mut self) { Capture::start(self) }
+	fn stop(&mut self) { Capture::stop(self) }
+	fn available(&self) -> usize { self.samples_len().max(0) as usize }
+	fn read(&mut self, out: &mut [F]) -> AltoResult<usize> { self.capture_samples(out) }
+}
+
+
+/// A sample frame that can be written to a WAV file as integer PCM.
+pub trait WavFrame: StandardFrame {
+	/// The WAV format for frames of this type at `freq`.
+	fn spec(freq: u32) -> WavSpec;
+	/// Append this frame to `writer`.
+	fn write<W: Write + Seek>(&self, writer: &mut WavWriter<W>) -> hound::Result<()>;
+}
+
+
+// 8-bit WAV data is unsigned like OpenAL's, but hound takes it as signed samples centered on 0.
+fn u8_sample(s: u8) -> i8 { (s as i16 - 128) as i8 }
+
+
+fn spec(channels: u16, bits: u16, freq: u32) -> WavSpec {
+	WavSpec{channels, sample_rate: freq, bits_per_sample: bits, sample_format: SampleFormat::Int}
+}
+
+
+impl WavFrame for Mono<u8> {
+	fn spec(freq: u32) -> WavSpec { spec(1, 8, freq) }
+	fn write<W: Write + Seek>(&self, writer: &mut WavWriter<W>) -> hound::Result<()> {
+		writer.write_sample(u8_sample(self.center))
+	}
+}
+impl WavFrame for Mono<i16> {
+	fn spec(freq: u32) -> WavSpec { spec(1, 16, freq) }
+	fn write<W: Write + Seek>(&self, writer: &mut WavWriter<W>) -> hound::Result<()> {
+		writer.write_sample(self.center)
+	}
+}
+impl WavFrame for Stereo<u8> {
+	fn spec(freq: u32) -> WavSpec { spec(2, 8, freq) }
+	fn write<W: Write + Seek>(&self, writer: &mut WavWriter<W>) -> hound::Result<()> {
+		writer.write_sample(u8_sample(self.left))?;
+		writer.write_sample(u8_sample(self.right))
+	}
+}
+impl WavFrame for Stereo<i16> {
+	fn spec(freq: u32) -> WavSpec { spec(2, 16, freq) }
+	fn write<W: Write + Seek>(&self, writer: &mut WavWriter<W>) -> hound::Result<()> {
+		writer.write_sample(self.left)?;
+		writer.write_sample(self.right)
+	}
+}
+
+
+/// Writes everything a capture device records to a WAV file.
+/// `pump` must be called regularly, often enough that the capture ring buffer does not overrun.
+pub struct Recorder<F: WavFrame, C: CaptureSource<F>, W: Write + Seek> {
+	capture: C,
+	writer: WavWriter<W>,
+	scratch: Vec<F>,
+	frames: u64,
+}
+
+
+impl<F: WavFrame> Recorder<F, Capture<F>, BufWriter<File>> {
+	/// Record from `capture`, opened at `freq`, to a new WAV file at `path`.
+	pub fn create<P: AsRef<Path>>(capture: Capture<F>, freq: u32, path: P) -> AltoResult<Self> {
+		let file = BufWriter::new(File::create(path)?);
+		Recorder::new(capture, freq, file)
+	}
+}
+
+
+impl<F: WavFrame, C: CaptureSource<F>, W: Write + Seek> Recorder<F, C, W> {
+	/// Record from `capture`, running at `freq`, to `writer`. The WAV header is written straight away.
+	pub fn new(capture: C, freq: u32, writer: W) -> AltoResult<Self> {
+		let writer = WavWriter::new(writer, F::spec(freq)).map_err(wav_error)?;
+		Ok(Recorder{capture, writer, scratch: Vec::new(), frames: 0})
+	}
+
+
+	/// The capture device being recorded.
+	pub fn capture(&self) -> &C { &self.capture }
+	/// Frames written so far.
+	pub fn frames_written(&self) -> u64 { self.frames }
+
+
+	/// `alcCaptureStart()`
+	pub fn start(&mut self) { self.capture.start() }
+	/// `alcCaptureStop()`
+	/// Frames already captured can still be drained with `pump`.
+	pub fn stop(&mut self) { self.capture.stop() }
+
+
+	/// `alcCaptureSamples()`
+	/// Drain every available frame to the file, returning how many were written.
+	pub fn pump(&mut self) -> AltoResult<usize> {
+		let available = self.capture.available();
+		if available == 0 {
+			return Ok(0);
+		}
+
+		self.scratch.clear();
+		self.scratch.resize(available, unsafe { ::std::mem::zeroed() });
+		let len = self.capture.read(&mut self.scratch)?;
+		for frame in &self.scratch[.. len] {
+			frame.write(&mut self.writer).map_err(wav_error)?;
+		}
+		self.frames += len as u64;
+		Ok(len)
+	}
+
+
+	/// Stop capturing, write out whatever was still captured, and complete the WAV header.
+	pub fn finish(mut self) -> io::Result<()> {
+		self.capture.stop();
+		self.pump()?;
+		self.writer.finalize().map_err(|e| wav_error(e).into())
+	}
+}
+
+
+fn wav_error(e: hound::Error) -> AltoError {
+	match e {
+		hound::Error::IoError(e) => AltoError::Io(e),
+		e => AltoError::Io(io::Error::other(e)),
+	}
+}
@@ -2,7 +2,7 @@ extern crate alto;
 
 use std::ffi::CStr;
 
-use alto::{Alto, Capture, DeviceObject, Stereo};
+use alto::{Alto, AltoError, Capture, DeviceObject, Mono, Stereo};
 
 type MyCapture = Capture<Stereo<i16>>;
 
@@ -65,3 +65,2876 @@ fn specified_input() {
         assert_eq!(dev.specifier().unwrap(), device.as_ref());
     }
 }
+
+
+#[test]
+fn buffer_duplicate() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let buf = ctx.new_buffer(data, 44_100).unwrap();
+
+    match buf.duplicate(&ctx) {
+        Ok(dup) => {
+            assert!(dup.as_raw() != buf.as_raw());
+            assert_eq!(dup.frequency(), buf.frequency());
+            assert_eq!(dup.channels(), buf.channels());
+            assert_eq!(dup.bits(), buf.bits());
+            assert_eq!(dup.size(), buf.size());
+        },
+        Err(AltoError::ExtensionNotPresent) => (),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn cross_context_use() {
+    use alto::Source;
+    use alto::efx::{Filter, LowpassFilter, ReverbEffect};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx_a = dev.new_context(None).unwrap();
+    let ctx_b = dev.new_context(None).unwrap();
+    assert!(ctx_a.id() != ctx_b.id());
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let buf = std::sync::Arc::new(ctx_a.new_buffer(data, 44_100).unwrap());
+    let mut src = ctx_b.new_static_source().unwrap();
+    ctx_b.check_handle(buf.raw_handle()).unwrap();
+    assert_eq!(buf.raw_handle().owner, dev.id());
+    src.set_buffer(buf).unwrap();
+
+    // Sources are not shared, neither directly nor through a raw handle.
+    assert_eq!(src.raw_handle().owner, ctx_b.id());
+    match ctx_a.check_handle(src.raw_handle()) {
+        Err(AltoError::WrongContext{expected, found}) => {
+            assert_eq!(expected, ctx_a.id());
+            assert_eq!(found, ctx_b.id());
+        },
+        r => panic!("{:?}", r),
+    }
+    match ctx_a.set_positions(std::slice::from_mut(&mut src), &[[0.0; 3]]) {
+        Err(AltoError::WrongContext{expected, found}) => {
+            assert_eq!(expected, ctx_a.id());
+            assert_eq!(found, ctx_b.id());
+        },
+        r => panic!("{:?}", r),
+    }
+
+    if dev.is_extension_present(alto::ext::Alc::Efx) {
+        let filter = ctx_a.new_filter::<LowpassFilter>().unwrap();
+        match src.set_direct_filter(&filter) {
+            Err(AltoError::WrongContext{expected, found}) => {
+                assert_eq!(expected, ctx_b.id());
+                assert_eq!(found, ctx_a.id());
+            },
+            r => panic!("{:?}", r),
+        }
+        ctx_b.check_handle(filter.raw_handle()).unwrap();
+
+        let mut slot = ctx_a.new_aux_effect_slot().unwrap();
+        match src.set_aux_send(0, &mut slot) {
+            Err(AltoError::WrongContext{expected, found}) => {
+                assert_eq!(expected, ctx_b.id());
+                assert_eq!(found, ctx_a.id());
+            },
+            r => panic!("{:?}", r),
+        }
+        match ctx_b.check_handle(slot.raw_handle()) {
+            Err(AltoError::WrongContext{..}) => (),
+            r => panic!("{:?}", r),
+        }
+
+        let effect = ctx_b.new_effect::<ReverbEffect>().unwrap();
+        match slot.set_effect(&effect) {
+            Err(AltoError::InvalidValue) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+}
+
+#[test]
+fn source_outlives_context_handle() {
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let buf = ctx.new_buffer(data, 44_100).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    drop(ctx);
+
+    src.set_gain(0.5).unwrap();
+    assert_eq!(src.gain(), 0.5);
+    assert_eq!(buf.frequency(), 44_100);
+}
+
+#[test]
+fn xram_storage() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let mut buf = ctx.new_buffer(data, 44_100).unwrap();
+
+    if ctx.is_extension_present(alto::ext::Al::XRam) {
+        assert!(ctx.xram_free().unwrap() >= 0);
+        buf.storage_mode().unwrap();
+    } else {
+        assert_eq!(ctx.xram_free().unwrap_err().to_string(), AltoError::ExtensionNotPresent.to_string());
+        match buf.storage_mode() {
+            Err(AltoError::ExtensionNotPresent) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+}
+
+#[test]
+fn loopback_render_frame_count() {
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let _ctx = dev.new_context(44_100, None).unwrap();
+
+    let mut data = vec![Stereo{left: 0.0f32, right: 0.0}; 64];
+    assert_eq!(dev.soft_render_samples(&mut data[..]), 64);
+}
+
+#[test]
+fn drift_pitch_nudge() {
+    use alto::{drift_pitch_nudge, MAX_DRIFT_NUDGE};
+
+    assert_eq!(drift_pitch_nudge(1_000_000_000, 1_000_000_000), 0.0);
+    assert_eq!(drift_pitch_nudge(0, 1_000_000_000), 0.0);
+
+    // Secondary 0.1% fast: slow it down by the same ratio.
+    let nudge = drift_pitch_nudge(1_000_000_000, 1_001_000_000);
+    assert!((nudge - (1_000.0 / 1_001.0 - 1.0)).abs() < 1e-6);
+    assert!(nudge < 0.0);
+
+    // Secondary 0.1% slow: speed it up.
+    assert!(drift_pitch_nudge(1_001_000_000, 1_000_000_000) > 0.0);
+
+    // Gross drift is clamped.
+    assert_eq!(drift_pitch_nudge(1_000_000_000, 2_000_000_000), -MAX_DRIFT_NUDGE);
+    assert_eq!(drift_pitch_nudge(2_000_000_000, 1_000_000_000), MAX_DRIFT_NUDGE);
+}
+
+#[test]
+fn drift_offset_nudge() {
+    use alto::{drift_offset_nudge, DRIFT_CORRECTION_WINDOW, MAX_DRIFT_NUDGE};
+
+    assert_eq!(drift_offset_nudge(0), 0.0);
+    // A secondary that is ahead is slowed down, in proportion to the offset.
+    assert!((drift_offset_nudge(DRIFT_CORRECTION_WINDOW / 1_000) + 0.001).abs() < 1e-6);
+    assert!(drift_offset_nudge(-DRIFT_CORRECTION_WINDOW / 1_000) > 0.0);
+    assert_eq!(drift_offset_nudge(DRIFT_CORRECTION_WINDOW), -MAX_DRIFT_NUDGE);
+}
+
+#[test]
+fn mirror_output() {
+    let a = load_alto();
+    let mirror = alto::MirrorOutput::new(a.open(None).unwrap(), a.open(None).unwrap()).unwrap();
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let buf = mirror.new_buffer(data, 44_100).unwrap();
+    let mut src = mirror.new_source().unwrap();
+    src.set_buffer(&buf).unwrap();
+    src.set_gain(0.25).unwrap();
+    src.set_pitch(1.5).unwrap();
+
+    {
+        use alto::Source;
+        assert_eq!(src.primary().gain(), 0.25);
+        assert_eq!(src.secondary().gain(), 0.25);
+        assert_eq!(src.primary().pitch(), 1.5);
+    }
+
+    let nudge = mirror.apply_clock_readings(1_000_000_000, 1_001_000_000).unwrap();
+    assert_eq!(nudge, mirror.drift_nudge());
+    {
+        use alto::Source;
+        assert!((src.secondary().pitch() - 1.5 * (1.0 + nudge)).abs() < 1e-4);
+    }
+    assert_eq!(src.pitch(), 1.5);
+}
+
+#[test]
+fn into_io_error() {
+    use std::error::Error;
+    use std::io;
+
+    #[allow(deprecated)]
+    let desc = AltoError::ExtensionNotPresent.description().to_owned();
+    let io: io::Error = AltoError::ExtensionNotPresent.into();
+    assert_eq!(io.kind(), io::ErrorKind::Other);
+    assert!(io.to_string().contains(&desc));
+
+    let io: io::Error = AltoError::Io(io::Error::new(io::ErrorKind::NotFound, "missing")).into();
+    assert_eq!(io.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+fn field_counter_orientation() {
+    use alto::field_counter_orientation;
+
+    fn assert_near(a: [f32; 3], b: [f32; 3]) {
+        for i in 0 .. 3 {
+            assert!((a[i] - b[i]).abs() < 1e-6, "{:?} != {:?}", a, b);
+        }
+    }
+
+    // Facing forward: no rotation.
+    let (at, up) = field_counter_orientation([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]);
+    assert_near(at, [0.0, 0.0, -1.0]);
+    assert_near(up, [0.0, 1.0, 0.0]);
+
+    // Listener turned 90 degrees left: field turns 90 degrees right.
+    let (at, up) = field_counter_orientation([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    assert_near(at, [1.0, 0.0, 0.0]);
+    assert_near(up, [0.0, 1.0, 0.0]);
+
+    // Listener upside down: field is rolled over to match.
+    let (at, up) = field_counter_orientation([0.0, 0.0, -1.0], [0.0, -1.0, 0.0]);
+    assert_near(at, [0.0, 0.0, -1.0]);
+    assert_near(up, [0.0, -1.0, 0.0]);
+
+    // Unnormalized and non-orthogonal input is tolerated.
+    let (at, up) = field_counter_orientation([-2.0, 0.0, 0.0], [3.0, 2.0, 0.0]);
+    assert_near(at, [1.0, 0.0, 0.0]);
+    assert_near(up, [0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn process_streams() {
+    use alto::{Source, SourceState};
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 4096];
+
+    let data: Vec<_> = (0 .. 64).map(|_| Mono{center: 0i16}).collect();
+    let mut idle = ctx.new_streaming_source().unwrap();
+    idle.queue_buffer(ctx.new_buffer(&data, 44_100).unwrap()).unwrap();
+
+    let mut src = ctx.new_streaming_source().unwrap();
+    for _ in 0 .. 3 {
+        src.queue_buffer(ctx.new_buffer(&data, 44_100).unwrap()).unwrap();
+    }
+    src.play();
+    dev.soft_render_samples(&mut out[..]);
+
+    // Everything played through; nothing left to restart.
+    ctx.process_streams().unwrap();
+    assert_eq!(src.buffers_processed(), 3);
+    assert_eq!(src.buffers_queued(), 3);
+    assert_eq!(src.state(), SourceState::Stopped);
+    for _ in 0 .. 3 {
+        src.unqueue_buffer().unwrap();
+    }
+    assert_eq!(src.buffers_queued(), 0);
+
+    // Underrun with a freshly queued buffer is restarted.
+    src.queue_buffer(ctx.new_buffer(&data, 44_100).unwrap()).unwrap();
+    ctx.process_streams().unwrap();
+    assert_eq!(src.state(), SourceState::Playing);
+
+    // A source that was never played is left alone.
+    assert_eq!(idle.buffers_processed(), 0);
+    assert_eq!(idle.state(), SourceState::Initial);
+}
+
+#[test]
+fn shutdown_with_live_source() {
+    use std::sync::mpsc;
+    use std::thread;
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(data, 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.play();
+    drop(ctx);
+    drop(dev);
+
+    let (shut_tx, shut_rx) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        shut_rx.recv().unwrap();
+        match src.set_gain(0.5) {
+            Err(AltoError::ShutDown) => (),
+            r => panic!("{:?}", r),
+        }
+    });
+
+    let report = a.clone().shutdown();
+    assert_eq!(report.devices, 1);
+    assert_eq!(report.contexts, 1);
+    assert_eq!(report.sources, 1);
+    assert_eq!(report.buffers, 1);
+
+    shut_tx.send(()).unwrap();
+    worker.join().unwrap();
+
+    match a.open(None) {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r.map(|_| ())),
+    }
+}
+
+#[test]
+fn loopback_frequency() {
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let _ctx = dev.new_context(22_050, None).unwrap();
+
+    assert_eq!(dev.frequency().unwrap(), 22_050);
+    assert!(dev.refresh().unwrap() > 0);
+}
+
+#[test]
+fn context_stats() {
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    assert_eq!(ctx.stats(), Default::default());
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let mut buf = ctx.new_buffer(&data, 44_100).unwrap();
+    let stats = ctx.stats();
+    assert_eq!(stats.buffers, 1);
+    assert_eq!(stats.buffered_bytes, 882);
+    assert!((stats.buffered_secs - 0.01).abs() < 1e-9);
+
+    buf.set_data(&data[.. 100], 44_100).unwrap();
+    assert_eq!(ctx.stats().buffered_bytes, 200);
+
+    let mut src = ctx.new_static_source().unwrap();
+    let _streaming = ctx.new_streaming_source().unwrap();
+    src.set_buffer(std::sync::Arc::new(buf)).unwrap();
+    src.play();
+    src.pause();
+    let stats = ctx.stats();
+    assert_eq!(stats.sources, 2);
+    assert_eq!(stats.paused, 1);
+    assert_eq!(stats.initial, 1);
+    assert!(stats.to_string().starts_with("2 sources (1 initial, 0 playing, 1 paused, 0 stopped), "), "{}", stats);
+
+    drop(src);
+    let stats = ctx.stats();
+    assert_eq!(stats.sources, 1);
+    assert_eq!(stats.buffers, 0);
+    assert_eq!(stats.buffered_bytes, 0);
+}
+
+struct Ramp {
+    pos: u64,
+    len: u64,
+}
+
+impl alto::SampleSource<Stereo<i16>> for Ramp {
+    fn frequency(&self) -> i32 { 22_050 }
+
+    fn read(&mut self, out: &mut Vec<Stereo<i16>>, max: usize) -> alto::AltoResult<usize> {
+        let n = std::cmp::min(max as u64, self.len - self.pos);
+        for i in self.pos .. self.pos + n {
+            let v = (i % 30_000) as i16;
+            out.push(Stereo{left: v, right: v});
+        }
+        self.pos += n;
+        Ok(n as usize)
+    }
+
+    fn seek(&mut self, frame: u64) -> alto::AltoResult<u64> {
+        self.pos = std::cmp::min(frame, self.len);
+        Ok(self.pos)
+    }
+}
+
+#[test]
+fn source_stream_seek() {
+    use std::time::Duration;
+    use alto::{Source, SourceStream};
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(22_050, None).unwrap();
+
+    let mut stream = SourceStream::new(&ctx, Ramp{pos: 0, len: 22_050 * 3}, 1024, 4).unwrap();
+    let _ = stream.source_mut().set_soft_direct_channels(true);
+    stream.play();
+
+    stream.seek(Duration::from_millis(1500)).unwrap();
+    assert_eq!(stream.position(), Duration::from_millis(1500));
+    assert_eq!(stream.source().state(), alto::SourceState::Playing);
+
+    let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 256];
+    dev.soft_render_samples(&mut out[..]);
+    // The ramp wraps every 30_000 frames.
+    let expected = ((33_075 + 128) % 30_000) as f32 / 32_768.0;
+    assert!((out[128].left - expected).abs() < 1e-3, "{} != {}", out[128].left, expected);
+
+    // Past the end clamps and ends the stream.
+    stream.seek(Duration::from_secs(10)).unwrap();
+    assert_eq!(stream.position(), Duration::from_secs(3));
+    assert!(stream.is_ended());
+
+    // Every buffer is still around to refill after seeking back.
+    stream.seek(Duration::from_secs(0)).unwrap();
+    assert!(!stream.is_ended());
+    assert_eq!(stream.source().buffers_queued(), 4);
+    stream.play();
+    dev.soft_render_samples(&mut out[..]);
+    assert!((out[128].left - 128.0 / 32_768.0).abs() < 1e-3, "{}", out[128].left);
+
+    // A paused stream stays silent across a seek, and resumes on the next play.
+    stream.pause();
+    stream.seek(Duration::from_millis(500)).unwrap();
+    assert!(stream.is_paused());
+    stream.update().unwrap();
+    assert!(stream.source().state() != alto::SourceState::Playing);
+    stream.play();
+    assert_eq!(stream.source().state(), alto::SourceState::Playing);
+    dev.soft_render_samples(&mut out[..]);
+    let expected = (11_025 + 128) as f32 / 32_768.0;
+    assert!((out[128].left - expected).abs() < 1e-3, "{} != {}", out[128].left, expected);
+}
+
+#[test]
+fn streaming_progress_total() {
+    use std::time::Duration;
+    use alto::Source;
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+
+    // Three buffers of 10ms each.
+    let data: Vec<_> = (0 .. 441).map(|_| Mono{center: 0i16}).collect();
+    let mut src = ctx.new_streaming_source().unwrap();
+    for _ in 0 .. 3 {
+        src.queue_buffer(ctx.new_buffer(&data, 44_100).unwrap()).unwrap();
+    }
+    assert_eq!(src.progress_total().unwrap(), Duration::from_secs(0));
+
+    src.play();
+    let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 441 + 220];
+    dev.soft_render_samples(&mut out[..]);
+
+    // One buffer retired through upkeep, the other still playing.
+    ctx.process_streams().unwrap();
+    let total = src.progress_total().unwrap();
+    assert!(src.progress().unwrap() < Duration::from_millis(10));
+    assert!(total >= Duration::from_millis(10) && total < Duration::from_millis(20), "{:?}", total);
+
+    // Retiring the second buffer directly counts it once.
+    dev.soft_render_samples(&mut out[.. 441]);
+    src.unqueue_buffer().unwrap();
+    src.unqueue_buffer().unwrap();
+    let total = src.progress_total().unwrap();
+    assert!(total >= Duration::from_millis(20) && total < Duration::from_millis(30), "{:?}", total);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn replay_log_round_trip() {
+    use alto::replay::{self, Call, Entry, NullBackend};
+
+    let entries = vec![
+        Entry{call: Call::GenBuffer{buf: 0}, error: None},
+        Entry{call: Call::BufferData{buf: 0, format: alto::sys::AL_FORMAT_MONO16, freq: 44_100, size: 4, hash: replay::payload_hash(&[1, 2, 3, 4]), data: Some(vec![1, 2, 3, 4])}, error: Some(0)},
+        Entry{call: Call::GenSource{src: 0}, error: None},
+        Entry{call: Call::SourceBuffer{src: 0, buf: Some(0)}, error: Some(0)},
+        Entry{call: Call::Sourcefv{src: 0, param: alto::sys::AL_POSITION, values: vec![1.0, 2.0, 3.0]}, error: Some(0)},
+        Entry{call: Call::SourceQueueBuffers{src: 0, bufs: vec![0]}, error: Some(alto::sys::AL_INVALID_OPERATION)},
+        Entry{call: Call::SourcePlayAtTime{src: 0, time: -1_000_000}, error: Some(0)},
+        Entry{call: Call::BufferSamples{buf: 0, freq: 44_100, internal_format: 0x10011, frames: 1, channels: 0x1500, ty: 0x1408, size: 3, hash: replay::payload_hash(&[5, 6, 7]), data: None}, error: Some(0)},
+        Entry{call: Call::BufferMode{buf: 0, value: 0x202}, error: None},
+        Entry{call: Call::SourceBuffer{src: 0, buf: None}, error: None},
+        Entry{call: Call::DeleteSource{src: 0}, error: None},
+    ];
+
+    let path = std::env::temp_dir().join("alto-replay-round-trip.bin");
+    replay::write(&path, &entries).unwrap();
+    assert_eq!(replay::read(&path).unwrap(), entries);
+
+    let mut null = NullBackend::new();
+    assert!(replay::run(&path, &mut null).unwrap().is_empty());
+    assert_eq!(null.calls(), &entries.iter().map(|e| e.call.clone()).collect::<Vec<_>>()[..]);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn replay_recorded_scenario() {
+    use alto::Source;
+    use alto::replay::{self, Call, LiveBackend, NullBackend};
+
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    let path = std::env::temp_dir().join("alto-replay-scenario.bin");
+
+    ctx.start_recording(&path, false).unwrap();
+    {
+        let data: Vec<_> = (0 .. 64).map(|i| Mono{center: i as i16}).collect();
+        let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+        let mut src = ctx.new_static_source().unwrap();
+        src.set_buffer(buf).unwrap();
+        src.set_gain(0.5).unwrap();
+        src.play();
+        src.stop();
+    }
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    assert_eq!(calls[0], Call::GenBuffer{buf: 0});
+    match calls[1] {
+        Call::BufferData{buf: 0, size: 128, data: None, ..} => (),
+        ref c => panic!("{:?}", c),
+    }
+    assert_eq!(calls[2], Call::GenSource{src: 0});
+    assert_eq!(calls[3], Call::SourceBuffer{src: 0, buf: Some(0)});
+    assert_eq!(calls[4], Call::Sourcef{src: 0, param: alto::sys::AL_GAIN, value: 0.5});
+    assert_eq!(calls[5], Call::SourcePlay{src: 0});
+    assert_eq!(calls[6], Call::SourceStop{src: 0});
+    assert!(calls.contains(&Call::DeleteSource{src: 0}));
+    assert!(calls.contains(&Call::DeleteBuffer{buf: 0}));
+
+    let mut null = NullBackend::new();
+    replay::run(&path, &mut null).unwrap();
+    assert_eq!(null.calls(), &calls[..]);
+
+    let ctx2 = dev.new_context(44_100, None).unwrap();
+    assert!(replay::run(&path, &mut LiveBackend::new(&ctx2)).unwrap().is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn annotate_capture_specifiers() {
+    let a = load_alto();
+    let annotated = a.enumerate_captures_annotated().unwrap();
+    assert_eq!(annotated.iter().map(|&(ref n, _)| n.clone()).collect::<Vec<_>>(), a.enumerate_captures_lossy().unwrap());
+    assert!(annotated.iter().filter(|&&(_, d)| d).count() <= 1);
+}
+
+#[test]
+fn enumeration_fallback() {
+    let a = load_alto();
+
+    if !a.enumeration_supported() {
+        assert_eq!(a.enumerate_outputs(), a.default_output().into_iter().collect::<Vec<_>>());
+        assert_eq!(a.enumerate_captures(), a.default_capture().into_iter().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn packed_24bit_frame_size() {
+    use alto::{Format, Int24Sample, SampleFrame, SoftByte3Format, StandardFormat, ExtIma4Format};
+
+    assert_eq!(Format::SoftByte3(SoftByte3Format::Mono24).frame_bytes(), Some(3));
+    assert_eq!(Format::SoftByte3(SoftByte3Format::Stereo24).frame_bytes(), Some(6));
+    assert_eq!(Format::Standard(StandardFormat::StereoI16).frame_bytes(), Some(4));
+    assert_eq!(Format::ExtIma4(ExtIma4Format::Mono).frame_bytes(), None);
+
+    // There is no alBufferData() token for packed 24-bit data.
+    match Format::SoftByte3(SoftByte3Format::Mono24).into_raw(None) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+
+    assert_eq!(std::mem::size_of::<Mono<Int24Sample>>(), 3);
+    assert_eq!(std::mem::size_of::<Stereo<Int24Sample>>(), 6);
+    assert_eq!(Some(std::mem::size_of::<Stereo<Int24Sample>>()), <Stereo<Int24Sample> as SampleFrame>::format().frame_bytes());
+
+    assert_eq!(Int24Sample::new(-2).value(), -2);
+    assert_eq!(Int24Sample::new(0x7f_ffff).value(), 0x7f_ffff);
+    assert_eq!(Int24Sample::new(0x12_3456).0, [0x56, 0x34, 0x12]);
+}
+
+#[test]
+fn packed_24bit_buffer() {
+    use alto::Int24Sample;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let data: Vec<_> = (0 .. 100).map(|i| Stereo{left: Int24Sample::new(i), right: Int24Sample::new(-i)}).collect();
+    match ctx.new_buffer(&data, 44_100) {
+        Ok(buf) => {
+            assert_eq!(buf.channels(), 2);
+            assert_eq!(ctx.stats().buffered_bytes, 600);
+        },
+        Err(AltoError::ExtensionNotPresent) => assert!(!ctx.is_extension_present(alto::ext::Al::SoftBufferSamples)),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn aux_send_bookkeeping() {
+    use alto::Source;
+    use alto::efx::LowpassFilter;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    if !dev.is_extension_present(alto::ext::Alc::Efx) || dev.max_aux_sends() < 2 {
+        return;
+    }
+    let ctx = dev.new_context(None).unwrap();
+
+    let mut src = ctx.new_static_source().unwrap();
+    let mut slot = ctx.new_aux_effect_slot().unwrap();
+    let filter = ctx.new_filter::<LowpassFilter>().unwrap();
+    assert!(src.aux_send(0).is_none());
+    assert_eq!(src.aux_sends().count(), 0);
+
+    src.set_aux_send(0, &mut slot).unwrap();
+    src.set_aux_send_filter(1, &mut slot, &filter).unwrap();
+    let info = src.aux_send(0).unwrap();
+    assert_eq!(info.slot(), slot.as_raw());
+    assert!(info.is_slot_alive());
+    assert_eq!(info.filter(), None);
+    let info = src.aux_send(1).unwrap();
+    assert_eq!(info.filter(), Some(alto::efx::Filter::as_raw(&filter)));
+    assert!(info.is_filter_alive());
+    assert_eq!(slot.attached_sources(), vec![(src.as_raw(), 0), (src.as_raw(), 1)]);
+
+    drop(filter);
+    assert!(!src.aux_send(1).unwrap().is_filter_alive());
+
+    src.clear_aux_send(0);
+    assert!(src.aux_send(0).is_none());
+    assert_eq!(slot.attached_sources(), vec![(src.as_raw(), 1)]);
+
+    let stale = src.aux_send(1).unwrap();
+    drop(slot);
+    assert!(!stale.is_slot_alive());
+    assert!(src.aux_send(1).is_none());
+    assert_eq!(src.aux_sends().count(), 0);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn default_resampler_applied() {
+    use alto::replay::{self, Call};
+
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    if !ctx.is_extension_present(alto::ext::Al::SoftSourceResampler) {
+        match ctx.set_default_resampler(0) {
+            Err(AltoError::ExtensionNotPresent) => (),
+            r => panic!("{:?}", r),
+        }
+        return;
+    }
+
+    let index = ctx.enumerate_soft_resamplers().len() as i32 - 1;
+    match ctx.set_default_resampler(index + 1) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+    assert_eq!(ctx.default_resampler(), None);
+    ctx.set_default_resampler(index).unwrap();
+    assert_eq!(ctx.default_resampler(), Some(index));
+
+    let path = std::env::temp_dir().join("alto-default-resampler.bin");
+    ctx.start_recording(&path, false).unwrap();
+    {
+        let _static_src = ctx.new_static_source().unwrap();
+        let _stream_src = ctx.new_streaming_source().unwrap();
+    }
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    assert_eq!(calls[0], Call::GenSource{src: 0});
+    match calls[1] {
+        Call::Sourceiv{src: 0, ref values, ..} => assert_eq!(values, &[index]),
+        ref c => panic!("{:?}", c),
+    }
+    assert_eq!(calls[2], Call::GenSource{src: 1});
+    match calls[3] {
+        Call::Sourceiv{src: 1, ref values, ..} => assert_eq!(values, &[index]),
+        ref c => panic!("{:?}", c),
+    }
+}
+
+#[test]
+fn tpdf_dither_statistics() {
+    use alto::conv::*;
+
+    let ramp: Vec<f32> = (0 .. 20_000).map(|i| (i as f32 * 0.37 - 3_700.0) / 32_768.0).collect();
+    let errors = |opts: &ConvertOptions| -> Vec<f64> {
+        f32_to_i16(&ramp, opts).iter().zip(&ramp).map(|(&q, &v)| q as f64 - v as f64 * 32_768.0).collect()
+    };
+
+    let plain = errors(&ConvertOptions::default());
+    assert!(plain.iter().all(|e| e.abs() <= 0.5 + 1e-3));
+
+    let dithered = errors(&ConvertOptions{dither: DitherMode::Tpdf, seed: 7});
+    let mean = dithered.iter().sum::<f64>() / dithered.len() as f64;
+    assert!(mean.abs() < 0.02, "{}", mean);
+    assert!(dithered.iter().all(|e| e.abs() <= 1.5 + 1e-3));
+    assert!(dithered.iter().any(|e| e.abs() > 0.5 + 1e-3));
+}
+
+#[test]
+fn tpdf_dither_fixed_seed() {
+    use alto::conv::*;
+
+    let opts = ConvertOptions{dither: DitherMode::Tpdf, seed: 42};
+    let floats = [0.1, 0.1, 0.1, 0.1, -0.3, -0.3, 0.7, 0.7];
+    assert_eq!(f32_to_i16(&floats, &opts), vec![3276, 3277, 3277, 3276, -9830, -9831, 22938, 22937]);
+    assert_eq!(f32_to_i16_iter(floats.iter().cloned(), &opts).collect::<Vec<_>>(), f32_to_i16(&floats, &opts));
+
+    let ints = [100_000, 100_000, 100_000, 100_000, -5, -5, 123_456_789, 123_456_789];
+    assert_eq!(i32_to_i16(&ints, &opts), vec![1, 2, 2, 1, 0, 0, 1884, 1883]);
+    assert_eq!(i32_to_i16(&[i32::MIN, i32::MAX], &ConvertOptions::default()), vec![i16::MIN, i16::MAX]);
+}
+
+struct FloatRamp(u64);
+
+impl alto::SampleSource<Stereo<f32>> for FloatRamp {
+    fn frequency(&self) -> i32 { 48_000 }
+
+    fn read(&mut self, out: &mut Vec<Stereo<f32>>, max: usize) -> alto::AltoResult<usize> {
+        let n = std::cmp::min(max as u64, 1_000 - self.0);
+        for i in self.0 .. self.0 + n {
+            out.push(Stereo{left: i as f32 / 1_000.0, right: -(i as f32) / 1_000.0});
+        }
+        self.0 += n;
+        Ok(n as usize)
+    }
+
+    fn seek(&mut self, frame: u64) -> alto::AltoResult<u64> {
+        self.0 = std::cmp::min(frame, 1_000);
+        Ok(self.0)
+    }
+}
+
+#[test]
+fn convert_source_passes_options() {
+    use alto::SampleSource;
+    use alto::conv::*;
+
+    let opts = ConvertOptions{dither: DitherMode::Tpdf, seed: 3};
+    let mut whole = Vec::new();
+    let mut src = ConvertSource::new(FloatRamp(0), &opts);
+    while src.read(&mut whole, 1_000).unwrap() > 0 {}
+
+    let mut chunked = Vec::new();
+    let mut src = ConvertSource::new(FloatRamp(0), &opts);
+    while src.read(&mut chunked, 37).unwrap() > 0 {}
+
+    assert_eq!(whole.len(), 1_000);
+    assert!(whole == chunked);
+    let interleaved: Vec<f32> = (0 .. 1_000).flat_map(|i| vec![i as f32 / 1_000.0, -(i as f32) / 1_000.0]).collect();
+    let expected = f32_to_i16(&interleaved, &opts);
+    assert!(whole.iter().flat_map(|f| vec![f.left, f.right]).eq(expected));
+}
+
+#[test]
+fn capture_buffer_minimum() {
+    assert_eq!(
+        AltoError::CaptureBufferTooSmall{requested: 100, minimum: 441}.to_string(),
+        "ALTO ERROR: Capture buffer too small (requested 100 frames, minimum 441)",
+    );
+
+    let a = load_alto();
+    match a.open_capture::<Stereo<i16>>(None, 44_100, 440) {
+        Err(AltoError::CaptureBufferTooSmall{requested: 440, minimum: 441}) => (),
+        r => panic!("{:?}", r.map(|_| ())),
+    }
+    if let Err(AltoError::CaptureBufferTooSmall{..}) = a.open_capture::<Stereo<i16>>(None, 44_100, 441) {
+        panic!();
+    }
+}
+
+#[test]
+fn probe_max_sources_cleans_up() {
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let mut ctx = dev.new_context(44_100, None).unwrap();
+    let _src = ctx.new_static_source().unwrap();
+    let baseline = ctx.stats();
+
+    let probe = ctx.probe_max_sources(12).unwrap();
+    assert_eq!(probe.max_sources, 12);
+    assert!(probe.max_playing.unwrap() <= 12);
+    assert_eq!(ctx.stats(), baseline);
+
+    assert_eq!(ctx.probe_max_sources(0).unwrap().max_sources, 0);
+    let srcs: Vec<_> = (0 .. 12).map(|_| ctx.new_static_source().unwrap()).collect();
+    assert_eq!(ctx.stats().sources, baseline.sources + srcs.len());
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn probe_max_sources_recorded() {
+    use alto::replay::{self, Call};
+
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let mut ctx = dev.new_context(44_100, None).unwrap();
+    let path = std::env::temp_dir().join("alto-replay-probe.bin");
+
+    ctx.start_recording(&path, false).unwrap();
+    ctx.probe_max_sources(4).unwrap();
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    for src in 0 .. 4 {
+        assert!(calls.contains(&Call::GenSource{src}), "{:?}", calls);
+        assert!(calls.contains(&Call::SourceBuffer{src, buf: Some(0)}), "{:?}", calls);
+        assert!(calls.contains(&Call::SourcePlay{src}), "{:?}", calls);
+        assert!(calls.contains(&Call::DeleteSource{src}), "{:?}", calls);
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn buffer_metadata_cached() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let data: Vec<_> = (0 .. 441).map(|i| Mono{center: i as i16}).collect();
+    let mut buf = ctx.new_buffer(&data, 44_100).unwrap();
+    assert_eq!((buf.frequency(), buf.channels(), buf.bits(), buf.size()), (44_100, 1, 16, 882));
+
+    let data: Vec<_> = (0 .. 100).map(|i| Stereo{left: i as u8, right: i as u8}).collect();
+    buf.set_data(&data, 22_050).unwrap();
+    assert_eq!((buf.frequency(), buf.channels(), buf.bits(), buf.size()), (22_050, 2, 8, 200));
+}
+
+#[test]
+fn source_2d_mode() {
+    use alto::Source;
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+
+    let data: Vec<_> = (0 .. 4410).map(|_| Stereo{left: 16_000i16, right: 0}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.set_position([3.0, 1.0, -2.0]).unwrap();
+    src.set_rolloff_factor(2.0).unwrap();
+
+    src.set_2d(true).unwrap();
+    assert!(src.is_2d());
+    assert!(src.relative());
+    assert_eq!(src.position::<[f32; 3]>(), [0.0, 0.0, 0.0]);
+    assert_eq!(src.rolloff_factor(), 0.0);
+
+    src.set_looping(true);
+    src.play();
+    let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 1024];
+    for &(at, up) in &[([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]), ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]), ([1.0, 0.0, 0.0], [0.0, -1.0, 0.0])] {
+        ctx.set_orientation::<[f32; 3]>((at, up)).unwrap();
+        dev.soft_render_samples(&mut out[..]);
+        let tail = &out[512 ..];
+        assert!(tail.iter().all(|f| f.left > 0.1), "{:?}", tail[0]);
+        assert!(tail.iter().all(|f| f.right.abs() < 1e-3), "{:?}", tail[0]);
+    }
+
+    src.set_2d(false).unwrap();
+    assert!(!src.is_2d());
+    assert!(!src.relative());
+    assert_eq!(src.position::<[f32; 3]>(), [3.0, 1.0, -2.0]);
+    assert_eq!(src.rolloff_factor(), 2.0);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn shutdown_deletes_tracked_names_once() {
+    use alto::Source;
+    use alto::replay::{self, Call};
+
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    let path = std::env::temp_dir().join("alto-shutdown-deletes.bin");
+
+    ctx.start_recording(&path, false).unwrap();
+    let data: Vec<_> = (0 .. 64).map(|i| Mono{center: i as i16}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+    let spare = ctx.new_buffer(&data, 44_100).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf.clone()).unwrap();
+    let idle = ctx.new_streaming_source().unwrap();
+    drop(dev);
+
+    let report = a.clone().shutdown();
+    assert_eq!((report.sources, report.buffers), (2, 2));
+    drop((src, idle, buf, spare));
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    for id in 0 .. 2 {
+        assert_eq!(calls.iter().filter(|&c| *c == Call::DeleteSource{src: id}).count(), 1);
+        assert_eq!(calls.iter().filter(|&c| *c == Call::DeleteBuffer{buf: id}).count(), 1);
+    }
+}
+
+#[test]
+fn pause_all_restores_mixed_states() {
+    use alto::{Source, SourceState};
+
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let mut ctx = dev.new_context(44_100, None).unwrap();
+
+    let data: Vec<_> = (0 .. 441).map(|_| Mono{center: 0i16}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+    let mut srcs: Vec<_> = (0 .. 3).map(|_| {
+        let mut src = ctx.new_static_source().unwrap();
+        src.set_buffer(buf.clone()).unwrap();
+        src.set_looping(true);
+        src
+    }).collect();
+    srcs[0].play();
+    srcs[1].play();
+    srcs[1].pause();
+
+    let outer = ctx.pause_all();
+    let states: Vec<_> = srcs.iter().map(|s| s.state()).collect();
+    assert_eq!(states, [SourceState::Paused, SourceState::Paused, SourceState::Initial]);
+
+    // A nested token finds nothing playing and so resumes nothing.
+    let inner = ctx.pause_all();
+    inner.resume();
+    assert_eq!(srcs[0].state(), SourceState::Paused);
+
+    outer.resume();
+    let states: Vec<_> = srcs.iter().map(|s| s.state()).collect();
+    assert_eq!(states, [SourceState::Playing, SourceState::Paused, SourceState::Initial]);
+
+    // Out of order, the sources stay paused until the last token is gone.
+    let outer = ctx.pause_all();
+    let inner = ctx.pause_all();
+    outer.resume();
+    assert_eq!(srcs[0].state(), SourceState::Paused);
+    inner.resume();
+    assert_eq!(srcs[0].state(), SourceState::Playing);
+}
+
+#[test]
+fn pause_all_defers_new_sources() {
+    use alto::{Source, SourceState};
+
+    let a = load_alto();
+    let dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let mut ctx = dev.new_context(44_100, None).unwrap();
+    let data: Vec<_> = (0 .. 441).map(|_| Mono{center: 0i16}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+
+    let outer = ctx.pause_all();
+    let inner = ctx.pause_all();
+    let mut born = ctx.new_static_source().unwrap();
+    born.set_buffer(buf.clone()).unwrap();
+    born.set_looping(true);
+    born.play();
+    let mut cancelled = ctx.new_static_source().unwrap();
+    cancelled.set_buffer(buf).unwrap();
+    cancelled.play();
+    cancelled.stop();
+    assert_eq!(born.state(), SourceState::Initial);
+
+    drop(inner);
+    assert_eq!(born.state(), SourceState::Initial);
+    drop(outer);
+    assert_eq!(born.state(), SourceState::Playing);
+    assert_eq!(cancelled.state(), SourceState::Stopped);
+}
+
+#[test]
+fn loopback_format_names() {
+    use alto::{LoopbackFormatChannels, LoopbackFormatType};
+
+    for &c in &[
+        LoopbackFormatChannels::Mono, LoopbackFormatChannels::Stereo, LoopbackFormatChannels::Quad,
+        LoopbackFormatChannels::Mc51, LoopbackFormatChannels::Mc61, LoopbackFormatChannels::Mc71,
+    ] {
+        assert_eq!(c.to_string().parse::<LoopbackFormatChannels>(), Ok(c));
+    }
+    for &t in &[LoopbackFormatType::U8, LoopbackFormatType::I16, LoopbackFormatType::F32] {
+        assert_eq!(t.to_string().parse::<LoopbackFormatType>(), Ok(t));
+    }
+    assert_eq!(LoopbackFormatChannels::Mc51.to_string(), "5.1");
+    assert_eq!("Stereo".parse::<LoopbackFormatChannels>(), Ok(LoopbackFormatChannels::Stereo));
+    assert_eq!(LoopbackFormatType::F32.to_string(), "float32");
+
+    let err = "5.2".parse::<LoopbackFormatChannels>().unwrap_err();
+    assert_eq!(err.to_string(), "ALTO ERROR: Unknown loopback channel layout \"5.2\"");
+    let err = "int24".parse::<LoopbackFormatType>().unwrap_err();
+    assert_eq!(err.to_string(), "ALTO ERROR: Unknown loopback sample type \"int24\"");
+}
+
+#[test]
+fn attenuation_distance_models() {
+    use alto::DistanceModel::*;
+    use alto::distance_gain;
+
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+    let cases = [
+        (Inverse, 4.0, 1.0, 0.25), (Inverse, 0.5, 1.0, 2.0), (Inverse, 20.0, 1.0, 0.05),
+        (InverseClamped, 0.5, 1.0, 1.0), (InverseClamped, 4.0, 1.0, 0.25), (InverseClamped, 20.0, 1.0, 0.1),
+        (Linear, 4.0, 1.0, 2.0 / 3.0), (Linear, 0.5, 1.0, 1.0 + 0.5 / 9.0), (Linear, 20.0, 1.0, 0.0),
+        (LinearClamped, 0.5, 1.0, 1.0), (LinearClamped, 4.0, 1.0, 2.0 / 3.0), (LinearClamped, 20.0, 1.0, 0.0),
+        (Exponent, 4.0, 2.0, 0.0625), (Exponent, 0.5, 2.0, 4.0), (Exponent, 20.0, 2.0, 0.0025),
+        (ExponentClamped, 0.5, 2.0, 1.0), (ExponentClamped, 4.0, 2.0, 0.0625), (ExponentClamped, 20.0, 2.0, 0.01),
+        (None, 20.0, 1.0, 1.0),
+    ];
+    for &(model, distance, rolloff, expected) in &cases {
+        let gain = distance_gain(model, distance, 1.0, rolloff, 10.0);
+        assert!(close(gain, expected), "{:?} at {}: {} != {}", model, distance, gain, expected);
+    }
+
+    // Degenerate parameters leave the gain alone.
+    assert_eq!(distance_gain(InverseClamped, 4.0, 2.0, 1.0, 1.0), 1.0);
+    assert_eq!(distance_gain(Linear, 4.0, 2.0, 1.0, 2.0), 1.0);
+    assert_eq!(distance_gain(Exponent, 4.0, 0.0, 1.0, 10.0), 1.0);
+    assert_eq!(distance_gain(Inverse, 4.0, 1.0, 0.0, 10.0), 1.0);
+}
+
+#[test]
+fn attenuation_cone_clamp_relative() {
+    use alto::{attenuation, cone_gain, DistanceModel, SourceProps};
+
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+    let cone = SourceProps{
+        direction: [0.0, 0.0, -1.0],
+        cone_inner_angle: 90.0,
+        cone_outer_angle: 180.0,
+        cone_outer_gain: 0.25,
+        ..SourceProps::default()
+    };
+    let a = 67.5f32.to_radians();
+    assert!(close(cone_gain(&cone, [0.0, 0.0, -1.0]), 1.0));
+    assert!(close(cone_gain(&cone, [1.0, 0.0, -1.0]), 1.0));
+    assert!(close(cone_gain(&cone, [a.sin(), 0.0, -a.cos()]), 0.625));
+    assert!(close(cone_gain(&cone, [1.0, 0.0, 0.0]), 0.25));
+    assert!(close(cone_gain(&cone, [0.0, 0.0, 1.0]), 0.25));
+    assert!(close(cone_gain(&SourceProps::default(), [1.0, 0.0, 0.0]), 1.0));
+
+    // Distance and cone combine, scaled by the source gain.
+    let far = SourceProps{position: [0.0, 0.0, 4.0], gain: 0.8, ..cone};
+    assert!(close(attenuation(DistanceModel::InverseClamped, &far, [0.0, 0.0, 0.0]), 0.8 * 0.25));
+    let behind = SourceProps{position: [0.0, 0.0, -4.0], ..far};
+    assert!(close(attenuation(DistanceModel::InverseClamped, &behind, [0.0, 0.0, 0.0]), 0.8 * 0.25 * 0.25));
+
+    // Min and max gain are applied last.
+    let quiet = SourceProps{position: [20.0, 0.0, 0.0], min_gain: 0.1, ..SourceProps::default()};
+    assert!(close(attenuation(DistanceModel::Inverse, &quiet, [0.0, 0.0, 0.0]), 0.1));
+    let loud = SourceProps{position: [0.5, 0.0, 0.0], ..SourceProps::default()};
+    assert!(close(attenuation(DistanceModel::Inverse, &loud, [0.0, 0.0, 0.0]), 1.0));
+
+    // Relative sources ignore the listener position, and a per-source model wins.
+    let rel = SourceProps{position: [0.0, 0.0, -4.0], relative: true, ..SourceProps::default()};
+    assert!(close(attenuation(DistanceModel::Inverse, &rel, [100.0, 0.0, 0.0]), 0.25));
+    let own = SourceProps{distance_model: Some(DistanceModel::None), ..rel};
+    assert!(close(attenuation(DistanceModel::Inverse, &own, [100.0, 0.0, 0.0]), 1.0));
+}
+
+#[test]
+fn attenuation_matches_mixer() {
+    use alto::{DistanceModel, Source, SourceProps};
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    let data: Vec<_> = (0 .. 4410).map(|i| Mono{center: ((i as f32 * 0.0628).sin() * 16_000.0) as i16}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+
+    let mut measure = |props: &SourceProps| -> f32 {
+        let mut src = ctx.new_static_source().unwrap();
+        src.set_buffer(buf.clone()).unwrap();
+        src.set_looping(true);
+        src.set_position(props.position).unwrap();
+        src.set_gain(props.gain).unwrap();
+        src.set_max_distance(props.max_distance).unwrap();
+        src.play();
+        let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 1024 + 4410];
+        dev.soft_render_samples(&mut out[..]);
+        src.stop();
+        (out[1024 ..].iter().map(|f| f.left * f.left).sum::<f32>() / 4410.0).sqrt()
+    };
+
+    let base_props = SourceProps{position: [0.0, 0.0, -1.0], ..SourceProps::default()};
+    let base = measure(&base_props);
+    assert!(base > 0.01);
+    for &(model, position, gain, max_distance) in &[
+        (DistanceModel::InverseClamped, [0.0, 0.0, -4.0], 1.0, 1000.0),
+        (DistanceModel::InverseClamped, [0.0, 0.0, -2.0], 0.5, 1000.0),
+        (DistanceModel::LinearClamped, [0.0, 0.0, -4.0], 1.0, 10.0),
+        (DistanceModel::ExponentClamped, [0.0, 0.0, -3.0], 1.0, 1000.0),
+    ] {
+        ctx.set_distance_model(model);
+        let props = SourceProps{position, gain, max_distance, ..SourceProps::default()};
+        let predicted = ctx.compute_attenuation(&props, [0.0, 0.0, 0.0]) / ctx.compute_attenuation(&base_props, [0.0, 0.0, 0.0]);
+        let measured = measure(&props) / base;
+        assert!((measured - predicted).abs() < 0.05 * predicted.max(0.1), "{:?}: {} != {}", model, measured, predicted);
+    }
+}
+
+#[test]
+fn device_min_latency() {
+    use std::time::Duration;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let _ctx = dev.new_context(None).unwrap();
+    let latency = dev.min_latency().unwrap();
+    assert!(latency > Duration::from_millis(0) && latency < Duration::from_secs(1));
+}
+
+#[test]
+fn occlusion_curves() {
+    use alto::Occlusion;
+
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+    assert_eq!(Occlusion::occluded(0.0).unwrap(), Occlusion::NONE);
+    assert_eq!(Occlusion::obstructed(0.0).unwrap(), Occlusion::NONE);
+    assert_eq!(Occlusion::excluded(0.0).unwrap(), Occlusion::NONE);
+
+    let o = Occlusion::occluded(0.5).unwrap();
+    assert!(close(o.direct_lf, 0.1) && close(o.direct_gain, 0.75) && close(o.send_lf, 1.0) && close(o.send_gain, 0.75));
+    let o = Occlusion::occluded(1.0).unwrap();
+    assert!(close(o.direct_lf, 0.01) && close(o.direct_gain, 0.5) && close(o.send_lf, 1.0) && close(o.send_gain, 0.5));
+    assert!(close(o.fallback_gain(), 0.5 * 1.01 / 2.0));
+
+    let o = Occlusion::obstructed(0.5).unwrap();
+    assert!(close(o.direct_lf, 0.1) && close(o.direct_gain, 0.875) && close(o.send_lf, 1.0) && close(o.send_gain, 1.0));
+    let o = Occlusion::excluded(1.0).unwrap();
+    assert!(close(o.direct_lf, 1.0) && close(o.direct_gain, 1.0) && close(o.send_lf, 0.01) && close(o.send_gain, 0.75));
+
+    for &amount in &[-0.1, 1.1, std::f32::NAN] {
+        assert!(Occlusion::occluded(amount).is_err());
+        assert!(Occlusion::obstructed(amount).is_err());
+        assert!(Occlusion::excluded(amount).is_err());
+    }
+}
+
+#[test]
+fn occlusion_filters_high_band() {
+    use alto::Source;
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    if !dev.is_extension_present(alto::ext::Alc::Efx) {
+        return;
+    }
+
+    let mut seed = 1u32;
+    let noise: Vec<_> = (0 .. 8820).map(|_| {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        Mono{center: ((seed >> 16) as i16) / 4}
+    }).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&noise, 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.set_looping(true);
+    src.set_relative(true);
+
+    // Split the rendered left channel into bands with an 8-tap box filter and its complement.
+    let mut bands = |src: &mut alto::StaticSource| -> (f32, f32) {
+        src.play();
+        let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 1024 + 8192];
+        dev.soft_render_samples(&mut out[..]);
+        src.stop();
+        let x: Vec<f32> = out[1024 ..].iter().map(|f| f.left).collect();
+        let (mut lf, mut hf) = (0.0, 0.0);
+        for w in x.windows(8) {
+            let low = w.iter().sum::<f32>() / 8.0;
+            lf += low * low;
+            hf += (w[7] - low) * (w[7] - low);
+        }
+        (lf, hf)
+    };
+
+    let (lf0, hf0) = bands(&mut src);
+    let (mut last_lf, mut last_hf) = (1.0, 1.0);
+    for &amount in &[0.5, 1.0] {
+        src.occlude(amount).unwrap();
+        let (lf, hf) = bands(&mut src);
+        let (lf, hf) = (lf / lf0, hf / hf0);
+        assert!(hf < lf, "amount {}: hf {} lf {}", amount, hf, lf);
+        assert!(lf < last_lf && hf < last_hf);
+        last_lf = lf;
+        last_hf = hf;
+    }
+
+    src.clear_occlusion();
+    assert!(src.occlusion().is_none());
+    let (lf, hf) = bands(&mut src);
+    assert!((lf / lf0 - 1.0).abs() < 0.1 && (hf / hf0 - 1.0).abs() < 0.1);
+}
+
+#[test]
+fn streaming_queue_buffers() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let mono: Vec<_> = (0 .. 64).map(|_| Mono{center: 0i16}).collect();
+    let stereo: Vec<_> = (0 .. 64).map(|_| Stereo{left: 0i16, right: 0}).collect();
+    let mut src = ctx.new_streaming_source().unwrap();
+    src.queue_buffers(Vec::new()).unwrap();
+    assert_eq!(src.buffers_queued(), 0);
+
+    let bufs = (0 .. 3).map(|_| ctx.new_buffer(&mono, 44_100).unwrap()).collect();
+    src.queue_buffers(bufs).unwrap();
+    assert_eq!(src.buffers_queued(), 3);
+
+    // Mixed formats are rejected as a whole.
+    let bufs = vec![ctx.new_buffer(&mono, 44_100).unwrap(), ctx.new_buffer(&stereo, 44_100).unwrap()];
+    assert!(src.queue_buffers(bufs).is_err());
+    assert_eq!(src.buffers_queued(), 3);
+    for _ in 0 .. 3 {
+        src.unqueue_buffer().unwrap();
+    }
+    assert_eq!(src.buffers_queued(), 0);
+}
+
+#[test]
+fn auto_mixer_virtualization() {
+    use std::collections::HashSet;
+    use std::time::Duration;
+    use alto::{audibility, AutoMixer, Source, SourceProps};
+
+    assert_eq!(audibility(0.5, 2.0, Duration::from_secs(0)), 1.0);
+    assert_eq!(audibility(1.0, 1.0, Duration::from_secs(10)), 0.5);
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let ctx = dev.new_context(44_100, None).unwrap();
+    let data: Vec<_> = (0 .. 33_075).map(|_| Mono{center: 0i16}).collect();
+    let buf = std::sync::Arc::new(ctx.new_buffer(&data, 44_100).unwrap());
+    let len = 0.75;
+
+    // Sounds strung along the x axis, every seventh one more important.
+    let mut mixer = AutoMixer::new(&ctx, 16).unwrap();
+    let mut listener = [0.3, 0.0, 0.0];
+    ctx.set_position(listener).unwrap();
+    let sounds: Vec<_> = (0 .. 100).map(|i| {
+        let props = SourceProps{position: [i as f32, 0.0, 0.0], reference_distance: 0.1, ..SourceProps::default()};
+        let priority = if i % 7 == 0 { 3.0 } else { 1.0 };
+        (mixer.play(buf.clone(), props, priority, true).unwrap(), props, priority)
+    }).collect();
+    assert_eq!(mixer.len(), 100);
+
+    let expected = |listener: [f32; 3]| -> HashSet<_> {
+        let mut ranked: Vec<_> = sounds.iter()
+            .map(|&(id, ref props, priority)| (ctx.compute_attenuation(props, listener) * priority, id))
+            .collect();
+        ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        ranked.into_iter().take(16).map(|(_, id)| id).collect()
+    };
+    assert_eq!(mixer.voiced().into_iter().collect::<HashSet<_>>(), expected(listener));
+
+    let mut out = vec![Stereo{left: 0.0f32, right: 0.0}; 4410];
+    let mut time = 0.0f32;
+    let mut ever_voiced = HashSet::new();
+    let mut dropped = HashSet::new();
+    let mut revoiced = 0;
+    for &x in &[0.3, 20.3, 40.3, 60.3, 40.3, 20.3, 0.3] {
+        for _ in 0 .. 3 {
+            dev.soft_render_samples(&mut out[..]);
+            time += 0.1;
+            listener[0] = x;
+            ctx.set_position(listener).unwrap();
+            mixer.update(Duration::from_millis(100)).unwrap();
+
+            let voiced: HashSet<_> = mixer.voiced().into_iter().collect();
+            assert_eq!(voiced, expected(listener));
+            for &id in &voiced {
+                if dropped.remove(&id) {
+                    revoiced += 1;
+                }
+                // Voices resume where the sound would have been had it played all along.
+                let offset = mixer.source(id).unwrap().sec_offset();
+                let want = time % len;
+                let diff = (offset - want).abs();
+                assert!(diff.min(len - diff) < 0.01, "{:?}: offset {} != {}", id, offset, want);
+            }
+            dropped.extend(ever_voiced.difference(&voiced).cloned());
+            ever_voiced.extend(voiced);
+        }
+    }
+    assert!(revoiced > 0);
+    assert_eq!(mixer.len(), 100);
+}
+
+#[test]
+fn error_hints() {
+    let e = AltoError::InvalidOperation;
+    assert_eq!(e.hint(), Some("source may be playing, or buffer is attached"));
+    assert_eq!(e.to_string(), "ALTO ERROR: AL Invalid Operation (source may be playing, or buffer is attached)");
+    assert_eq!(AltoError::OutOfMemory.hint(), None);
+    assert_eq!(AltoError::OutOfMemory.to_string(), "ALTO ERROR: ALC Out of Memory");
+    assert_eq!(AltoError::ExtensionNotPresent.hint(), None);
+}
+
+#[test]
+fn stale_handles_rejected() {
+    use alto::{ObjectKind, RawHandle, Source};
+    use alto::efx::AuxEffectSlot;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let data: Vec<_> = (0 .. 64).map(|_| Mono{center: 0i16}).collect();
+
+    let mut stale: Vec<RawHandle> = Vec::new();
+    let mut reused = 0;
+    for _ in 0 .. 16 {
+        let buf = ctx.new_buffer(&data, 44_100).unwrap();
+        let src = ctx.new_static_source().unwrap();
+        let (bh, sh) = (buf.raw_handle(), src.raw_handle());
+        assert_eq!(bh.kind, ObjectKind::Buffer);
+        assert_eq!(sh.kind, ObjectKind::Source);
+        ctx.check_handle(bh).unwrap();
+        ctx.check_handle(sh).unwrap();
+
+        for &old in &stale {
+            if old == bh || old == sh {
+                panic!("generation not bumped for {:?}", old);
+            }
+            if (old.kind, old.name) == (bh.kind, bh.name) || (old.kind, old.name) == (sh.kind, sh.name) {
+                reused += 1;
+            }
+        }
+        stale.push(bh);
+        stale.push(sh);
+    }
+    assert!(reused > 0, "names were never reused");
+    for &h in &stale {
+        match ctx.check_handle(h) {
+            Err(AltoError::StaleHandle) => (),
+            r => panic!("{:?}: {:?}", h, r),
+        }
+    }
+
+    if !dev.is_extension_present(alto::ext::Alc::Efx) {
+        return;
+    }
+    let mut src = ctx.new_static_source().unwrap();
+    let info = {
+        let mut slot = ctx.new_aux_effect_slot().unwrap();
+        src.set_aux_send(0, &mut slot).unwrap();
+        src.aux_send(0).unwrap()
+    };
+    let slot: AuxEffectSlot = ctx.new_aux_effect_slot().unwrap();
+    assert_eq!(slot.as_raw(), info.slot());
+    assert!(ctx.check_handle(slot.raw_handle()).is_ok());
+    match ctx.check_handle(info.slot_handle()) {
+        Err(AltoError::StaleHandle) => (),
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn buffer_handles_shared_across_contexts() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx1 = dev.new_context(None).unwrap();
+    let ctx2 = dev.new_context(None).unwrap();
+    let data: Vec<_> = (0 .. 64).map(|_| Mono{center: 0i16}).collect();
+
+    let old = ctx1.new_buffer(&data, 44_100).unwrap().raw_handle();
+    let buf = ctx2.new_buffer(&data, 44_100).unwrap();
+    if buf.as_raw() != old.name {
+        return;
+    }
+    assert!(ctx1.check_handle(buf.raw_handle()).is_ok());
+    match ctx2.check_handle(old) {
+        Err(AltoError::StaleHandle) => (),
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn capture_raw_interop() {
+    let a = load_alto();
+    let mut cap = a.open_capture::<Stereo<i16>>(None, 44_100, 4410).unwrap();
+    assert!(cap.is_owned());
+    let raw = cap.as_raw();
+
+    // A borrowed handle leaves the device open when dropped.
+    let borrowed = unsafe { MyCapture::from_raw(&a, raw, false) }.unwrap();
+    assert!(!borrowed.is_owned());
+    assert_eq!(borrowed.as_raw(), raw);
+    assert!(borrowed.specifier().is_none());
+    drop(borrowed);
+    cap.start();
+    let _ = cap.samples_len();
+    cap.stop();
+
+    // Ownership can be handed out and taken back.
+    let raw = cap.into_raw();
+    let cap = unsafe { MyCapture::from_raw(&a, raw, true) }.unwrap();
+    assert!(cap.is_owned());
+    assert_eq!(a.clone().shutdown().captures, 1);
+    match unsafe { MyCapture::from_raw(&a, std::ptr::null_mut(), false) } {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r.map(|_| ())),
+    }
+    drop(cap);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn reset_to_initial_defaults() {
+    use alto::replay::{self, Call};
+    use alto::DistanceModel;
+    use alto::sys;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    ctx.set_distance_model(DistanceModel::Linear);
+    ctx.set_doppler_factor(2.0).unwrap();
+    ctx.set_speed_of_sound(100.0).unwrap();
+    ctx.set_position([1.0, 2.0, 3.0]).unwrap();
+    ctx.set_velocity([1.0, 0.0, 0.0]).unwrap();
+    ctx.set_gain(0.5).unwrap();
+    ctx.set_orientation(([1.0, 0.0, 0.0], [0.0, 0.0, 1.0])).unwrap();
+
+    let path = std::env::temp_dir().join("alto-reset-to-initial.bin");
+    ctx.start_recording(&path, false).unwrap();
+    ctx.reset_to_initial().unwrap();
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    assert_eq!(calls, vec![
+        Call::DistanceModel{value: sys::AL_INVERSE_DISTANCE_CLAMPED},
+        Call::DopplerFactor{value: 1.0},
+        Call::SpeedOfSound{value: 343.3},
+        Call::Listenerfv{param: sys::AL_POSITION, values: vec![0.0, 0.0, 0.0]},
+        Call::Listenerfv{param: sys::AL_VELOCITY, values: vec![0.0, 0.0, 0.0]},
+        Call::Listenerf{param: sys::AL_GAIN, value: 1.0},
+        Call::Listenerfv{param: sys::AL_ORIENTATION, values: vec![0.0, 0.0, -1.0, 0.0, 1.0, 0.0]},
+    ]);
+
+    assert_eq!(ctx.distance_model(), DistanceModel::InverseClamped);
+    assert_eq!(ctx.position::<[f32; 3]>(), [0.0, 0.0, 0.0]);
+    assert_eq!(ctx.orientation::<[f32; 3]>(), ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]));
+}
+
+#[test]
+fn buffer_get_samples_bounds() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let data: Vec<_> = (0 .. 64).map(|i| Mono{center: i as i16 * 100}).collect();
+    let buf = ctx.new_buffer(&data, 44_100).unwrap();
+    let mut out = vec![Mono{center: 0i16}; 8];
+
+    if !ctx.is_extension_present(alto::ext::Al::SoftBufferSamples) {
+        match buf.get_samples(0, &mut out[..]) {
+            Err(AltoError::ExtensionNotPresent) => (),
+            r => panic!("{:?}", r),
+        }
+        return;
+    }
+
+    buf.get_samples(56, &mut out[..]).unwrap();
+    assert_eq!(out, &data[56 ..]);
+    buf.get_samples(64, &mut out[.. 0]).unwrap();
+    for &offset in &[57, 64, std::usize::MAX] {
+        match buf.get_samples(offset, &mut out[..]) {
+            Err(AltoError::InvalidValue) => (),
+            r => panic!("{}: {:?}", offset, r),
+        }
+    }
+    let mut too_long = vec![Mono{center: 0i16}; 65];
+    assert!(buf.get_samples(0, &mut too_long[..]).is_err());
+}
+
+#[test]
+fn loopback_render_callback_chunks() {
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let _ctx = dev.new_context(44_100, None).unwrap();
+
+    let mut sizes = Vec::new();
+    dev.render_callback(1000, 300, |bytes| { sizes.push(bytes.len()); Ok(()) }).unwrap();
+    assert_eq!(sizes, vec![300 * 8, 300 * 8, 300 * 8, 100 * 8]);
+
+    let mut calls = 0;
+    match dev.render_callback(1000, 300, |_| { calls += 1; if calls == 2 { Err(AltoError::InvalidOperation) } else { Ok(()) } }) {
+        Err(AltoError::InvalidOperation) => (),
+        r => panic!("{:?}", r),
+    }
+    assert_eq!(calls, 2);
+
+    match dev.render_callback(1000, 0, |_| Ok(())) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn implementation_from_name() {
+    use alto::Implementation;
+
+    for &(name, expected) in &[
+        ("OpenAL Soft", Implementation::Soft),
+        ("OpenAL Soft on Speakers (Realtek High Definition Audio)", Implementation::Soft),
+        ("OpenAL Soft 1.23.1", Implementation::Soft),
+        ("openal soft", Implementation::Soft),
+        ("Generic Hardware", Implementation::CreativeHardware),
+        ("Generic Software", Implementation::CreativeHardware),
+        ("SB X-Fi Audio [0001]", Implementation::CreativeHardware),
+        ("Creative Sound Blaster Z", Implementation::CreativeHardware),
+        ("Speakers (Realtek High Definition Audio)", Implementation::Unknown),
+        ("Built-in Output", Implementation::Unknown),
+        ("", Implementation::Unknown),
+    ] {
+        assert_eq!(Implementation::from_name(name), expected, "{}", name);
+    }
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let imp = dev.implementation().unwrap();
+    assert_eq!(dev.is_soft().unwrap(), imp == Implementation::Soft);
+}
+
+#[test]
+fn fade_out_curve_and_stop() {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use alto::{fade_gain, Source, SourceState};
+
+    let ms = Duration::from_millis;
+    assert_eq!(fade_gain(0.8, ms(0), ms(200)), 0.8);
+    assert!((fade_gain(0.8, ms(50), ms(200)) - 0.6).abs() < 1e-6);
+    assert!((fade_gain(0.8, ms(100), ms(200)) - 0.4).abs() < 1e-6);
+    assert_eq!(fade_gain(0.8, ms(200), ms(200)), 0.0);
+    assert_eq!(fade_gain(0.8, ms(300), ms(200)), 0.0);
+    assert_eq!(fade_gain(0.8, ms(0), ms(0)), 0.0);
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let buf = Arc::new(ctx.new_buffer(&vec![Mono{center: 0i16}; 4410][..], 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.set_looping(true);
+    src.set_gain(0.8).unwrap();
+    src.play();
+
+    let mut fade = src.fade_out(ms(200)).unwrap();
+    assert!(!fade.tick(ms(100)).unwrap());
+    assert!((src.gain() - 0.4).abs() < 1e-6);
+    assert_eq!(src.state(), SourceState::Playing);
+
+    assert!(fade.tick(ms(150)).unwrap());
+    assert!(fade.is_finished());
+    assert_eq!(src.state(), SourceState::Stopped);
+    assert_eq!(src.gain(), 0.8);
+    assert!(fade.tick(ms(10)).unwrap());
+
+    src.play();
+    let mut fade = src.fade_out(ms(200)).unwrap();
+    drop(src);
+    assert!(fade.tick(ms(10)).unwrap());
+}
+
+#[test]
+fn buffer_data_prevalidation() {
+    use alto::BufferConstraint;
+
+    let err = AltoError::InvalidBufferData(BufferConstraint::PartialFrame{size: 6, frame_size: 4});
+    assert_eq!(err.to_string(), "ALTO ERROR: Invalid buffer data (6 bytes is not a whole number of 4 byte frames)");
+    assert!(AltoError::InvalidBufferData(BufferConstraint::Empty).to_string().contains("data is empty"));
+    assert!(AltoError::InvalidBufferData(BufferConstraint::TooLarge{size: 1 << 40}).to_string().contains("1099511627776 bytes"));
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    match ctx.new_buffer(&[] as &[Mono<i16>], 44_100) {
+        Err(AltoError::InvalidBufferData(BufferConstraint::Empty)) => (),
+        r => panic!("{:?}", r.map(|_| ())),
+    }
+    match ctx.new_buffer::<Stereo<i16>, _>(&[0i16; 3][..], 44_100) {
+        Err(AltoError::InvalidBufferData(BufferConstraint::PartialFrame{size: 6, frame_size: 4})) => (),
+        r => panic!("{:?}", r.map(|_| ())),
+    }
+    let doubles = [Mono{center: 0.0f64}; 16];
+    match ctx.new_buffer(&doubles[..], 44_100) {
+        Ok(_) => assert!(ctx.is_extension_present(alto::ext::Al::Double)),
+        Err(AltoError::ExtensionNotPresent) => assert!(!ctx.is_extension_present(alto::ext::Al::Double)),
+        Err(e) => panic!("{}", e),
+    }
+
+    let data = [Stereo{left: 0i16, right: 0}; 32];
+    let mut buf = ctx.new_buffer(&data[..], 44_100).unwrap();
+    match buf.set_data::<Stereo<i16>, _>(&[0i16; 5][..], 44_100) {
+        Err(AltoError::InvalidBufferData(BufferConstraint::PartialFrame{size: 10, frame_size: 4})) => (),
+        r => panic!("{:?}", r),
+    }
+    match buf.set_data(&[] as &[Stereo<i16>], 44_100) {
+        Err(AltoError::InvalidBufferData(BufferConstraint::Empty)) => (),
+        r => panic!("{:?}", r),
+    }
+    assert_eq!(buf.size(), 128);
+}
+
+#[test]
+fn playing_source_count() {
+    use std::sync::Arc;
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let buf = Arc::new(ctx.new_buffer(&vec![Mono{center: 0i16}; 4410][..], 44_100).unwrap());
+    assert_eq!(ctx.playing_source_count().unwrap(), 0);
+
+    let mut srcs: Vec<_> = (0 .. 4).map(|_| {
+        let mut src = ctx.new_static_source().unwrap();
+        src.set_buffer(buf.clone()).unwrap();
+        src.set_looping(true);
+        src
+    }).collect();
+    srcs[0].play();
+    srcs[1].play();
+    srcs[2].play();
+    srcs[2].pause();
+    assert_eq!(ctx.playing_source_count().unwrap(), 2);
+
+    srcs[1].stop();
+    srcs[3].play();
+    srcs.remove(0);
+    assert_eq!(ctx.playing_source_count().unwrap(), 1);
+}
+
+#[test]
+fn loopback_open_with_format() {
+    use alto::{LoopbackDevice, LoopbackFormat, LoopbackFormatChannels, LoopbackFormatType, SampleFrame};
+
+    let stereo_f32 = LoopbackFormat{freq: 48_000, channels: LoopbackFormatChannels::Stereo, sample_ty: LoopbackFormatType::F32};
+    assert_eq!(LoopbackFormat::from_format(48_000, Stereo::<f32>::format()), Some(stereo_f32));
+    assert_eq!(LoopbackFormat::from_format(22_050, Mono::<u8>::format()).map(|f| (f.channels, f.sample_ty)), Some((LoopbackFormatChannels::Mono, LoopbackFormatType::U8)));
+    assert_eq!(LoopbackFormat::from_format(48_000, Mono::<f64>::format()), None);
+
+    let a = load_alto();
+    let mono_i16 = LoopbackFormat{channels: LoopbackFormatChannels::Mono, sample_ty: LoopbackFormatType::I16, ..stereo_f32};
+    for fmt in &[mono_i16, LoopbackFormat{freq: 0, ..stereo_f32}] {
+        match LoopbackDevice::<Stereo<f32>>::open_with_format(&a, None, fmt, None) {
+            Err(AltoError::InvalidValue) => (),
+            r => panic!("{:?}", r.map(|_| ())),
+        }
+    }
+
+    let (dev, _ctx) = match LoopbackDevice::<Stereo<f32>>::open_with_format(&a, None, &stereo_f32, None) {
+        Ok(r) => r,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    assert!(dev.is_render_format_supported(&stereo_f32).unwrap());
+    assert_eq!(dev.frequency().unwrap(), 48_000);
+}
+
+#[test]
+fn error_raw_codes() {
+    use alto::sys;
+
+    for &(ref err, alc) in &[
+        (AltoError::InvalidDevice, sys::ALC_INVALID_DEVICE),
+        (AltoError::InvalidContext, sys::ALC_INVALID_CONTEXT),
+        (AltoError::InvalidEnum, sys::ALC_INVALID_ENUM),
+        (AltoError::InvalidValue, sys::ALC_INVALID_VALUE),
+        (AltoError::OutOfMemory, sys::ALC_OUT_OF_MEMORY),
+        (AltoError::UnknownAlcError(0x1234), 0x1234),
+    ] {
+        assert_eq!(err.raw_alc_code(), Some(alc), "{:?}", err);
+    }
+    for &(ref err, al) in &[
+        (AltoError::InvalidName, sys::AL_INVALID_NAME),
+        (AltoError::InvalidEnum, sys::AL_INVALID_ENUM),
+        (AltoError::InvalidValue, sys::AL_INVALID_VALUE),
+        (AltoError::InvalidOperation, sys::AL_INVALID_OPERATION),
+        (AltoError::OutOfMemory, sys::AL_OUT_OF_MEMORY),
+        (AltoError::UnknownAlError(0x4321), 0x4321),
+    ] {
+        assert_eq!(err.raw_al_code(), Some(al), "{:?}", err);
+    }
+    assert_eq!(AltoError::InvalidName.raw_alc_code(), None);
+    assert_eq!(AltoError::InvalidDevice.raw_al_code(), None);
+    for err in &[
+        AltoError::UnsupportedVersion{found: alto::Version::new(1, 0), required: alto::Version::new(1, 1)},
+        AltoError::ExtensionNotPresent,
+        AltoError::NullError,
+        AltoError::WrongDevice,
+        AltoError::ShutDown,
+        AltoError::StaleHandle,
+    ] {
+        assert_eq!((err.raw_alc_code(), err.raw_al_code()), (None, None), "{:?}", err);
+    }
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let err = ctx.set_doppler_factor(-1.0).unwrap_err();
+    assert_eq!(err.raw_al_code(), Some(sys::AL_INVALID_VALUE));
+}
+
+#[test]
+fn scheduled_play_and_clock() {
+    use std::sync::Arc;
+    use alto::{Source, SourceState};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let clock = dev.clock_time();
+    match clock {
+        Ok(t) => {
+            assert!(t >= 0);
+            assert!(dev.clock_time().unwrap() >= t);
+        },
+        Err(AltoError::ExtensionNotPresent) => assert!(!dev.is_extension_present(alto::ext::Alc::SoftDeviceClock)),
+        Err(e) => panic!("{}", e),
+    }
+
+    let buf = Arc::new(ctx.new_buffer(&vec![Mono{center: 0i16}; 4410][..], 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.set_looping(true);
+    let start = clock.unwrap_or(0) + 1_000_000_000;
+    match src.play_at(start) {
+        Ok(()) => {
+            assert!(ctx.is_extension_present(alto::ext::Al::SoftSourceStartDelay));
+            assert_eq!(src.state(), SourceState::Playing);
+        },
+        Err(AltoError::ExtensionNotPresent) => {
+            assert!(!ctx.is_extension_present(alto::ext::Al::SoftSourceStartDelay));
+            assert_eq!(src.state(), SourceState::Initial);
+        },
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn device_clock_latency() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    match dev.clock_latency() {
+        Ok((clock, latency)) => assert!(clock >= 0 && latency >= 0),
+        Err(AltoError::ExtensionNotPresent) => assert!(!dev.is_extension_present(alto::ext::Alc::SoftDeviceClock)),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn clear_aux_sends_calls() {
+    use alto::replay::{self, Call};
+    use alto::Source;
+
+    // `AL_AUXILIARY_SEND_FILTER`
+    const SEND_FILTER: alto::sys::ALenum = 0x20006;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    if !dev.is_extension_present(alto::ext::Alc::Efx) {
+        match src.clear_aux_sends(None) {
+            Err(AltoError::ExtensionNotPresent) => (),
+            r => panic!("{:?}", r),
+        }
+        return;
+    }
+    let max = dev.max_aux_sends();
+
+    let path = std::env::temp_dir().join("alto-clear-aux-sends.bin");
+    ctx.start_recording(&path, false).unwrap();
+    src.clear_aux_sends(None).unwrap();
+    src.clear_aux_sends(Some(max.min(1))).unwrap();
+    ctx.stop_recording().unwrap();
+
+    let sends: Vec<_> = replay::read(&path).unwrap().into_iter().filter_map(|e| match e.call {
+        Call::Sourceiv{param: SEND_FILTER, values, ..} => Some(values),
+        _ => None,
+    }).collect();
+    let mut expected: Vec<_> = (0 .. max).map(|s| vec![0, s, 0]).collect();
+    expected.extend((0 .. max.min(1)).map(|s| vec![0, s, 0]));
+    assert_eq!(sends, expected);
+
+    match src.clear_aux_sends(Some(max + 1)) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn reverb_properties_lerp() {
+    use alto::efx::{REVERB_PRESET_CAVE, REVERB_PRESET_ROOM};
+
+    let (room, cave) = (&REVERB_PRESET_ROOM, &REVERB_PRESET_CAVE);
+    assert_eq!(room.lerp(cave, 0.0), *room);
+    assert_eq!(room.lerp(cave, 1.0), *cave);
+    assert_eq!(room.lerp(cave, -1.0), *room);
+    assert_eq!(room.lerp(cave, 2.0), *cave);
+
+    let half = room.lerp(cave, 0.5);
+    assert!((half.decay_time - (0.40 + 2.91) / 2.0).abs() < 1e-5);
+    assert!((half.density - (0.4287 + 1.0) / 2.0).abs() < 1e-5);
+    assert!((half.late_reverb_delay - (0.0030 + 0.0220) / 2.0).abs() < 1e-6);
+    assert_eq!(half.reflections_pan, [0.0, 0.0, 0.0]);
+    assert!(!half.decay_hflimit);
+    assert!(room.lerp(cave, 0.49).decay_hflimit);
+
+    let mut panned = *room;
+    panned.late_reverb_pan = [1.0, -1.0, 0.5];
+    assert_eq!(room.lerp(&panned, 0.5).late_reverb_pan, [0.5, -0.5, 0.25]);
+}
+
+#[test]
+fn global_state_round_trip() {
+    use alto::{DistanceModel, GlobalState};
+
+    let state = GlobalState{
+        distance_model: DistanceModel::Exponent,
+        doppler_factor: 0.5,
+        speed_of_sound: 200.0,
+        position: [1.0, 2.0, 3.0],
+        velocity: [0.0, 1.0, 0.0],
+        gain: 0.25,
+        orientation: ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    };
+    let copy = state;
+    assert_eq!(copy, state.clone());
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let initial = ctx.snapshot_global().unwrap();
+    assert_eq!(initial.distance_model, DistanceModel::InverseClamped);
+
+    ctx.restore_global(&state).unwrap();
+    assert_eq!(ctx.snapshot_global().unwrap(), state);
+    ctx.restore_global(&initial).unwrap();
+    assert_eq!(ctx.snapshot_global().unwrap(), initial);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn restore_global_calls() {
+    use alto::replay::{self, Call};
+    use alto::{DistanceModel, GlobalState};
+    use alto::sys;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let state = GlobalState{
+        distance_model: DistanceModel::Linear,
+        doppler_factor: 2.0,
+        speed_of_sound: 100.0,
+        position: [1.0, 2.0, 3.0],
+        velocity: [1.0, 0.0, 0.0],
+        gain: 0.5,
+        orientation: ([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    };
+
+    let path = std::env::temp_dir().join("alto-restore-global.bin");
+    ctx.start_recording(&path, false).unwrap();
+    ctx.restore_global(&state).unwrap();
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    assert_eq!(calls, vec![
+        Call::DistanceModel{value: sys::AL_LINEAR_DISTANCE},
+        Call::DopplerFactor{value: 2.0},
+        Call::SpeedOfSound{value: 100.0},
+        Call::Listenerfv{param: sys::AL_POSITION, values: vec![1.0, 2.0, 3.0]},
+        Call::Listenerfv{param: sys::AL_VELOCITY, values: vec![1.0, 0.0, 0.0]},
+        Call::Listenerf{param: sys::AL_GAIN, value: 0.5},
+        Call::Listenerfv{param: sys::AL_ORIENTATION, values: vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0]},
+    ]);
+}
+
+#[test]
+fn source_state_predicates() {
+    use std::sync::Arc;
+    use alto::{Source, SourceState};
+
+    for &(state, expected) in &[
+        (SourceState::Initial, [true, false, false, false]),
+        (SourceState::Playing, [false, true, false, false]),
+        (SourceState::Paused, [false, false, true, false]),
+        (SourceState::Stopped, [false, false, false, true]),
+        (SourceState::Unknown(0), [false, false, false, false]),
+    ] {
+        assert_eq!([state.is_initial(), state.is_playing(), state.is_paused(), state.is_stopped()], expected, "{:?}", state);
+    }
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let buf = Arc::new(ctx.new_buffer(&vec![Mono{center: 0i16}; 4410][..], 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.set_looping(true);
+    assert!(src.is_initial());
+    src.play();
+    assert!(src.is_playing() && !src.is_initial());
+    src.pause();
+    assert!(src.is_paused());
+    src.stop();
+    assert!(src.is_stopped() && !src.is_playing());
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn source_drop_detaches_before_delete() {
+    use std::sync::Arc;
+    use alto::replay::{self, Call};
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let data = vec![Mono{center: 0i16}; 4410];
+    let buf = Arc::new(ctx.new_buffer(&data[..], 44_100).unwrap());
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_buffer(buf).unwrap();
+    src.set_looping(true);
+    src.play();
+    let mut stream = ctx.new_streaming_source().unwrap();
+    stream.queue_buffer(ctx.new_buffer(&data[..], 44_100).unwrap()).unwrap();
+    stream.queue_buffer(ctx.new_buffer(&data[..], 44_100).unwrap()).unwrap();
+    stream.play();
+
+    let path = std::env::temp_dir().join("alto-source-drop-order.bin");
+    ctx.start_recording(&path, false).unwrap();
+    drop(src);
+    drop(stream);
+    ctx.stop_recording().unwrap();
+
+    // Sources and buffers that predate the recording are introduced with `Gen*` entries, which are left out here.
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter()
+        .map(|e| e.call)
+        .filter(|c| match *c { Call::GenSource{..} | Call::GenBuffer{..} => false, _ => true })
+        .collect();
+    assert_eq!(&calls[.. 3], &[
+        Call::SourceStop{src: 0},
+        Call::SourceBuffer{src: 0, buf: None},
+        Call::DeleteSource{src: 0},
+    ]);
+    match calls[3] {
+        Call::DeleteBuffer{..} => (),
+        ref c => panic!("{:?}", c),
+    }
+    assert_eq!(&calls[4 .. 8], &[
+        Call::SourceStop{src: 1},
+        Call::SourceUnqueueBuffers{src: 1, count: 2},
+        Call::SourceBuffer{src: 1, buf: None},
+        Call::DeleteSource{src: 1},
+    ]);
+    match calls[8 ..] {
+        [Call::DeleteBuffer{..}, Call::DeleteBuffer{..}] => (),
+        ref c => panic!("{:?}", c),
+    }
+}
+
+#[test]
+fn efx_context_creation() {
+    assert_eq!(alto::ContextAttrs::default().max_aux_sends(2).max_aux_sends, Some(2));
+    assert_eq!(alto::ContextAttrs::default().max_aux_sends(u32::MAX).max_aux_sends, Some(i32::MAX));
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    match dev.new_efx_context(2) {
+        Ok(ctx) => {
+            assert!(dev.is_extension_present(alto::ext::Alc::Efx));
+            assert!(dev.max_aux_sends() >= 1);
+            drop(ctx);
+        },
+        Err(AltoError::ExtensionNotPresent) => assert!(!dev.is_extension_present(alto::ext::Alc::Efx)),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn resample_linear_ramp() {
+    use alto::util::resample_linear;
+
+    let ramp: Vec<_> = (0 .. 8).map(|i| Stereo{left: i as f32 * 2.0, right: -(i as f32) * 2.0}).collect();
+
+    let up = resample_linear(&ramp, 22_050, 44_100);
+    assert_eq!(up.len(), 16);
+    for (i, f) in up.iter().enumerate().take(15) {
+        assert_eq!(f.left, i as f32);
+        assert_eq!(f.right, -(i as f32));
+    }
+    // Past the last input frame, its value is held.
+    assert_eq!((up[15].left, up[15].right), (14.0, -14.0));
+
+    let down = resample_linear(&ramp, 44_100, 22_050);
+    assert_eq!(down.iter().map(|f| f.left).collect::<Vec<_>>(), vec![0.0, 4.0, 8.0, 12.0]);
+    assert_eq!(down.iter().map(|f| f.right).collect::<Vec<_>>(), vec![0.0, -4.0, -8.0, -12.0]);
+
+    let ints: Vec<_> = [0i16, 101, i16::MAX].iter().map(|&c| Mono{center: c}).collect();
+    let up = resample_linear(&ints, 1, 2);
+    assert_eq!(up.iter().map(|f| f.center).collect::<Vec<_>>(), vec![0, 51, 101, 16434, i16::MAX, i16::MAX]);
+    assert_eq!(resample_linear(&ints, 3, 3).len(), 3);
+    assert!(resample_linear(&ints, 0, 44_100).is_empty());
+    assert!(resample_linear::<Mono<i16>>(&[], 22_050, 44_100).is_empty());
+}
+
+#[test]
+fn empty_device_spec_is_default() {
+    let empty = CStr::from_bytes_with_nul(b"\0").unwrap();
+
+    let a = load_alto();
+    let default = a.open(None).unwrap();
+    let empty = a.open(Some(empty)).unwrap();
+    assert_eq!(empty.specifier(), default.specifier());
+}
+
+#[test]
+fn source_read_state() {
+    use alto::{Source, SourceState};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_gain(0.5).unwrap();
+    src.set_pitch(1.5).unwrap();
+    src.set_position([1.0, 2.0, 3.0]).unwrap();
+    src.set_velocity([-1.0, 0.0, 4.0]).unwrap();
+    src.set_looping(true);
+
+    let snapshot = src.read_state().unwrap();
+    assert_eq!(snapshot.gain, 0.5);
+    assert_eq!(snapshot.pitch, 1.5);
+    assert_eq!(snapshot.position, [1.0, 2.0, 3.0]);
+    assert_eq!(snapshot.velocity, [-1.0, 0.0, 4.0]);
+    assert!(snapshot.looping);
+    assert_eq!(snapshot.state, SourceState::Initial);
+
+    // The snapshot agrees with the individual getters.
+    assert_eq!(snapshot.gain, src.gain());
+    assert_eq!(snapshot.pitch, src.pitch());
+    assert_eq!(snapshot.position, src.position::<[f32; 3]>());
+    assert_eq!(snapshot.velocity, src.velocity::<[f32; 3]>());
+    assert_eq!(snapshot.looping, src.looping());
+    assert_eq!(snapshot.state, src.state());
+}
+
+#[test]
+fn capture_format_negotiation() {
+    use alto::{Format, StandardFormat, ExtFloat32Format};
+
+    let candidates = [
+        Format::Standard(StandardFormat::StereoI16),
+        Format::Standard(StandardFormat::MonoI16),
+    ];
+
+    let a = load_alto();
+    // Float formats are never capture compatible, so they are skipped without opening anything.
+    let float = [Format::ExtFloat32(ExtFloat32Format::Stereo)];
+    match a.best_capture_format(None, 44_100, &float) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+    if let Ok(fmt) = a.best_capture_format(None, 44_100, &candidates) {
+        assert!(candidates.contains(&fmt));
+        // The probe device is gone, so the device can be opened for real.
+        let _cap = a.open_capture::<Mono<i16>>(None, 44_100, 4410).unwrap();
+    }
+}
+
+#[test]
+fn sink_buffer_recycling() {
+    use alto::{AudioSink, BufferPool, Format, StandardFormat};
+
+    // Mock buffers are plain ids, allocated in order.
+    let mut pool = BufferPool::new();
+    let mut next = 0u32;
+    let mut alloc = || { next += 1; Ok(next) };
+
+    let a = pool.acquire(&mut alloc).unwrap();
+    let b = pool.acquire(&mut alloc).unwrap();
+    assert_eq!((a, b), (1, 2));
+    assert_eq!((pool.in_flight(), pool.free(), pool.allocated()), (2, 0, 2));
+
+    // A played buffer is reused before anything new is allocated.
+    pool.release(a);
+    assert_eq!((pool.in_flight(), pool.free()), (1, 1));
+    assert_eq!(pool.acquire(&mut alloc).unwrap(), 1);
+    assert_eq!(pool.acquire(&mut alloc).unwrap(), 3);
+    assert_eq!((pool.in_flight(), pool.free(), pool.allocated()), (3, 0, 3));
+
+    // A failed allocation leaves the pool untouched.
+    match pool.acquire(|| Err(AltoError::OutOfMemory)) {
+        Err(AltoError::OutOfMemory) => (),
+        r => panic!("{:?}", r),
+    }
+    assert_eq!((pool.in_flight(), pool.allocated()), (3, 3));
+
+    let al = load_alto();
+    let dev = al.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    match ctx.sink(Format::Standard(StandardFormat::MonoU8), 44_100) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r.map(|_| ())),
+    }
+    let mut sink = ctx.sink(Format::Standard(StandardFormat::StereoI16), 44_100).unwrap();
+    assert_eq!(sink.channels(), 2);
+    match sink.write(&[0.0; 3]) {
+        Err(AltoError::InvalidBufferData(..)) => (),
+        r => panic!("{:?}", r),
+    }
+    sink.write(&[0.0; 882]).unwrap();
+    sink.write(&[0.5; 882]).unwrap();
+    assert_eq!(sink.source().buffers_queued(), 2);
+    assert_eq!((sink.pool().in_flight(), sink.pool().allocated()), (2, 2));
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn reset_preserving_brackets_reset() {
+    use alto::replay::{self, Call};
+    use alto::{sys, DistanceModel};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    ctx.set_distance_model(DistanceModel::Exponent);
+    ctx.set_gain(0.25).unwrap();
+
+    // What happens inside the bracket is undone by the restore that follows it.
+    let path = std::env::temp_dir().join("alto-preserving-global.bin");
+    ctx.start_recording(&path, false).unwrap();
+    ctx.preserving_global(|| ctx.set_gain(1.0)).unwrap();
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter().map(|e| e.call).collect();
+    assert_eq!(calls[0], Call::Listenerf{param: sys::AL_GAIN, value: 1.0});
+    assert_eq!(calls[1], Call::DistanceModel{value: sys::AL_EXPONENT_DISTANCE});
+    assert!(calls[2 ..].contains(&Call::Listenerf{param: sys::AL_GAIN, value: 0.25}));
+    assert_eq!(ctx.gain(), 0.25);
+
+    match ctx.preserving_global(|| -> alto::AltoResult<()> { ctx.set_gain(1.0)?; Err(AltoError::InvalidValue) }) {
+        Err(AltoError::InvalidValue) => assert_eq!(ctx.gain(), 0.25),
+        r => panic!("{:?}", r),
+    }
+
+    match dev.reset_preserving(None, &ctx) {
+        Ok(()) => assert_eq!(ctx.gain(), 0.25),
+        Err(AltoError::ExtensionNotPresent) => (),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn error_kinds() {
+    use alto::{BufferConstraint, ErrorKind, Version};
+
+    // No wildcard arm: this stops compiling if a kind is ever added.
+    fn name(kind: ErrorKind) -> &'static str {
+        match kind {
+            ErrorKind::InvalidHandle => "handle",
+            ErrorKind::InvalidArgument => "argument",
+            ErrorKind::InvalidState => "state",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::OutOfMemory => "memory",
+            ErrorKind::Io => "io",
+            ErrorKind::Unknown => "unknown",
+        }
+    }
+
+    let cases = vec![
+        (AltoError::InvalidDevice, "handle"),
+        (AltoError::InvalidContext, "handle"),
+        (AltoError::InvalidName, "handle"),
+        (AltoError::ShutDown, "handle"),
+        (AltoError::StaleHandle, "handle"),
+        (AltoError::InvalidEnum, "argument"),
+        (AltoError::InvalidValue, "argument"),
+        (AltoError::CaptureBufferTooSmall{requested: 1, minimum: 441}, "argument"),
+        (AltoError::InvalidBufferData(BufferConstraint::Empty), "argument"),
+        (AltoError::InvalidOperation, "state"),
+        (AltoError::WrongDevice, "state"),
+        (AltoError::WrongContext{expected: 0, found: 1}, "state"),
+        (AltoError::UnsupportedVersion{found: Version::new(1, 0), required: Version::new(1, 1)}, "unsupported"),
+        (AltoError::ExtensionNotPresent, "unsupported"),
+        (AltoError::OutOfMemory, "memory"),
+        (AltoError::Io(std::io::Error::other("dylib")), "io"),
+        (AltoError::UnknownAlcError(0x1234), "unknown"),
+        (AltoError::UnknownAlError(0x1234), "unknown"),
+        (AltoError::NullError, "unknown"),
+    ];
+    for (e, expected) in cases {
+        assert_eq!(name(e.kind()), expected, "{:?}", e);
+    }
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn play_buffer_calls() {
+    use std::sync::Arc;
+    use alto::replay::{self, Call};
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let data = vec![Mono{center: 0i16}; 4410];
+    let first = Arc::new(ctx.new_buffer(&data[..], 44_100).unwrap());
+    let second = Arc::new(ctx.new_buffer(&data[..], 44_100).unwrap());
+
+    let path = std::env::temp_dir().join("alto-play-buffer.bin");
+    ctx.start_recording(&path, false).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_looping(true);
+    src.play_buffer(first).unwrap();
+    src.play_buffer(second).unwrap();
+    ctx.stop_recording().unwrap();
+
+    let calls: Vec<_> = replay::read(&path).unwrap().into_iter()
+        .map(|e| e.call)
+        .filter(|c| match *c { Call::GenBuffer{..} | Call::Sourcei{..} => false, _ => true })
+        .collect();
+    // A fresh source is bound and played right away; a playing one is stopped first.
+    assert_eq!(calls, vec![
+        Call::GenSource{src: 0},
+        Call::SourceBuffer{src: 0, buf: Some(0)},
+        Call::SourcePlay{src: 0},
+        Call::SourceStop{src: 0},
+        Call::SourceBuffer{src: 0, buf: Some(1)},
+        Call::SourcePlay{src: 0},
+    ]);
+    assert!(src.is_playing());
+
+    // An error raised by the stop is reported before the new buffer is bound.
+    let third = Arc::new(ctx.new_buffer(&data[..], 44_100).unwrap());
+    ctx.start_recording(&path, false).unwrap();
+    unsafe { a.raw_api().alSourcei(0x7fff_ffff, alto::sys::AL_LOOPING, alto::sys::AL_TRUE as alto::sys::ALint); }
+    match src.play_buffer(third) {
+        Err(AltoError::InvalidName) => (),
+        r => panic!("{:?}", r),
+    }
+    ctx.stop_recording().unwrap();
+
+    let entries = replay::read(&path).unwrap();
+    let last = entries.last().unwrap();
+    assert_eq!(last.call, Call::SourceStop{src: 0});
+    assert_eq!(last.error, Some(alto::sys::AL_INVALID_NAME));
+    assert!(!entries.iter().any(|e| match e.call { Call::SourceBuffer{..} | Call::SourcePlay{..} => true, _ => false }));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "wav")]
+#[test]
+fn recorder_writes_captured_frames() {
+    extern crate hound;
+
+    use std::io::Cursor;
+    use alto::AltoResult;
+    use alto::wav::{CaptureSource, Recorder};
+
+    // Hands out a fixed list of frames a few at a time, as a capture device would.
+    struct MockCapture {
+        frames: Vec<Stereo<i16>>,
+        pos: usize,
+        started: bool,
+    }
+    impl CaptureSource<Stereo<i16>> for MockCapture {
+        fn start(&mut self) { self.started = true; }
+        fn stop(&mut self) { self.started = false; }
+        fn available(&self) -> usize {
+            if self.started { (self.frames.len() - self.pos).min(100) } else { self.frames.len() - self.pos }
+        }
+        fn read(&mut self, out: &mut [Stereo<i16>]) -> AltoResult<usize> {
+            let len = out.len().min(self.frames.len() - self.pos);
+            out[.. len].copy_from_slice(&self.frames[self.pos .. self.pos + len]);
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    let frames: Vec<_> = (0 .. 250).map(|i| Stereo{left: i as i16, right: -(i as i16)}).collect();
+    let mut wav = Vec::new();
+    {
+        let capture = MockCapture{frames: frames.clone(), pos: 0, started: false};
+        let mut rec = Recorder::new(capture, 22_050, Cursor::new(&mut wav)).unwrap();
+        rec.start();
+        assert_eq!(rec.pump().unwrap(), 100);
+        assert_eq!(rec.pump().unwrap(), 100);
+        assert_eq!(rec.frames_written(), 200);
+        // The rest is drained when finishing.
+        rec.finish().unwrap();
+    }
+
+    let mut reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+    assert_eq!(reader.spec().channels, 2);
+    assert_eq!(reader.spec().sample_rate, 22_050);
+    assert_eq!(reader.duration(), 250);
+    let samples: Vec<i16> = reader.samples().map(|s| s.unwrap()).collect();
+    assert_eq!(&samples[498 ..], &[249, -249]);
+}
+
+#[test]
+fn batch_source_ops_check_context() {
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let other = dev.new_context(None).unwrap();
+
+    let mut sources = vec![ctx.new_static_source().unwrap(), other.new_static_source().unwrap()];
+    match ctx.play_all(&mut sources) {
+        Err(AltoError::WrongContext{expected, found}) => assert_eq!((expected, found), (ctx.id(), other.id())),
+        r => panic!("{:?}", r),
+    }
+    // Nothing is touched if any source is rejected.
+    assert!(sources.iter().all(|s| s.is_initial()));
+    match ctx.set_positions(&mut sources, &[[1.0, 0.0, 0.0], [2.0, 0.0, 0.0]]) {
+        Err(AltoError::WrongContext{..}) => (),
+        r => panic!("{:?}", r),
+    }
+    assert_eq!(sources[0].position::<[f32; 3]>(), [0.0, 0.0, 0.0]);
+
+    sources.pop();
+    sources.push(ctx.new_static_source().unwrap());
+    match ctx.set_positions(&mut sources, &[[1.0, 0.0, 0.0]]) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+    ctx.set_positions(&mut sources, &[[1.0, 0.0, 0.0], [2.0, 0.0, 0.0]]).unwrap();
+    assert_eq!(sources[1].position::<[f32; 3]>(), [2.0, 0.0, 0.0]);
+    ctx.play_all(&mut sources).unwrap();
+}
+
+#[test]
+fn format_frames_in() {
+    use alto::{ExtFloat32Format, ExtIma4Format, ExtMcFormat, Format, SoftByte3Format, StandardFormat};
+
+    let frames = |fmt: Format, len| match fmt.frames_in(len) {
+        Ok(n) => Some(n),
+        Err(AltoError::InvalidValue) => None,
+        Err(e) => panic!("{}", e),
+    };
+
+    assert_eq!(frames(Format::Standard(StandardFormat::MonoU8), 7), Some(7));
+    assert_eq!(frames(Format::Standard(StandardFormat::StereoI16), 400), Some(100));
+    assert_eq!(frames(Format::Standard(StandardFormat::StereoI16), 402), None);
+    assert_eq!(frames(Format::ExtFloat32(ExtFloat32Format::Stereo), 16), Some(2));
+    assert_eq!(frames(Format::ExtFloat32(ExtFloat32Format::Stereo), 12), None);
+    assert_eq!(frames(Format::SoftByte3(SoftByte3Format::Stereo24), 12), Some(2));
+    assert_eq!(frames(Format::SoftByte3(SoftByte3Format::Stereo24), 8), None);
+    assert_eq!(frames(Format::ExtMcFormats(ExtMcFormat::Mc51ChnI16), 24), Some(2));
+    assert_eq!(frames(Format::ExtMcFormats(ExtMcFormat::Mc51ChnI16), 20), None);
+    assert_eq!(frames(Format::Standard(StandardFormat::MonoI16), 0), Some(0));
+    // Block compressed formats have no fixed frame size.
+    assert_eq!(frames(Format::ExtIma4(ExtIma4Format::Mono), 36), None);
+}
+
+#[test]
+fn clear_current_context() {
+    use alto::Source;
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_gain(0.5).unwrap();
+
+    a.clear_current_context().unwrap();
+    assert!(!a.is_any_context_current());
+
+    // Alto objects make their context current again as needed.
+    assert_eq!(src.gain(), 0.5);
+    a.clear_current_context().unwrap();
+    assert!(!a.is_any_context_current());
+}
+
+#[test]
+fn loop_points_verification() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let buf = ctx.new_buffer(&vec![Mono{center: 0i16}; 4410][..], 44_100).unwrap();
+    if !ctx.is_extension_present(alto::ext::Al::SoftLoopPoints) {
+        match buf.loop_points() {
+            Err(AltoError::ExtensionNotPresent) => return,
+            r => panic!("{:?}", r),
+        }
+    }
+
+    assert_eq!(buf.loop_points().unwrap(), (0, 4410));
+    buf.set_soft_loop_points_verified((100, 2000)).unwrap();
+    assert_eq!(buf.loop_points().unwrap(), (100, 2000));
+    // Points the implementation refuses fail before they could be read back.
+    assert!(buf.set_soft_loop_points_verified((2000, 100)).is_err());
+    assert_eq!(buf.loop_points().unwrap(), (100, 2000));
+}
+
+#[test]
+fn output_mode_tokens() {
+    use alto::OutputMode;
+    use alto::ext::{ALC_SOFT_output_mode, ExtensionError};
+
+    // Token values from alext.h.
+    let asom = ALC_SOFT_output_mode{
+        ALC_OUTPUT_MODE_SOFT: Ok(0x19AC),
+        ALC_ANY_SOFT: Ok(0x19AD),
+        ALC_MONO_SOFT: Ok(0x1500),
+        ALC_STEREO_SOFT: Ok(0x1501),
+        ALC_STEREO_BASIC_SOFT: Ok(0x19AE),
+        ALC_STEREO_UHJ_SOFT: Ok(0x19AF),
+        ALC_STEREO_HRTF_SOFT: Ok(0x19B2),
+        ALC_QUAD_SOFT: Ok(0x1503),
+        ALC_SURROUND_5_1_SOFT: Ok(0x1504),
+        ALC_SURROUND_6_1_SOFT: Ok(0x1505),
+        ALC_SURROUND_7_1_SOFT: Err(ExtensionError),
+    };
+    for &(token, mode) in &[
+        (0x19AD, OutputMode::Any),
+        (0x1500, OutputMode::Mono),
+        (0x1501, OutputMode::Stereo),
+        (0x19AE, OutputMode::StereoBasic),
+        (0x19AF, OutputMode::StereoUhj),
+        (0x19B2, OutputMode::StereoHrtf),
+        (0x1503, OutputMode::Quad),
+        (0x1504, OutputMode::Surround5_1),
+        (0x1505, OutputMode::Surround6_1),
+        (0x1506, OutputMode::Unknown(0x1506)),
+        (0, OutputMode::Unknown(0)),
+    ] {
+        assert_eq!(OutputMode::from_raw(&asom, token), mode);
+    }
+    let asom = ALC_SOFT_output_mode{ALC_SURROUND_7_1_SOFT: Ok(0x1506), ..asom};
+    assert_eq!(OutputMode::from_raw(&asom, 0x1506), OutputMode::Surround7_1);
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    match dev.output_mode() {
+        Ok(mode) => assert!(dev.is_extension_present(alto::ext::Alc::SoftOutputMode), "{:?}", mode),
+        Err(AltoError::ExtensionNotPresent) => assert!(!dev.is_extension_present(alto::ext::Alc::SoftOutputMode)),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[test]
+fn cached_gain_and_pitch() {
+    use alto::{CachedSource, Source};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+    src.set_gain(0.5).unwrap();
+    let mut src = CachedSource::new(src);
+    assert_eq!(src.cached_gain(), 0.5);
+    assert_eq!(src.cached_pitch(), 1.0);
+
+    src.set_gain(0.25).unwrap();
+    src.set_pitch(1.5).unwrap();
+    assert_eq!(src.cached_gain(), 0.25);
+    assert_eq!(src.cached_pitch(), 1.5);
+    assert_eq!(src.cached_gain(), src.gain());
+    assert_eq!(src.cached_pitch(), src.pitch());
+
+    assert!(src.set_gain(-1.0).is_err());
+    assert_eq!(src.cached_gain(), 0.25);
+
+    // Other source methods pass through, and changes made on the inner source bypass the cache.
+    src.set_looping(true);
+    assert!(src.looping());
+    Source::set_gain(&mut *src, 0.75).unwrap();
+    assert_eq!(src.cached_gain(), 0.25);
+    assert_eq!(src.into_inner().gain(), 0.75);
+}
+
+#[test]
+fn render_with_sources_sine() {
+    use alto::Source;
+
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+
+    let sine: Vec<_> = (0 .. 44_100).map(|i| {
+        let t = i as f32 / 44_100.0;
+        Mono{center: ((t * 440.0 * 2.0 * std::f32::consts::PI).sin() * 16_000.0) as i16}
+    }).collect();
+    let output = dev.render_with_sources(44_100, None, 4096, |ctx| {
+        let buf = ctx.new_buffer(&sine[..], 44_100)?;
+        let mut src = ctx.new_static_source()?;
+        src.set_buffer(std::sync::Arc::new(buf))?;
+        src.play();
+        Ok(src)
+    }).unwrap();
+
+    assert_eq!(output.len(), 4096);
+    assert!(output.iter().any(|f| f.left != 0.0 || f.right != 0.0));
+}
+
+#[test]
+fn errors_are_clone() {
+    use std::io;
+    use alto::{BufferConstraint, ErrorKind, Version};
+
+    let errors = vec![
+        AltoError::InvalidValue,
+        AltoError::UnknownAlError(0x1234),
+        AltoError::UnsupportedVersion{found: Version{major: 1, minor: 0}, required: Version{major: 1, minor: 1}},
+        AltoError::WrongContext{expected: 1, found: 2},
+        AltoError::InvalidBufferData(BufferConstraint::PartialFrame{size: 3, frame_size: 4}),
+        AltoError::Io(io::Error::new(io::ErrorKind::NotFound, "libopenal.so not found")),
+    ];
+    for e in &errors {
+        let cloned = e.clone();
+        assert_eq!(cloned.kind(), e.kind());
+        assert_eq!(cloned.to_string(), e.to_string());
+    }
+
+    match errors[5].clone() {
+        AltoError::Io(io) => {
+            assert_eq!(io.kind(), io::ErrorKind::NotFound);
+            assert_eq!(io.to_string(), "libopenal.so not found");
+        },
+        e => panic!("expected Io, got {:?}", e),
+    }
+    assert_eq!(errors[5].kind(), ErrorKind::Io);
+}
+
+#[test]
+fn new_buffer_from_iter_sine() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+
+    let sine = (0 .. 44_100).map(|i| {
+        let t = i as f32 / 44_100.0;
+        Mono{center: ((t * 440.0 * 2.0 * std::f32::consts::PI).sin() * 16_000.0) as i16}
+    });
+    let buf = ctx.new_buffer_from_iter(sine, 44_100, 44_100).unwrap();
+    assert_eq!(buf.size(), 44_100 * 2);
+    assert_eq!(buf.frequency(), 44_100);
+
+    // A hint that is too small only costs a reallocation.
+    let stereo = (0 .. 1000).map(|i| Stereo{left: i as i16, right: -(i as i16)});
+    let buf = ctx.new_buffer_from_iter(stereo, 10, 22_050).unwrap();
+    assert_eq!(buf.size(), 1000 * 4);
+
+    match ctx.new_buffer_from_iter(Vec::<Mono<i16>>::new(), 0, u32::MAX) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("expected InvalidValue, got {:?}", r.map(|b| b.size())),
+    }
+}
+
+#[test]
+fn context_keeps_device_alive() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let spec = dev.specifier().map(|s| s.to_owned());
+    let efx = dev.is_extension_present(alto::ext::Alc::Efx);
+    let ctx = dev.new_context(None).unwrap();
+    drop(dev);
+
+    let dev = ctx.device();
+    assert_eq!(dev.specifier().map(|s| s.to_owned()), spec);
+    assert_eq!(dev.is_extension_present(alto::ext::Alc::Efx), efx);
+    assert_eq!(dev.connected().unwrap(), true);
+    ctx.new_buffer(vec![Mono{center: 0i16}; 64], 44_100).unwrap();
+}
+
+#[test]
+fn format_capture_compatibility() {
+    use alto::{ExtALawFormat, ExtBFormat, ExtDoubleFormat, ExtFloat32Format, ExtIma4Format, ExtMcFormat, ExtMuLawFormat};
+    use alto::{Format, SoftByte3Format, SoftMsadpcmFormat, StandardFormat};
+
+    for &f in &[StandardFormat::MonoU8, StandardFormat::MonoI16, StandardFormat::StereoU8, StandardFormat::StereoI16] {
+        assert!(Format::Standard(f).is_capture_compatible());
+    }
+
+    for &f in &[
+        Format::ExtFloat32(ExtFloat32Format::Mono),
+        Format::ExtFloat32(ExtFloat32Format::Stereo),
+        Format::ExtALaw(ExtALawFormat::Mono),
+        Format::ExtBFormat(ExtBFormat::B2DI16),
+        Format::ExtDouble(ExtDoubleFormat::Stereo),
+        Format::ExtIma4(ExtIma4Format::Mono),
+        Format::ExtMcFormats(ExtMcFormat::QuadI16),
+        Format::ExtMuLaw(ExtMuLawFormat::Mono),
+        Format::SoftMsadpcm(SoftMsadpcmFormat::Stereo),
+        Format::SoftByte3(SoftByte3Format::Mono24),
+    ] {
+        assert!(!f.is_capture_compatible(), "{:?}", f);
+    }
+}
+
+#[test]
+fn pan_position_curve() {
+    use alto::pan_position;
+
+    fn close(a: [f32; 3], b: [f32; 3]) -> bool {
+        a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-6)
+    }
+
+    assert!(close(pan_position(-1.0), [-1.0, 0.0, 0.0]));
+    assert!(close(pan_position(0.0), [0.0, 0.0, -1.0]));
+    assert!(close(pan_position(1.0), [1.0, 0.0, 0.0]));
+
+    // Constant power: the source stays at unit distance, with x following sin(pan * pi / 2).
+    let half = pan_position(0.5);
+    assert!((half[0] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    assert!(((half[0] * half[0] + half[2] * half[2]).sqrt() - 1.0).abs() < 1e-6);
+
+    assert_eq!(pan_position(-3.0), pan_position(-1.0));
+    assert_eq!(pan_position(7.5), pan_position(1.0));
+    assert_eq!(pan_position(std::f32::NAN), pan_position(0.0));
+}
+
+#[test]
+fn source_set_pan() {
+    use alto::{pan_position, Source};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let mut src = ctx.new_static_source().unwrap();
+
+    src.set_pan(-1.0).unwrap();
+    assert!(src.relative());
+    assert_eq!(src.position::<[f32; 3]>(), pan_position(-1.0));
+}
+
+#[test]
+fn render_after_shutdown() {
+    let a = load_alto();
+    let mut dev = match a.open_loopback::<Stereo<f32>>(None) {
+        Ok(dev) => dev,
+        Err(AltoError::ExtensionNotPresent) => return,
+        Err(e) => panic!("{}", e),
+    };
+    let _ctx = dev.new_context(44_100, None).unwrap();
+    a.clone().shutdown();
+
+    let mut data = vec![Stereo{left: 0.0f32, right: 0.0}; 64];
+    assert_eq!(dev.soft_render_samples(&mut data[..]), 0);
+    match dev.render_callback(128, 64, |_| panic!("rendered after shutdown")) {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r),
+    }
+}
+
+#[test]
+fn device_queries_after_shutdown() {
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let _ctx = dev.new_context(None).unwrap();
+    a.clone().shutdown();
+
+    match dev.connected() {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r),
+    }
+    match dev.min_latency() {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r),
+    }
+    match dev.clock_time() {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r),
+    }
+    match dev.soft_pause() {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r),
+    }
+    dev.soft_resume();
+    match dev.soft_reset(None) {
+        Err(AltoError::ShutDown) => (),
+        r => panic!("{:?}", r),
+    }
+    assert!(dev.enumerate_soft_hrtfs().is_empty());
+    assert!(!dev.soft_output_limiter());
+    assert_eq!(dev.max_aux_sends(), 0);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn replay_rejects_malformed_logs() {
+    use std::io::Write;
+    use alto::replay::{self, Call, Entry};
+
+    let path = std::env::temp_dir().join("alto-replay-malformed.bin");
+    let read_fails = |bytes: &[u8]| {
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(b"ALTOREC\x01").unwrap();
+        file.write_all(bytes).unwrap();
+        drop(file);
+        match replay::read(&path) {
+            Err(AltoError::Io(..)) => (),
+            r => panic!("{:?}", r),
+        }
+    };
+
+    // A buffer upload claiming 4GB of data.
+    let mut huge = vec![4, 0];
+    huge.extend(&[0; 12]);
+    huge.extend(&u32::MAX.to_le_bytes());
+    huge.extend(&[0; 8]);
+    huge.push(1);
+    read_fails(&huge);
+
+    // Embedded data cut short.
+    let mut truncated = vec![4, 0];
+    truncated.extend(&[0; 12]);
+    truncated.extend(&16u32.to_le_bytes());
+    truncated.extend(&[0; 8]);
+    truncated.push(1);
+    truncated.extend(&[0; 4]);
+    read_fails(&truncated);
+
+    // A vector argument longer than any parameter takes, and an oversized queue.
+    let mut vector = vec![10, 0];
+    vector.extend(&[0; 8]);
+    vector.extend(&1000u32.to_le_bytes());
+    read_fails(&vector);
+    let mut queue = vec![15, 0];
+    queue.extend(&[0; 4]);
+    queue.extend(&u32::MAX.to_le_bytes());
+    read_fails(&queue);
+
+    // Well formed, but with too few elements for the parameter.
+    let entries = vec![Entry{call: Call::Listenerfv{param: alto::sys::AL_ORIENTATION, values: vec![0.0; 3]}, error: None}];
+    replay::write(&path, &entries).unwrap();
+    assert_eq!(replay::read(&path).unwrap(), entries);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(feature = "replay")]
+#[test]
+fn replay_checks_vector_lengths() {
+    use alto::replay::{self, Call, Entry, LiveBackend};
+
+    let a = load_alto();
+    let dev = a.open(None).unwrap();
+    let ctx = dev.new_context(None).unwrap();
+    let path = std::env::temp_dir().join("alto-replay-vector-lengths.bin");
+
+    let short = vec![Entry{call: Call::Listenerfv{param: alto::sys::AL_ORIENTATION, values: vec![0.0; 3]}, error: None}];
+    replay::write(&path, &short).unwrap();
+    match replay::run(&path, &mut LiveBackend::new(&ctx)) {
+        Err(AltoError::InvalidValue) => (),
+        r => panic!("{:?}", r),
+    }
+
+    let unknown = vec![Entry{call: Call::Listenerfv{param: alto::sys::AL_GAIN, values: vec![1.0]}, error: None}];
+    replay::write(&path, &unknown).unwrap();
+    match replay::run(&path, &mut LiveBackend::new(&ctx)) {
+        Err(AltoError::InvalidEnum) => (),
+        r => panic!("{:?}", r),
+    }
+
+    let full = vec![Entry{call: Call::Listenerfv{param: alto::sys::AL_ORIENTATION, values: vec![0.0, 0.0, -1.0, 0.0, 1.0, 0.0]}, error: Some(0)}];
+    replay::write(&path, &full).unwrap();
+    assert!(replay::run(&path, &mut LiveBackend::new(&ctx)).unwrap().is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}